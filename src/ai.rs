@@ -1,26 +1,67 @@
+//! The built-in AI: heuristics avoiding moves that lose right away, a search for forced wins in
+//! the endgame, and an exact solver once few spaces are left.
+
 use crate::{
     field::Pos,
-    game::{Game, Player, Status},
+    game::{Game, Phase, Player, Status, MAX_SOLVER_SPACES},
     piece::Piece,
     rng::RomuDuoJrRand,
 };
-use std::{collections::HashSet, time::Instant};
+use std::time::Instant;
+
+/// How far contempt looks for the replies that lose, see [`Game::contempt`]: to a forced win
+/// within our next two placements. Searching to the end, like the solve, would double its cost.
+const SHARPNESS_PLIES: usize = 3;
 
+/// An AI player. With [`Game::ai_reasoning`], it explains its moves, see
+/// [`SimpleAi::take_reasoning`].
 #[allow(clippy::module_name_repetitions)]
+#[derive(Debug)]
 pub struct SimpleAi {
     own_player: Player,
     rng: RomuDuoJrRand,
+    /// The explanations of the moves since they were last taken
+    reasoning: Vec<String>,
+}
+
+/// `e^x` for `x <= 0`, using basic arithmetic only. IEEE 754 rounds that the same everywhere,
+/// unlike [`f64::exp`], so the same seed gives the same games on every platform.
+fn portable_exp(x: f64) -> f64 {
+    debug_assert!(x <= 0.0);
+    // e^x = (e^(x / 2^10))^(2^10), with a Taylor series for the small exponent. Below -700,
+    // the result is 0 for all practical purposes.
+    let small = x.max(-700.0) / 1024.0;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for i in 1..=12 {
+        term *= small / f64::from(i);
+        sum += term;
+    }
+    (0..10).fold(sum, |acc, _| acc * acc)
 }
 
 impl SimpleAi {
+    /// An AI playing as `own_player`, its random choices following `seed`
     pub fn with_seed(own_player: Player, seed: u64) -> Self {
         #[allow(clippy::cast_possible_truncation)]
         Self {
             rng: RomuDuoJrRand::with_seed(seed),
             own_player,
+            reasoning: vec![],
         }
     }
 
+    /// Returns the lines explaining the moves made since the last call, and forgets them. There
+    /// are only any with [`Game::ai_reasoning`].
+    pub fn take_reasoning(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.reasoning)
+    }
+
+    /// Notes down a line explaining the current move.
+    fn reason(&mut self, line: String) {
+        self.reasoning.push(line);
+    }
+
     /// Tries to play the game iteratively, searching for a locally optimal move
     /// Strategy:
     ///     We are given a piece by the opponent, we will then calculate all states that are
@@ -44,7 +85,9 @@ impl SimpleAi {
             } => {
                 assert!(self.own_player == player);
                 if game.ai_reasoning {
-                    println!("AI: Does not matter which piece we pick on the initial move.");
+                    self.reason(
+                        "AI: Does not matter which piece we pick on the initial move.".to_string(),
+                    );
                 }
                 // return a random piece from `remaining_pieces`
                 let random_piece = *self.rng.choose(game.remaining_pieces());
@@ -56,6 +99,9 @@ impl SimpleAi {
                 next_piece: our_piece,
             } => {
                 // This is where the interesting stuff happens.
+                if game.hidden_property.is_some() {
+                    return self.play_blind(game);
+                }
 
                 let it = if game.ai_reasoning {
                     Some(Instant::now())
@@ -66,10 +112,11 @@ impl SimpleAi {
                 // Grab the empty spaces.
                 let empty_spaces = t_game.field.empty_spaces();
                 if game.ai_reasoning {
-                    println!(
+                    self.reason(format!("AI: We are in the {}", game.phase()));
+                    self.reason(format!(
                         "AI: There are {} empty spaces for us to put our piece on",
                         empty_spaces.len()
-                    );
+                    ));
                 }
 
                 let mut states: Vec<(Game, Pos)> = Vec::with_capacity(16);
@@ -102,117 +149,498 @@ impl SimpleAi {
                 }
 
                 if game.ai_reasoning {
-                    println!("AI: We have {} states for our move", states.len());
+                    self.reason(format!("AI: We have {} states for our move", states.len()));
                 }
 
-                // This tracks which states we will remove after we calculate for the adversary.
-                let mut removals = Vec::new();
+                if game.remaining_pieces().is_empty() {
+                    // This will be a draw.
+                    game.do_move(states[0].1, our_piece).unwrap();
+                    return game.clone();
+                }
 
-                // This tracks the pieces, we should not pick, i.e. they allow the opponent to win.
-                let mut non_picks = HashSet::new();
+                // This tracks, for each state, the pieces we can give without our opponent
+                // winning immediately.
+                let mut safe_picks: Vec<Vec<Piece>> = Vec::with_capacity(states.len());
 
                 // None of these states win immediately, try to check if any of the remaining
                 // pieces will let the opponent win.
-                for (state_idx, (state, _our_pos)) in states.iter().enumerate() {
-                    // This is the piece we will give to our opponent.
-                    for piece in state.remaining_pieces() {
-                        // Perform all the moves our opponent could do with this piece.
-                        for pos in state.field.empty_spaces() {
-                            // Grab a clone
-                            let mut new_state = state.clone();
-                            // Perform the move
-                            new_state
-                                .field
-                                .put(pos, *piece)
-                                .expect("huh ai should only do legal moves!");
-
-                            // Check if any of these moves are winning.
-                            if new_state.field.check_field_for_win() && game.ai_reasoning {
-                                println!("Piece: {piece:?} will let opponent win on pos {pos:?} if we place ours({our_piece:?}) on {pos:?}");
-                                // remove these states from the states vector.
-                                removals.push(state_idx);
-                                // Add the piece to the non_picks.
-                                non_picks.insert(piece);
+                for (state, our_pos) in &states {
+                    let mut state_safe_picks = Vec::new();
+                    // These are the pieces we may give to our opponent, and where they would win.
+                    for (piece, winning_spaces) in state.danger_matrix() {
+                        if let Some(pos) = winning_spaces.first() {
+                            if game.ai_reasoning {
+                                self.reason(format!("Piece: {piece:?} will let opponent win on pos {pos:?} if we place ours({our_piece:?}) on {our_pos:?}"));
                             }
+                        } else {
+                            state_safe_picks.push(piece);
                         }
                     }
+                    safe_picks.push(state_safe_picks);
                 }
 
-                let remaining_pieces = game.remaining_pieces().iter().collect::<HashSet<_>>();
                 if game.ai_reasoning {
-                    println!("AI: Game has {} remaining pieces", remaining_pieces.len());
-                    println!(
-                        "AI: We have {} pieces that we want to avoid",
-                        non_picks.len()
-                    );
+                    self.reason(format!(
+                        "AI: Game has {} remaining pieces",
+                        game.remaining_pieces().len()
+                    ));
+                    self.reason(format!("AI: calculated all states that we can put things on without our opponent immediately winning after {:.4} us", it.unwrap().elapsed().as_micros()));
                 }
 
-                let potential_picks: Vec<Piece> = remaining_pieces
-                    .difference(&non_picks)
-                    .map(|x| **x)
+                // remove the states we do not want, i.e. every piece we could give afterwards
+                // lets our opponent win.
+                let good_states: Vec<usize> = (0..states.len())
+                    .filter(|&idx| !safe_picks[idx].is_empty())
                     .collect();
 
-                if game.ai_reasoning {
-                    println!("AI: calculated all states that we can put things on without our opponent immediately winning after {:.4} us", it.unwrap().elapsed().as_micros());
-                }
-
-                // This means, our opponent will definitely win next round or it is a draw :(
-                // Just shortcut and pick any piece.
-                if potential_picks.is_empty() {
+                // Oh no! we cannot avoid a game loss here. Just return.
+                if good_states.is_empty() {
                     if game.ai_reasoning {
-                        println!("AI: Loss is imminent, just give a random piece");
-                    }
-                    if game.remaining_pieces().is_empty() {
-                        // This will be a draw.
-                        game.do_move(states[0].1, our_piece).unwrap();
-                        return game.clone();
+                        self.reason("AI: We will lose on the next move, wherever we place our piece and whichever piece we select! :<".to_string());
                     }
+                    // return a random piece from `remaining_pieces`
                     let random_piece = *self.rng.choose(game.remaining_pieces());
-                    game.do_move(states[0].1, random_piece).unwrap();
+
+                    let random_pos = self.rng.choose(game.field.empty_spaces());
+                    game.do_move(random_pos, random_piece).unwrap();
                     return game.clone();
                 }
-                //let potential_picks = Vec::from(potential_picks);
 
-                let random_potential_pick = self.rng.choose(potential_picks);
+                if game.ai_reasoning {
+                    self.reason(format!(
+                        "AI: {} of {} states leave us a safe piece to give",
+                        good_states.len(),
+                        states.len()
+                    ));
+                }
 
-                // remove the states we do not want, i.e. the next move will let our opponent win.
-                let states: Vec<(Game, Pos)> = states
+                let candidates: Vec<(Pos, Piece)> = good_states
                     .iter()
-                    .enumerate()
-                    // Get all states, which do not want to remove
-                    // This value is the idx.
-                    .filter(|(idx, _)| !removals.contains(idx))
-                    // Map to the state and pos
-                    .map(|(_, state_pos)| state_pos)
-                    .cloned()
+                    .flat_map(|&idx| {
+                        let pos = states[idx].1;
+                        safe_picks[idx].iter().map(move |&piece| (pos, piece))
+                    })
                     .collect();
-
-                // Oh no! we cannot avoid a game loss here. Just return.
-                if states.is_empty() {
+                let spaces = game.field.empty_spaces().len();
+                let solver_spaces = game.solver_spaces.min(MAX_SOLVER_SPACES);
+                if spaces <= solver_spaces {
                     if game.ai_reasoning {
-                        println!("AI: We will lose on the next move, wherever we place our piece and whichever piece we select! :<");
+                        self.reason(format!(
+                            "AI: {spaces} empty spaces, solving the game exactly"
+                        ));
                     }
-                    // return a random piece from `remaining_pieces`
-                    let random_piece = *self.rng.choose(game.remaining_pieces());
+                    if let Some((pos, piece)) = self.solve(game, &candidates) {
+                        game.do_move(pos, piece).unwrap();
+                        return game.clone();
+                    }
+                } else if game.ai_reasoning {
+                    self.reason(format!(
+                        "AI: {spaces} empty spaces, more than {solver_spaces} to solve exactly, using heuristics"
+                    ));
+                }
 
-                    let random_pos = self.rng.choose(game.field.empty_spaces());
-                    game.do_move(random_pos, random_piece).unwrap();
-                    return game.clone();
+                // In the endgame, look for a move that wins by force: our opponent has to give us
+                // a winning piece whatever they do next.
+                if game.phase() == Phase::Endgame && game.forced_win_in(3) {
+                    let forcing: Vec<(Pos, Piece)> = candidates
+                        .iter()
+                        .copied()
+                        .filter(|&(pos, piece)| {
+                            let mut next = game.clone();
+                            next.do_move(pos, piece).unwrap();
+                            next.wins_within(game.player(), 2)
+                        })
+                        .collect();
+                    if !forcing.is_empty() {
+                        if game.ai_reasoning {
+                            self.reason(format!(
+                                "AI: We can force a win, {} moves do",
+                                forcing.len()
+                            ));
+                        }
+                        let (pos, piece) = self.rng.choose(forcing);
+                        game.do_move(pos, piece).unwrap();
+                        return game.clone();
+                    }
                 }
 
-                // Pick a random state from this list for now.
-                let state = self.rng.choose(states.iter());
+                // Pick a random state from this list, and a random safe piece for it.
+                let state_idx = self.pick_state(game, &good_states, &safe_picks);
+                let random_potential_pick = *self.rng.choose(&safe_picks[state_idx]);
 
                 // Grab the best move and then construct the new game.
-                #[allow(clippy::cast_possible_truncation)]
-                game.do_move(state.1, random_potential_pick)
+                game.do_move(states[state_idx].1, random_potential_pick)
                     .expect("ai should only do legal moves!");
                 game.clone()
             }
+            Status::SwapDecision { .. } => {
+                let swap = game
+                    .hand_candidates()
+                    .into_iter()
+                    .all(|piece| Self::wants_swap(game, piece));
+                if game.ai_reasoning {
+                    if swap {
+                        self.reason(
+                            "AI: We can't place our piece safely, swapping sides.".to_string(),
+                        );
+                    } else {
+                        self.reason(
+                            "AI: We are happy with our piece, not swapping sides.".to_string(),
+                        );
+                    }
+                }
+                game.decide_swap(swap).unwrap();
+                game.clone()
+            }
             // On won and draw.
             _ => {
                 unreachable!("Game should just terminate here.");
             }
         }
     }
+
+    /// Picks the best of the `candidates` by searching the game to the end: one winning by force,
+    /// or else one the opponent can't win against. Returns `None` if all of them lose against
+    /// perfect play, leaving it to the heuristics to hope for a mistake.
+    fn solve(&mut self, game: &Game, candidates: &[(Pos, Piece)]) -> Option<(Pos, Piece)> {
+        let it = Instant::now();
+        let (player, plies) = (game.player(), game.field.empty_spaces().len());
+        let mut wins = vec![];
+        let mut draws = vec![];
+        for &(pos, piece) in candidates {
+            let mut next = game.clone();
+            next.do_move(pos, piece).unwrap();
+            if next.wins_within(player, plies) {
+                wins.push((pos, piece));
+            } else if wins.is_empty() && !next.wins_within(player.next(), plies) {
+                draws.push((pos, piece));
+            }
+        }
+        let (outcome, moves) = if wins.is_empty() {
+            ("draw", draws)
+        } else {
+            ("win", wins)
+        };
+        if game.ai_reasoning {
+            self.reason(format!(
+                "AI: Solved in {} us, {} of {} moves {outcome} with perfect play",
+                it.elapsed().as_micros(),
+                moves.len(),
+                candidates.len()
+            ));
+            if moves.is_empty() {
+                self.reason(
+                    "AI: Every move loses against perfect play, falling back to heuristics"
+                        .to_string(),
+                );
+            }
+        }
+        if moves.is_empty() {
+            return None;
+        }
+        if outcome == "draw" && game.contempt != 0.0 {
+            return Some(self.pick_draw(game, &moves));
+        }
+        Some(self.rng.choose(moves))
+    }
+
+    /// Picks one of the drawing `moves` by the contempt, see [`Game::contempt`]: each is weighted
+    /// by how sharp it is, the share of the opponent's replies that lose within
+    /// [`SHARPNESS_PLIES`].
+    fn pick_draw(&mut self, game: &Game, moves: &[(Pos, Piece)]) -> (Pos, Piece) {
+        let (player, plies) = (game.player(), game.field.empty_spaces().len());
+        let sharpness: Vec<f64> = moves
+            .iter()
+            .map(|&(pos, piece)| {
+                let mut next = game.clone();
+                next.do_move(pos, piece).unwrap();
+                Self::sharpness(&next, player, (plies - 1).min(SHARPNESS_PLIES))
+            })
+            .collect();
+        // Relative to the preferred end, to stay clear of overflows
+        let preferred = if game.contempt > 0.0 {
+            sharpness.iter().copied().fold(0.0, f64::max)
+        } else {
+            sharpness.iter().copied().fold(1.0, f64::min)
+        };
+        let weights: Vec<f64> = sharpness
+            .iter()
+            .map(|&share| portable_exp(-(share - preferred).abs() * game.contempt.abs() * 10.0))
+            .collect();
+        let idx = self.rng.choose_weighted(&weights);
+        if game.ai_reasoning {
+            self.reason(format!(
+                "AI: Contempt {}, playing a draw where {:.0}% of the replies lose",
+                game.contempt,
+                sharpness[idx] * 100.0
+            ));
+        }
+        moves[idx]
+    }
+
+    /// The share of the replies in `game` after which `player` wins within `plies` moves
+    fn sharpness(game: &Game, player: Player, plies: usize) -> f64 {
+        let replies = Self::replies(game);
+        let losing = replies
+            .iter()
+            .filter(|&&(pos, piece)| {
+                let mut after = game.clone();
+                after.do_move(pos, piece).unwrap();
+                after.wins_within(player, plies)
+            })
+            .count();
+        #[allow(clippy::cast_precision_loss)]
+        let share = losing as f64 / replies.len().max(1) as f64;
+        share
+    }
+
+    /// The moves of the player to place in `game`, each place with each piece to give
+    fn replies(game: &Game) -> Vec<(Pos, Piece)> {
+        let hand = game.next_piece().unwrap();
+        let gifts = match game.remaining_pieces() {
+            [] => vec![hand],
+            remaining => remaining.to_vec(),
+        };
+        game.field
+            .empty_spaces()
+            .into_iter()
+            .flat_map(|pos| gifts.iter().map(move |&piece| (pos, piece)))
+            .collect()
+    }
+
+    /// Picks one of the `good_states`, preferring those leaving us more safe pieces to give
+    /// depending on the temperature, see [`Game::ai_temperature`].
+    fn pick_state(
+        &mut self,
+        game: &Game,
+        good_states: &[usize],
+        safe_picks: &[Vec<Piece>],
+    ) -> usize {
+        let Some(temperature) = game.ai_temperature else {
+            return *self.rng.choose(good_states);
+        };
+        let scores: Vec<usize> = good_states
+            .iter()
+            .map(|&idx| safe_picks[idx].len())
+            .collect();
+        let best = *scores.iter().max().unwrap();
+        if temperature <= 0.0 {
+            let best_idx = scores.iter().position(|&score| score == best).unwrap();
+            return good_states[best_idx];
+        }
+        // Relative to the best score, to stay clear of overflows
+        #[allow(clippy::cast_precision_loss)]
+        let weights: Vec<f64> = scores
+            .iter()
+            .map(|&score| portable_exp((score as f64 - best as f64) / temperature))
+            .collect();
+        good_states[self.rng.choose_weighted(&weights)]
+    }
+
+    /// Plays Blind Quarto, where we don't know one property of the piece we place: we pick the
+    /// place looking only at the pieces we might be holding, and choose the piece to give once
+    /// ours is revealed on the field.
+    fn play_blind(&mut self, game: &mut Game) -> Game {
+        let candidates = game.hand_candidates();
+        let our_piece = candidates[0];
+
+        // Score every place by how many of the candidates would win there, then by how many of
+        // them leave us a safe piece to give.
+        let scored: Vec<(Pos, (usize, usize))> = game
+            .field
+            .empty_spaces()
+            .into_iter()
+            .map(|pos| {
+                let mut wins = 0;
+                let mut safe = 0;
+                for &piece in &candidates {
+                    let mut field = game.field.clone();
+                    field.put(pos, piece).unwrap();
+                    if field.check_field_for_win() {
+                        wins += 1;
+                    } else if game
+                        .remaining_pieces()
+                        .iter()
+                        .any(|&next| next != piece && field.winning_spaces(next).is_empty())
+                    {
+                        safe += 1;
+                    }
+                }
+                (pos, (wins, safe))
+            })
+            .collect();
+        let best = scored.iter().map(|&(_, score)| score).max().unwrap();
+        let best_places: Vec<Pos> = scored
+            .into_iter()
+            .filter(|&(_, score)| score == best)
+            .map(|(pos, _)| pos)
+            .collect();
+        if game.ai_reasoning {
+            self.reason(format!(
+                "AI: We might hold any of {} pieces, {} places are best for them",
+                candidates.len(),
+                best_places.len()
+            ));
+        }
+        let pos = self.rng.choose(best_places);
+
+        // Our piece is revealed now, give a safe piece if there is one.
+        let mut field = game.field.clone();
+        field.put(pos, our_piece).unwrap();
+        let safe_picks: Vec<Piece> = game
+            .remaining_pieces()
+            .iter()
+            .copied()
+            .filter(|&piece| field.winning_spaces(piece).is_empty())
+            .collect();
+        let next_piece = if !safe_picks.is_empty() {
+            self.rng.choose(safe_picks)
+        } else if game.remaining_pieces().is_empty() {
+            our_piece
+        } else {
+            *self.rng.choose(game.remaining_pieces())
+        };
+        game.do_move(pos, next_piece).unwrap();
+        game.clone()
+    }
+
+    /// With the pie rule, we swap sides if we can neither win with the piece we were given, nor
+    /// put it anywhere without handing our opponent a win.
+    fn wants_swap(game: &Game, our_piece: Piece) -> bool {
+        if !game.field.winning_spaces(our_piece).is_empty() {
+            return false;
+        }
+        !game.field.empty_spaces().into_iter().any(|pos| {
+            let mut field = game.field.clone();
+            field.put(pos, our_piece).unwrap();
+            game.remaining_pieces()
+                .iter()
+                .any(|&piece| field.winning_spaces(piece).is_empty())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::{Game, Player, MAX_SOLVER_SPACES, SEARCHED, SOLVER_SPACES},
+        generate::playout,
+        rng::RomuDuoJrRand,
+    };
+
+    use super::{portable_exp, SimpleAi, SHARPNESS_PLIES};
+
+    #[test]
+    fn test_portable_exp() {
+        for x in [0.0, -0.5, -1.0, -3.0, -20.0, -100.0] {
+            let expected: f64 = f64::exp(x);
+            assert!((portable_exp(x) - expected).abs() <= expected * 1e-6);
+        }
+        assert!(portable_exp(-1e9) < 1e-300);
+    }
+
+    #[test]
+    fn test_same_seed_same_game() {
+        let play = || {
+            let mut game = Game::new(Player::PlayerOne);
+            game.ai_temperature = Some(0.5);
+            let mut ais = [Player::PlayerOne, Player::PlayerTwo]
+                .map(|player| SimpleAi::with_seed(player, 42));
+            while game.running() {
+                game = ais[game.player().index()].play_iteratively(&mut game);
+            }
+            game.history().to_vec()
+        };
+        assert_eq!(play(), play());
+    }
+
+    #[test]
+    fn test_solver_keeps_forced_wins() {
+        let base = Game::new(Player::PlayerOne);
+        let mut rng = RomuDuoJrRand::with_seed(1337);
+        let mut checked = 0;
+        for _ in 0..200 {
+            let mut game = playout(&base, 16 - SOLVER_SPACES, &mut rng);
+            let plies = game.field.empty_spaces().len();
+            if !game.running() || !game.forced_win_in(plies) {
+                continue;
+            }
+            let player = game.player();
+            let mut ai = SimpleAi::with_seed(player, rng.next());
+            let after = ai.play_iteratively(&mut game);
+            assert!(after.wins_within(player, plies));
+            checked += 1;
+        }
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn test_contempt() {
+        let base = Game::new(Player::PlayerOne);
+        let mut rng = RomuDuoJrRand::with_seed(1337);
+        let mut sharpness = [0.0, 0.0];
+        for _ in 0..100 {
+            let game = playout(&base, 12, &mut rng);
+            let plies = game.field.empty_spaces().len();
+            let player = game.player();
+            if !game.running()
+                || game.forced_win_in(plies)
+                || game.wins_within(player.next(), plies)
+            {
+                continue;
+            }
+            let seed = rng.next();
+            for (idx, contempt) in [1.0, -1.0].into_iter().enumerate() {
+                let mut game = game.clone();
+                game.contempt = contempt;
+                let after = SimpleAi::with_seed(player, seed).play_iteratively(&mut game);
+                // Contempt never gives up the draw.
+                assert!(!after.wins_within(player.next(), plies));
+                if after.running() {
+                    sharpness[idx] +=
+                        SimpleAi::sharpness(&after, player, (plies - 1).min(SHARPNESS_PLIES));
+                }
+            }
+        }
+        assert!(sharpness[0] > sharpness[1]);
+    }
+
+    #[test]
+    fn test_contempt_cost() {
+        // At the most empty spaces the AI solves, contempt searches at most as much again as the
+        // solve itself.
+        let base = Game::new(Player::PlayerOne);
+        let mut rng = RomuDuoJrRand::with_seed(1337);
+        let mut drawn = 0;
+        while drawn < 2 {
+            let mut game = playout(&base, 16 - MAX_SOLVER_SPACES, &mut rng);
+            if !game.running() || game.field.empty_spaces().len() != MAX_SOLVER_SPACES {
+                continue;
+            }
+            game.solver_spaces = MAX_SOLVER_SPACES;
+            game.ai_reasoning = true;
+            let seed = rng.next();
+            let mut searched = [0, 0];
+            let mut contempt_applied = false;
+            for (idx, contempt) in [0.0, 1.0].into_iter().enumerate() {
+                let mut game = game.clone();
+                game.contempt = contempt;
+                let mut ai = SimpleAi::with_seed(game.player(), seed);
+                SEARCHED.with(|count| count.set(0));
+                ai.play_iteratively(&mut game);
+                searched[idx] = SEARCHED.with(std::cell::Cell::get);
+                contempt_applied = ai
+                    .take_reasoning()
+                    .iter()
+                    .any(|line| line.starts_with("AI: Contempt"));
+            }
+            if contempt_applied {
+                drawn += 1;
+                assert!(
+                    searched[1] <= 2 * searched[0],
+                    "{searched:?} positions searched without and with contempt"
+                );
+            }
+        }
+    }
 }
@@ -0,0 +1,118 @@
+//! Autosave of interactive games: after every move, the game is written to the `autosave` file
+//! of the [data dir](crate::profile::data_dir), so an interrupted game can be resumed on the next
+//! launch. Finished games remove the file.
+//!
+//! The file holds the [format version](quarto::version), the
+//! [share code](quarto::game::Game::to_share_code) of the game and the side of the human player,
+//! as `key: value` lines.
+
+use std::{fs, io, path::PathBuf};
+
+use quarto::{
+    game::{Game, Player},
+    version,
+};
+
+use crate::profile::data_dir;
+
+/// The version of the autosave format, see [`version`]
+const VERSION: u32 = 1;
+
+/// An interrupted game
+#[derive(Debug, PartialEq, Eq)]
+pub struct Autosave {
+    /// The share code of the game
+    pub code: String,
+    /// The side of the human player, the AI plays the other one unless in player vs player
+    /// mode
+    pub human: Player,
+}
+
+impl Autosave {
+    fn path() -> Option<PathBuf> {
+        Some(data_dir()?.join("autosave"))
+    }
+
+    /// Writes the game, first to a temporary file which then replaces the previous autosave, so
+    /// a crash while writing never leaves a broken file behind. Without a data dir, nothing is
+    /// saved.
+    pub fn save(game: &Game, human: Player) -> io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        let autosave = Self {
+            code: game.to_share_code(),
+            human,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, autosave.serialize())?;
+        fs::rename(tmp, path)
+    }
+
+    /// The interrupted game, if there is one
+    pub fn load() -> Result<Option<Self>, String> {
+        let Some(s) = Self::path().and_then(|path| fs::read_to_string(path).ok()) else {
+            return Ok(None);
+        };
+        Self::parse(&s).map(Some)
+    }
+
+    /// Removes the autosave, once the game is finished or the player doesn't want to resume it
+    pub fn clear() {
+        if let Some(path) = Self::path() {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    fn serialize(&self) -> String {
+        format!(
+            "version: {VERSION}\ncode: {}\nhuman: {}\n",
+            self.code,
+            self.human.index() + 1
+        )
+    }
+
+    fn parse(s: &str) -> Result<Self, String> {
+        let err = || "invalid autosave".to_string();
+        let (mut code, mut human) = (None, None);
+        for line in s.lines() {
+            match line.split_once(':').map(|(k, v)| (k, v.trim())) {
+                Some(("version", value)) => {
+                    version::check("autosave", value.parse().map_err(|_| err())?, VERSION)?;
+                }
+                Some(("code", value)) => code = Some(value.to_string()),
+                Some(("human", "1")) => human = Some(Player::PlayerOne),
+                Some(("human", "2")) => human = Some(Player::PlayerTwo),
+                _ => {}
+            }
+        }
+        Ok(Self {
+            code: code.ok_or_else(err)?,
+            human: human.ok_or_else(err)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quarto::game::{Game, Player};
+
+    use super::Autosave;
+
+    #[test]
+    fn test_autosave() {
+        let mut game = Game::new(Player::PlayerOne);
+        game.initial_move(game.remaining_pieces()[3]).unwrap();
+        let autosave = Autosave {
+            code: game.to_share_code(),
+            human: Player::PlayerTwo,
+        };
+        assert_eq!(Autosave::parse(&autosave.serialize()).unwrap(), autosave);
+        assert!(Autosave::parse("code: AQAA\n").is_err());
+        assert!(Autosave::parse("code: AQAA\nhuman: 3\n").is_err());
+        assert!(Autosave::parse("version: 2\ncode: AQAA\nhuman: 1\n").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! Statistics to judge the balance of a ruleset before adopting it (`quarto balance`): how many
+//! ways there are to win, how long games last, and how much starting the game helps, under
+//! random play and with the AI playing itself.
+//!
+//! The player starting the game gives the first piece, so the other one places first.
+
+use crate::{
+    ai::SimpleAi,
+    game::{Game, Player},
+    generate::{self, placements},
+    rng::RomuDuoJrRand,
+};
+
+/// The results of a number of games
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Sample {
+    /// The number of games played
+    pub games: u32,
+    /// The games won by the player starting them
+    pub starter_wins: u32,
+    /// The games won by the other player
+    pub second_wins: u32,
+    /// The pieces placed in all games together
+    pub placements: u32,
+}
+
+impl Sample {
+    fn record(&mut self, game: &Game) {
+        let starter = game.history_with_players()[0].0;
+        self.games += 1;
+        match game.winner() {
+            Some(winner) if winner == starter => self.starter_wins += 1,
+            Some(_) => self.second_wins += 1,
+            None => {}
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let placed = placements(game) as u32;
+        self.placements += placed;
+    }
+
+    /// The number of games that ended in a draw
+    pub fn draws(&self) -> u32 {
+        self.games - self.starter_wins - self.second_wins
+    }
+
+    /// The pieces placed per game, on average
+    pub fn average_length(&self) -> f64 {
+        f64::from(self.placements) / f64::from(self.games.max(1))
+    }
+
+    /// How much more often the starting player wins than the other one, from -1 to 1
+    pub fn first_move_advantage(&self) -> f64 {
+        (f64::from(self.starter_wins) - f64::from(self.second_wins)) / f64::from(self.games.max(1))
+    }
+
+    /// A summary, such as `1000 games, 9.8 pieces placed per game, starter 41% - 52% second, 7%
+    /// draws, first-move advantage -0.11`
+    pub fn describe(&self) -> String {
+        let percent = |n: u32| f64::from(n) * 100.0 / f64::from(self.games.max(1));
+        format!(
+            "{} games, {:.1} pieces placed per game, starter {:.0}% - {:.0}% second, {:.0}% draws, first-move advantage {:+.2}",
+            self.games,
+            self.average_length(),
+            percent(self.starter_wins),
+            percent(self.second_wins),
+            percent(self.draws()),
+            self.first_move_advantage()
+        )
+    }
+}
+
+/// The number of ways to win: each line, completed with pieces sharing one of the eight property
+/// values, such as all tall
+pub fn winning_combinations(base: &Game) -> (usize, usize) {
+    let lines = base.field.lines().len();
+    (lines, lines * 8)
+}
+
+/// Plays `games` games with random moves, with the rules of `base`.
+pub fn random_play(base: &Game, games: u32, rng: &mut RomuDuoJrRand) -> Sample {
+    let mut sample = Sample::default();
+    for _ in 0..games {
+        sample.record(&generate::playout(base, usize::MAX, rng));
+    }
+    sample
+}
+
+/// Plays `games` games of the AI against itself, with the rules of `base`, taking turns in
+/// starting.
+pub fn self_play(base: &Game, games: u32, rng: &mut RomuDuoJrRand) -> Sample {
+    let mut sample = Sample::default();
+    for i in 0..games {
+        let starter = if i % 2 == 0 {
+            Player::PlayerOne
+        } else {
+            Player::PlayerTwo
+        };
+        let mut game = base.rematch(starter);
+        let mut ais = [
+            SimpleAi::with_seed(Player::PlayerOne, rng.next()),
+            SimpleAi::with_seed(Player::PlayerTwo, rng.next()),
+        ];
+        while game.running() {
+            game = ais[game.player().index()].play_iteratively(&mut game);
+        }
+        sample.record(&game);
+    }
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::{Game, Player},
+        rng::RomuDuoJrRand,
+        rules::Ruleset,
+    };
+
+    use super::{random_play, self_play, winning_combinations};
+
+    #[test]
+    fn test_balance() {
+        let base = Game::new(Player::PlayerOne);
+        assert_eq!(winning_combinations(&base), (10, 80));
+        let square = Game::with_rules(Player::PlayerOne, &Ruleset::from_notation("s").unwrap());
+        assert_eq!(winning_combinations(&square.unwrap()), (19, 152));
+
+        let mut rng = RomuDuoJrRand::with_seed(1337);
+        let random = random_play(&base, 200, &mut rng);
+        assert_eq!(random.games, 200);
+        assert_eq!(
+            random.starter_wins + random.second_wins + random.draws(),
+            200
+        );
+        assert!((4.0..=16.0).contains(&random.average_length()));
+        assert!((-1.0..=1.0).contains(&random.first_move_advantage()));
+
+        let ai = self_play(&base, 2, &mut rng);
+        assert_eq!(ai.games, 2);
+        assert!(ai.average_length() >= 4.0);
+    }
+}
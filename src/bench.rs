@@ -0,0 +1,103 @@
+//! A benchmark of the hot path of the AI (`quarto bench`): searching positions for forced wins,
+//! as [`Game::forced_win_in`] does for the AI in the endgame and for puzzles. The workload is the
+//! same for the same seed, so timings can be compared between changes.
+//!
+//! For a flamegraph, build with `cargo build --profile profiling`, which keeps the debug symbols,
+//! and run the benchmark under a profiler, such as
+//! `perf record -g target/profiling/quarto bench`.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    game::Game,
+    generate::{self, Filters},
+    rng::RomuDuoJrRand,
+    suite::TestPosition,
+};
+
+/// How many positions the workload has, unless one is given
+pub const POSITIONS: usize = 20;
+
+/// The search of one position
+#[derive(Debug, Clone)]
+pub struct Sample {
+    /// The searched position
+    pub position: TestPosition,
+    /// If the side to move forces a win
+    pub forced_win: bool,
+    /// How long the search took
+    pub took: Duration,
+}
+
+/// The representative workload: [`POSITIONS`] positions late enough in a game to be searched,
+/// with the rules of `base`.
+pub fn workload(base: &Game, rng: &mut RomuDuoJrRand) -> Result<Vec<TestPosition>, String> {
+    let filters = Filters::parse(["plies=7-10"])?;
+    generate::generate(base, &filters, POSITIONS, rng)
+}
+
+/// Searches each of the `positions` for a forced win within `plies` moves of both players,
+/// calling `on_sample` after each.
+pub fn run(
+    positions: Vec<TestPosition>,
+    plies: usize,
+    mut on_sample: impl FnMut(&Sample),
+) -> Vec<Sample> {
+    positions
+        .into_iter()
+        .map(|position| {
+            let started = Instant::now();
+            let forced_win = position.game.forced_win_in(plies);
+            let sample = Sample {
+                position,
+                forced_win,
+                took: started.elapsed(),
+            };
+            on_sample(&sample);
+            sample
+        })
+        .collect()
+}
+
+/// A summary, such as `20 positions in 1.234s, 61.7ms per position, slowest 402.1ms, 3 forced
+/// wins`
+pub fn describe(samples: &[Sample]) -> String {
+    let total: Duration = samples.iter().map(|sample| sample.took).sum();
+    let slowest = samples.iter().map(|sample| sample.took).max();
+    let count = u32::try_from(samples.len()).unwrap().max(1);
+    format!(
+        "{} positions in {:.3}s, {:.1}ms per position, slowest {:.1}ms, {} forced wins",
+        samples.len(),
+        total.as_secs_f64(),
+        (total / count).as_secs_f64() * 1000.0,
+        slowest.unwrap_or_default().as_secs_f64() * 1000.0,
+        samples.iter().filter(|sample| sample.forced_win).count()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::{Game, Player},
+        rng::RomuDuoJrRand,
+    };
+
+    use super::{describe, run, workload, POSITIONS};
+
+    #[test]
+    fn test_bench() {
+        let base = Game::new(Player::PlayerOne);
+        let positions = workload(&base, &mut RomuDuoJrRand::with_seed(1337)).unwrap();
+        assert_eq!(positions.len(), POSITIONS);
+        let again = workload(&base, &mut RomuDuoJrRand::with_seed(1337)).unwrap();
+        assert_eq!(positions[0].to_line(), again[0].to_line());
+
+        let mut seen = 0;
+        let samples = run(positions[..3].to_vec(), 1, |_| seen += 1);
+        assert_eq!(seen, 3);
+        for sample in &samples {
+            assert_eq!(sample.forced_win, sample.position.game.forced_win_in(1));
+        }
+        assert!(describe(&samples).starts_with("3 positions in "));
+    }
+}
@@ -0,0 +1,170 @@
+//! Batch conversion between the formats games and positions are kept in (`quarto convert`),
+//! line by line, so archives of any size can be streamed through:
+//!  * `code`: [share codes](crate::share), a game per line, as printed at the end of each game,
+//!  * `moves`: a game per line, its moves in the [notation](crate::game::Move::to_notation)
+//!    separated by `;`, as in the `moves` column of [results](crate::results) files. They carry
+//!    no rules, games are read and written with the rules given by the options,
+//!  * `qpd`: a position per line, as in [suites](crate::suite).
+//!
+//! A game converts to positions as all positions in it with a piece to give or place, a position
+//! to a game as one set up from it. Positions have no moves, so they can't be written as `moves`.
+
+use crate::{
+    game::Game,
+    suite::TestPosition,
+    validate::{self, FILE_BASE},
+};
+
+/// A format to convert from or to, see the [module documentation](self)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// Share codes, a game per line
+    Code,
+    /// The moves of a game per line, separated by `;`
+    Moves,
+    /// Positions as in suites, one per line
+    Qpd,
+}
+
+impl Format {
+    /// Parses a format by its name, `code`, `moves` or `qpd`.
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "code" => Ok(Self::Code),
+            "moves" => Ok(Self::Moves),
+            "qpd" => Ok(Self::Qpd),
+            _ => Err(format!("Unknown format `{name}`, use code, moves or qpd")),
+        }
+    }
+}
+
+/// What a line holds
+enum Record {
+    Game(Game),
+    Position(TestPosition),
+}
+
+/// Reads a game or position from a line in the format `from`, with the rules of `base`.
+fn read(base: &Game, from: Format, line: &str) -> Result<Record, String> {
+    match from {
+        Format::Code => Game::from_share_code(line, base).map(Record::Game),
+        Format::Moves => {
+            let moves = line.split(';').map(ToString::to_string);
+            validate::validate(base, moves, |_, _| {})
+                .map(Record::Game)
+                .map_err(|illegal| format!("move {}: {}", illegal.line, illegal.reason))
+        }
+        Format::Qpd => TestPosition::parse(line).map(|mut position| {
+            position.game.copy_settings(base);
+            Record::Position(position)
+        }),
+    }
+}
+
+/// All positions in `game` that can be written to a suite, labeled with `label` and the ply
+fn positions(game: &Game, label: &str) -> Vec<TestPosition> {
+    (0..=game.history().len())
+        .filter_map(|ply| {
+            let mut position = game.clone();
+            position.rollback_to(ply).unwrap();
+            (position.running() && !position.is_swap_decision()).then(|| TestPosition {
+                game: position,
+                best_moves: vec![],
+                avoid_moves: vec![],
+                safe: false,
+                forced_win: None,
+                metadata: vec![("id".to_string(), format!("{label} ply {ply}"))],
+            })
+        })
+        .collect()
+}
+
+/// The moves of a game, separated by `;`
+fn moves(game: &Game) -> String {
+    game.history()
+        .iter()
+        .map(|mv| mv.to_notation(FILE_BASE))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Checks that a conversion is possible at all.
+pub fn check(from: Format, to: Format) -> Result<(), String> {
+    if from == Format::Qpd && to == Format::Moves {
+        return Err("Positions have no moves, they can't be converted to moves".to_string());
+    }
+    Ok(())
+}
+
+/// Converts the line numbered `line_number` from the format `from` to the lines of `to`, with
+/// the rules of `base`. Empty lines and `#` comments convert to nothing.
+pub fn convert_line(
+    base: &Game,
+    from: Format,
+    to: Format,
+    line: &str,
+    line_number: usize,
+) -> Result<Vec<String>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(vec![]);
+    }
+    Ok(match (read(base, from, line)?, to) {
+        (Record::Game(game), Format::Code) => vec![game.to_share_code()],
+        (Record::Game(game), Format::Moves) => vec![moves(&game)],
+        (Record::Game(game), Format::Qpd) => positions(&game, &format!("line {line_number}"))
+            .iter()
+            .map(TestPosition::to_line)
+            .collect(),
+        (Record::Position(position), Format::Code) => vec![position.game.to_share_code()],
+        (Record::Position(position), Format::Qpd) => vec![position.to_line()],
+        (Record::Position(_), Format::Moves) => unreachable!("ruled out by check"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, Player};
+
+    use super::{check, convert_line, Format};
+
+    #[test]
+    fn test_convert() {
+        let base = Game::new(Player::PlayerOne);
+        let moves = "give SDQH; put 1,1, give TDQH; put 2,1, give SDRH";
+        let code = convert_line(&base, Format::Moves, Format::Code, moves, 1).unwrap();
+        assert_eq!(code.len(), 1);
+        let back = convert_line(&base, Format::Code, Format::Moves, &code[0], 1).unwrap();
+        assert_eq!(back, [moves]);
+
+        let positions = convert_line(&base, Format::Code, Format::Qpd, &code[0], 7).unwrap();
+        assert_eq!(
+            positions,
+            [
+                "4/4/4/4 - - id \"line 7 ply 0\";",
+                "4/4/4/4 SDQH - id \"line 7 ply 1\";",
+                "SDQH3/4/4/4 TDQH - id \"line 7 ply 2\";",
+                "SDQHTDQH2/4/4/4 SDRH - id \"line 7 ply 3\";",
+            ]
+        );
+        let position = &positions[3];
+        let code = convert_line(&base, Format::Qpd, Format::Code, position, 1).unwrap();
+        let game = Game::from_share_code(&code[0], &base).unwrap();
+        assert_eq!(game.field.pieces().len(), 2);
+        assert_eq!(
+            convert_line(&base, Format::Qpd, Format::Qpd, position, 1).unwrap(),
+            [position.as_str()]
+        );
+
+        assert_eq!(
+            convert_line(&base, Format::Moves, Format::Code, "# comment", 1).unwrap(),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            convert_line(&base, Format::Moves, Format::Code, "give SDQH; put 1,1", 1).unwrap_err(),
+            "move 2: the game goes on, a piece has to be given"
+        );
+        assert!(check(Format::Qpd, Format::Moves).is_err());
+        assert!(Format::parse("fen").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Crash reports: if the game panics, the game being played, the seed and the command line are
+//! written to a file in the `crashes` directory of the [data dir](crate::profile::data_dir), so
+//! the crash can be reproduced.
+
+use std::{
+    env, fs,
+    panic::{self, PanicHookInfo},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use quarto::{game::Game, rng::time_nanos, validate::FILE_BASE};
+
+use crate::profile::data_dir;
+
+/// What is needed to reproduce a crash
+#[derive(Debug)]
+struct State {
+    /// The game being played
+    game: Option<Game>,
+    /// The seed of the RNG, in case the game doesn't have one
+    seed: Option<u64>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State {
+    game: None,
+    seed: None,
+});
+
+/// Remembers the game being played, for the crash report
+pub fn track(game: &Game) {
+    if let Ok(mut state) = STATE.lock() {
+        state.game = Some(game.clone());
+    }
+}
+
+/// Remembers the seed in use, for the crash report
+pub fn track_seed(seed: u64) {
+    if let Ok(mut state) = STATE.lock() {
+        state.seed = Some(seed);
+    }
+}
+
+/// Writes a crash report whenever the game panics, after the usual panic message.
+pub fn install_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        match write_report(info) {
+            Ok(path) => eprintln!(
+                "A crash report was written to {}, please attach it when reporting this bug.",
+                path.display()
+            ),
+            Err(err) => eprintln!("Could not write a crash report: {err}"),
+        }
+    }));
+}
+
+fn write_report(info: &PanicHookInfo) -> Result<PathBuf, String> {
+    // The panic might have happened while the state was locked.
+    let state = STATE.try_lock().map_err(|err| err.to_string())?;
+    let location = info
+        .location()
+        .map_or_else(String::new, |location| format!(" at {location}"));
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_default();
+    let args: Vec<String> = env::args().collect();
+    let report = report(&format!("{message}{location}"), &args, &state);
+
+    let dir = data_dir()
+        .ok_or("could not determine the data directory")?
+        .join("crashes");
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    let path = dir.join(format!("crash-{}.txt", time_nanos() / 1_000_000));
+    fs::write(&path, report).map_err(|err| err.to_string())?;
+    Ok(path)
+}
+
+/// The contents of a crash report
+fn report(message: &str, args: &[String], state: &State) -> String {
+    let mut lines = vec![
+        format!("quarto {} crashed: {message}", env!("CARGO_PKG_VERSION")),
+        format!("Command line: {}", args.join(" ")),
+    ];
+    if let Some(seed) = state
+        .game
+        .as_ref()
+        .and_then(|game| game.seed)
+        .or(state.seed)
+    {
+        lines.push(format!("Seed: {seed}"));
+    }
+    if let Some(game) = &state.game {
+        lines.push(format!("Rules: {}", game.rules().notation()));
+        lines.push(format!("Position: {:032x}", game.pack()));
+        lines.push(format!("Status: {:?}", game.status));
+        let moves: Vec<String> = game
+            .history()
+            .iter()
+            .map(|mv| mv.to_notation(FILE_BASE))
+            .collect();
+        lines.push(format!("Moves: {}", moves.join("; ")));
+        lines.push(format!("Share code: {}", game.to_share_code()));
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use quarto::game::{Game, Player};
+
+    use super::{report, State};
+
+    #[test]
+    fn test_report() {
+        let mut game = Game::new(Player::PlayerOne);
+        game.initial_move(game.remaining_pieces()[0]).unwrap();
+        let state = State {
+            game: Some(game.clone()),
+            seed: Some(42),
+        };
+        let text = report("oops", &["quarto".into(), "-q".into()], &state);
+        assert!(text.contains("crashed: oops\nCommand line: quarto -q\nSeed: 42\n"));
+        assert!(text.contains("Moves: give SDQH\n"));
+        assert!(text.contains(&format!("Share code: {}\n", game.to_share_code())));
+
+        let state = State {
+            game: None,
+            seed: None,
+        };
+        assert_eq!(report("oops", &[], &state).lines().count(), 2);
+    }
+}
@@ -0,0 +1,147 @@
+//! Dictating a physical board (`quarto import dictate`), for those who would rather answer
+//! questions than type a grid: each space is asked for in turn, row by row, then the piece to
+//! place, and the board is read back for confirmation before the game goes on.
+//!
+//! Each space is answered with a piece [code](Piece::code), such as `TLRF`, or `.` if it is
+//! empty. `back` asks for the previous space again, and `done` leaves the remaining spaces
+//! empty.
+
+use quarto::{
+    field::{Field, Pos},
+    game::Game,
+    piece::Piece,
+};
+
+/// The spaces in the order they are asked for, row by row
+fn spaces() -> Vec<Pos> {
+    (0..Field::SIZE)
+        .flat_map(|y| (0..Field::SIZE).map(move |x| (x, y)))
+        .collect()
+}
+
+/// Asks for the pieces on the spaces, returns them in the order of [`spaces`].
+fn ask_spaces(
+    base: &Game,
+    next_line: &mut impl FnMut() -> Option<String>,
+) -> Result<Vec<Option<Piece>>, String> {
+    let spaces = spaces();
+    let mut pieces: Vec<Option<Piece>> = vec![];
+    while pieces.len() < spaces.len() {
+        let (x, y) = spaces[pieces.len()];
+        let (x, y) = (base.array_base.based(x), base.array_base.based(y));
+        println!("Space {x},{y}? (a code such as TLRF, . for empty, back or done)");
+        let answer = next_line().ok_or("No more input")?;
+        match answer.trim() {
+            "back" => {
+                pieces.pop();
+            }
+            "done" => pieces.resize(spaces.len(), None),
+            "." | "empty" => pieces.push(None),
+            code => match Piece::from_code(code) {
+                Ok(piece) => {
+                    if let Some(idx) = pieces.iter().position(|&p| p == Some(piece)) {
+                        let (x, y) = spaces[idx];
+                        let (x, y) = (base.array_base.based(x), base.array_base.based(y));
+                        println!("The piece {code} is already on {x},{y}.");
+                    } else {
+                        pieces.push(Some(piece));
+                    }
+                }
+                Err(()) => println!("`{code}` is not a piece code, such as TLRF."),
+            },
+        }
+    }
+    Ok(pieces)
+}
+
+/// Asks for the piece to place, one not on `field`.
+fn ask_hand(
+    field: &Field,
+    next_line: &mut impl FnMut() -> Option<String>,
+) -> Result<Piece, String> {
+    loop {
+        println!("Which piece is to be placed next? (a code such as TLRF)");
+        let answer = next_line().ok_or("No more input")?;
+        let code = answer.trim();
+        match Piece::from_code(code) {
+            Ok(piece) if field.pieces().contains(&piece) => {
+                println!("The piece {code} is already on the board.");
+            }
+            Ok(piece) => return Ok(piece),
+            Err(()) => println!("`{code}` is not a piece code, such as TLRF."),
+        }
+    }
+}
+
+/// Asks for the board and the piece to place, with the rules of `base`, reading the answers
+/// with `next_line`, until they are confirmed. Without any piece on the board, there is no piece
+/// to place yet.
+pub fn dictate(
+    base: &Game,
+    mut next_line: impl FnMut() -> Option<String>,
+) -> Result<(Field, Option<Piece>), String> {
+    loop {
+        let mut field = Field::new();
+        for (pos, piece) in spaces().into_iter().zip(ask_spaces(base, &mut next_line)?) {
+            if let Some(piece) = piece {
+                field.put(pos, piece).unwrap();
+            }
+        }
+        let hand = if field.pieces().is_empty() {
+            None
+        } else {
+            Some(ask_hand(&field, &mut next_line)?)
+        };
+
+        println!("The board:");
+        print!("{}", field.render(base.render_style()));
+        if let Some(hand) = hand {
+            println!("To place: {} ({})", hand.render(base.glyphs), hand.code());
+        }
+        println!("Is this right? [Y/n]");
+        let answer = next_line().ok_or("No more input")?;
+        if !answer.trim().eq_ignore_ascii_case("n") {
+            return Ok((field, hand));
+        }
+        println!("Let's start over.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quarto::{
+        game::{Game, Player},
+        piece::Piece,
+    };
+
+    use super::dictate;
+
+    fn answers(s: &str) -> impl FnMut() -> Option<String> {
+        let mut lines = s
+            .lines()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .into_iter();
+        move || lines.next()
+    }
+
+    #[test]
+    fn test_dictate() {
+        let base = Game::new(Player::PlayerOne);
+        // A typo, a piece given twice, a space redone, then a wrong board started over.
+        let (field, hand) = dictate(
+            &base,
+            answers("TLRX\nTLRF\nTLRF\n.\nback\nSDQH\ndone\nTLRF\nSLRH\nn\ndone\ny"),
+        )
+        .unwrap();
+        assert_eq!(field.pieces().len(), 0);
+        assert_eq!(hand, None);
+
+        let (field, hand) = dictate(&base, answers(". \nTLRF\ndone\nSLRH\n\n")).unwrap();
+        assert_eq!(field.get((1, 0)), Piece::from_code("TLRF").ok());
+        assert_eq!(field.pieces().len(), 1);
+        assert_eq!(hand, Piece::from_code("SLRH").ok());
+
+        assert!(dictate(&base, answers("TLRF\n")).is_err());
+    }
+}
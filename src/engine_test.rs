@@ -0,0 +1,134 @@
+//! The engine self-test (`quarto engine-test`): runs the AI against curated tactical positions
+//! and reports pass/fail with timing, as a regression suite for engine changes.
+
+use std::time::{Duration, Instant};
+
+use quarto::{
+    ai::SimpleAi,
+    suite::{parse_suite, TestPosition},
+};
+
+/// The built-in suite, see [`quarto::suite`] for the format.
+const BUILTIN_SUITE: &str = include_str!("../suites/tactics.qpd");
+
+/// Every position is played with each of these seeds, as the AI picks randomly among moves it
+/// considers equal.
+const SEEDS: [u64; 8] = [1, 2, 3, 4, 1337, 31337, 0xdead_beef, 0x1234_5678_9abc];
+
+/// The positions of the built-in suite
+pub fn builtin_suite() -> Vec<TestPosition> {
+    parse_suite(BUILTIN_SUITE).expect("the built-in suite should be valid")
+}
+
+/// Lets the engine play a position once, returns `true` if the move passes the position's
+/// expectations.
+fn passes(position: &TestPosition, seed: u64) -> bool {
+    let mut game = position.game.clone();
+    let mut ai = SimpleAi::with_seed(game.player(), seed);
+    let result = ai.play_iteratively(&mut game);
+    for line in ai.take_reasoning() {
+        println!("{line}");
+    }
+    position.check(&result)
+}
+
+/// The result of a single test position
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub id: String,
+    /// The seeds the engine failed with
+    pub failed_seeds: Vec<u64>,
+    pub elapsed: Duration,
+}
+
+/// Runs all given positions with all seeds
+pub fn run(positions: &[TestPosition]) -> Vec<TestResult> {
+    positions
+        .iter()
+        .map(|position| {
+            let it = Instant::now();
+            let failed_seeds = SEEDS
+                .into_iter()
+                .filter(|&seed| !passes(position, seed))
+                .collect();
+            TestResult {
+                id: position.id().to_string(),
+                failed_seeds,
+                elapsed: it.elapsed(),
+            }
+        })
+        .collect()
+}
+
+/// Runs the given suite and prints a report, returns `true` if all positions passed.
+pub fn run_and_report(positions: &[TestPosition]) -> bool {
+    println!(
+        "Running {} positions, {} seeds each",
+        positions.len(),
+        SEEDS.len()
+    );
+    let it = Instant::now();
+    let results = run(positions);
+    for (position, result) in positions.iter().zip(&results) {
+        let verdict = if result.failed_seeds.is_empty() {
+            "PASS"
+        } else {
+            "FAIL"
+        };
+        println!(
+            "  {verdict}  {:<30} {:>9.3} ms",
+            result.id,
+            result.elapsed.as_secs_f64() * 1000.
+        );
+        if !result.failed_seeds.is_empty() {
+            println!("        failed with seeds {:?}", result.failed_seeds);
+            println!("        {}", position.to_line());
+        }
+    }
+    let passed = results.iter().filter(|r| r.failed_seeds.is_empty()).count();
+    println!(
+        "Passed {passed}/{} positions in {:.3} ms",
+        results.len(),
+        it.elapsed().as_secs_f64() * 1000.
+    );
+    passed == results.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{builtin_suite, run};
+
+    #[test]
+    fn test_positions_valid() {
+        for position in builtin_suite() {
+            let game = &position.game;
+            if let Some(plies) = position.forced_win {
+                assert!(game.forced_win_in(plies), "{}", position.id());
+                continue;
+            }
+            let hand = game.next_piece().unwrap();
+            let winning_spaces = game.field.winning_spaces(hand);
+            // Positions either test winning, or test safety, where no win is possible.
+            assert_eq!(
+                winning_spaces.is_empty(),
+                position.safe,
+                "{}",
+                position.id()
+            );
+            for &pos in &winning_spaces {
+                assert!(
+                    position.best_moves.iter().any(|mv| mv.matches(pos, None)),
+                    "{}",
+                    position.id()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_engine_passes_suite() {
+        for result in run(&builtin_suite()) {
+            assert!(result.failed_seeds.is_empty(), "{}", result.id);
+        }
+    }
+}
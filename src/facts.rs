@@ -0,0 +1,70 @@
+//! Known game-theoretic results (`quarto facts`): what a game ends in with perfect play from
+//! both sides, for the rulesets where that is known.
+
+use crate::rules::Ruleset;
+
+/// A known result for a ruleset
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Fact {
+    /// The rules, see [`crate::rules`] for the notation
+    pub rules: &'static str,
+    /// The result with perfect play from both sides
+    pub value: &'static str,
+    /// Where the result comes from
+    pub source: &'static str,
+}
+
+/// All known results. Only add results that have been established by an exhaustive search.
+const FACTS: [Fact; 1] = [Fact {
+    rules: "-",
+    value: "a draw",
+    source: "solved by Luc Goossens, 1998",
+}];
+
+/// The known result for the given rules, if any
+pub fn lookup(rules: &Ruleset) -> Option<Fact> {
+    FACTS.into_iter().find(|fact| {
+        Ruleset::from_notation(fact.rules)
+            .expect("the known results should have valid rules")
+            .same_as(rules)
+    })
+}
+
+/// What is known about the given rules, such as `Rules -: a draw with perfect play (...)`
+pub fn describe(rules: &Ruleset) -> String {
+    match lookup(rules) {
+        Some(fact) => format!(
+            "Rules {}: {} with perfect play ({})",
+            rules.notation(),
+            fact.value,
+            fact.source
+        ),
+        None => format!(
+            "Rules {}: the result with perfect play is not known",
+            rules.notation()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rules::Ruleset;
+
+    use super::{lookup, FACTS};
+
+    #[test]
+    fn test_facts_valid() {
+        for fact in FACTS {
+            let rules = Ruleset::from_notation(fact.rules).unwrap();
+            rules.validate().unwrap();
+            assert_eq!(rules.notation(), fact.rules);
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup(&Ruleset::default()).unwrap().value, "a draw");
+        assert!(lookup(&Ruleset::from_notation("s").unwrap()).is_none());
+        assert!(lookup(&Ruleset::from_notation("pie").unwrap()).is_none());
+    }
+}
@@ -1,15 +1,59 @@
-use crate::{game::ArrayBase, piece::Piece};
+//! The field: the pieces put on it, the lines and patterns that win, and rendering it.
 
+use std::{fmt::Write, sync::Arc};
+
+use crate::{
+    game::ArrayBase,
+    pattern::WinPattern,
+    piece::{GlyphSet, Piece},
+};
+
+/// The 4x4 field, with the pieces put on it and the patterns that win on it
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Field {
     /// The field of a quarto game.
     field: [[Option<Piece>; Self::SIZE]; Self::SIZE],
-    /// If true, squares are counted as winning condition.
-    pub square_mode: bool,
+    /// Winning patterns besides rows, columns and diagonals, shared between clones.
+    patterns: Arc<[WinPattern]>,
 }
 
+/// A space on the field, as (x, y), 0-based
 pub type Pos = (usize, usize);
 
+/// How to render a field, see [`Field::render`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RenderStyle {
+    /// Where the row and column numbers start
+    pub array_base: ArrayBase,
+    /// If true, the field is seen from the other side, with the last row on top. The rows keep
+    /// their numbers.
+    pub flipped: bool,
+    /// How pieces are drawn, the cells are as wide as the pieces
+    pub glyphs: GlyphSet,
+}
+
+impl RenderStyle {
+    /// The default style, with the given base
+    pub fn new(array_base: ArrayBase) -> Self {
+        Self {
+            array_base,
+            flipped: false,
+            glyphs: GlyphSet::Emoji,
+        }
+    }
+}
+
+/// A space that differs between two fields, see [`Field::diff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellChange {
+    /// The space
+    pub pos: Pos,
+    /// The piece on the space before the change
+    pub before: Option<Piece>,
+    /// The piece on the space after the change
+    pub after: Option<Piece>,
+}
+
 /// Tries to parse a "x,y" str to Pos
 pub fn try_parse_pos(s: &str) -> Result<Pos, ()> {
     let parts: Vec<&str> = s.trim().split(',').collect();
@@ -22,12 +66,56 @@ pub fn try_parse_pos(s: &str) -> Result<Pos, ()> {
 }
 
 impl Field {
+    /// The width and height of the field
     pub const SIZE: usize = 4;
 
+    /// An empty field, winning on rows, columns and diagonals
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// An empty field, with the same winning patterns as this one
+    pub fn emptied(&self) -> Self {
+        Self {
+            patterns: self.patterns.clone(),
+            ..Self::new()
+        }
+    }
+
+    /// The winning patterns besides rows, columns and diagonals
+    pub fn patterns(&self) -> &[WinPattern] {
+        &self.patterns
+    }
+
+    /// Adds a winning pattern, unless it's already there.
+    pub fn add_pattern(&mut self, pattern: WinPattern) {
+        if !self.patterns.contains(&pattern) {
+            self.patterns = self.patterns.iter().cloned().chain([pattern]).collect();
+        }
+    }
+
+    /// If true, squares are counted as winning condition.
+    #[cfg(test)]
+    pub fn square_mode(&self) -> bool {
+        self.patterns.contains(&WinPattern::square())
+    }
+
+    /// Turns square mode, winning on 2x2 squares too, on or off.
+    pub fn set_square_mode(&mut self, square_mode: bool) {
+        if square_mode {
+            self.add_pattern(WinPattern::square());
+        } else {
+            let square = WinPattern::square();
+            self.patterns = self
+                .patterns
+                .iter()
+                .filter(|&pattern| *pattern != square)
+                .cloned()
+                .collect();
+        }
+    }
+
+    /// Puts `piece` on the empty space `pos`, fails if it's taken or outside the field.
     pub fn put(&mut self, pos: Pos, piece: Piece) -> Result<(), ()> {
         if self.field[pos.1][pos.0].is_none() {
             self.field[pos.1][pos.0] = Some(piece);
@@ -36,21 +124,177 @@ impl Field {
         Err(())
     }
 
-    #[cfg(test)]
+    /// Parses a field from four lines of four whitespace separated cells, each being either a
+    /// piece [`code`](Piece::code) or `.` or `-` for an empty space. The first line is the top
+    /// row.
+    pub fn parse_grid(s: &str) -> Result<Self, ()> {
+        let mut field = Self::new();
+        let rows: Vec<&str> = s.lines().filter(|l| !l.trim().is_empty()).collect();
+        if rows.len() != Self::SIZE {
+            return Err(());
+        }
+        for (y, row) in rows.iter().enumerate() {
+            let cells: Vec<&str> = row.split_whitespace().collect();
+            if cells.len() != Self::SIZE {
+                return Err(());
+            }
+            for (x, cell) in cells.iter().enumerate() {
+                if *cell != "." && *cell != "-" {
+                    field.put((x, y), Piece::from_code(cell)?)?;
+                }
+            }
+        }
+        Ok(field)
+    }
+
+    /// Parses a field like [`Field::parse_grid`], as typed from a physical board: the grid may
+    /// have a header line with the column numbers, and start each row with its number, as
+    /// [rendered](Field::render). The numbers follow `array_base`, and may be in any order, such as
+    /// `4 3 2 1` for a board seen from the other side.
+    pub fn parse_labeled_grid(s: &str, array_base: ArrayBase) -> Result<Self, String> {
+        let label =
+            |token: &str| (0..Self::SIZE).find(|&i| array_base.based(i).to_string() == token);
+        // The order in which the labels appear, if all of them are there
+        let order = |labels: &[Option<usize>]| {
+            let order: Option<Vec<usize>> = labels.iter().copied().collect();
+            order.filter(|order| {
+                order.len() == Self::SIZE && (0..Self::SIZE).all(|i| order.contains(&i))
+            })
+        };
+
+        let mut rows: Vec<Vec<&str>> = s
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|cells| !cells.is_empty())
+            .collect();
+        let columns = rows
+            .first()
+            .and_then(|header| order(&header.iter().map(|&t| label(t)).collect::<Vec<_>>()));
+        if columns.is_some() {
+            rows.remove(0);
+        }
+        let row_labels: Vec<Option<usize>> = rows
+            .iter()
+            .map(|row| {
+                (row.len() == Self::SIZE + 1)
+                    .then(|| label(row[0]))
+                    .flatten()
+            })
+            .collect();
+        let row_order = order(&row_labels);
+        if row_order.is_some() {
+            for row in &mut rows {
+                row.remove(0);
+            }
+        } else if row_labels.iter().any(Option::is_some) {
+            return Err("the row numbers should each be given once".to_string());
+        }
+        if rows.len() != Self::SIZE || rows.iter().any(|row| row.len() != Self::SIZE) {
+            return Err(format!("expected {0} rows of {0} spaces", Self::SIZE));
+        }
+
+        let identity: Vec<usize> = (0..Self::SIZE).collect();
+        let (columns, row_order) = (
+            columns.unwrap_or_else(|| identity.clone()),
+            row_order.unwrap_or(identity),
+        );
+        let mut grid = vec![vec!["."; Self::SIZE]; Self::SIZE];
+        for (row, &y) in rows.iter().zip(&row_order) {
+            for (cell, &x) in row.iter().zip(&columns) {
+                grid[y][x] = cell;
+            }
+        }
+        let grid: Vec<String> = grid.iter().map(|row| row.join(" ")).collect();
+        Self::parse_grid(&grid.join("\n")).map_err(|()| "invalid piece code".to_string())
+    }
+
+    /// Returns all pieces currently on the field
+    pub fn pieces(&self) -> Vec<Piece> {
+        self.field.iter().flatten().flatten().copied().collect()
+    }
+
+    /// Returns all empty spaces where putting `piece` would fulfill the win condition
+    pub fn winning_spaces(&self, piece: Piece) -> Vec<Pos> {
+        self.empty_spaces()
+            .into_iter()
+            .filter(|&pos| {
+                let mut field = self.clone();
+                field.put(pos, piece).unwrap();
+                field.check_field_for_win()
+            })
+            .collect()
+    }
+
+    /// The piece on `pos`, if any
     pub fn get(&self, pos: Pos) -> Option<Piece> {
         self.field[pos.1][pos.0]
     }
 
     /// Clear at a position, returning the current piece at this point
-    #[cfg(test)]
     pub fn clear(&mut self, pos: Pos) -> Option<Piece> {
         let ret = self.get(pos);
         self.field[pos.1][pos.0] = None;
         ret
     }
 
-    /// Checks if the win condition on this field is fulfilled.
+    /// The spaces that differ from `other`, row by row, so [`Field::apply_patch`] turns this
+    /// field into `other`. The winning patterns are not compared.
+    pub fn diff(&self, other: &Field) -> Vec<CellChange> {
+        (0..Self::SIZE)
+            .flat_map(|y| (0..Self::SIZE).map(move |x| (x, y)))
+            .filter(|&pos| self.get(pos) != other.get(pos))
+            .map(|pos| CellChange {
+                pos,
+                before: self.get(pos),
+                after: other.get(pos),
+            })
+            .collect()
+    }
+
+    /// Applies the changes of a [`Field::diff`]. Fails without changing anything if a space
+    /// doesn't hold the piece it held `before` the change.
+    pub fn apply_patch(&mut self, changes: &[CellChange]) -> Result<(), ()> {
+        if changes
+            .iter()
+            .any(|change| self.get(change.pos) != change.before)
+        {
+            return Err(());
+        }
+        for change in changes {
+            self.field[change.pos.1][change.pos.0] = change.after;
+        }
+        Ok(())
+    }
+
+    /// Checks if the win condition on this field is fulfilled. Debug builds also check it is
+    /// [verified](Field::verified_win), so tests check every win detection they do.
     pub fn check_field_for_win(&self) -> bool {
+        let won = self.scan_for_win();
+        debug_assert_eq!(self.verified_win(), Ok(won));
+        won
+    }
+
+    /// Checks for a win like [`Field::check_field_for_win`], verified against a second scan of
+    /// all [lines](Field::lines), which compares only the set properties of the pieces, not the
+    /// complemented bits the first one relies on. Fails with the field if they disagree.
+    pub fn verified_win(&self) -> Result<bool, String> {
+        let won = self.scan_for_win();
+        if won == self.rescan_for_win() {
+            Ok(won)
+        } else {
+            Err(format!(
+                "the win checks disagree on this field:\n{}",
+                self.render(RenderStyle {
+                    array_base: ArrayBase::One,
+                    flipped: false,
+                    glyphs: GlyphSet::Ascii,
+                })
+            ))
+        }
+    }
+
+    /// The win check itself, going over rows, columns and diagonals by index
+    fn scan_for_win(&self) -> bool {
         for row in &self.field {
             if Self::check_array_for_win(row) {
                 return true;
@@ -76,21 +320,10 @@ impl Field {
             return true;
         }
 
-        if self.square_mode {
-            for i in 0..(Self::SIZE - 1) {
-                let mut flattened_square = [None; 4];
-                for k in 0..(Self::SIZE - 1) {
-                    //for l in 0..2 {
-                    //flattened_square[l] = self.field[i][k + l]
-                    //}
-                    flattened_square[..2].copy_from_slice(&self.field[i][k..(2 + k)]);
-                    //for l in 0..2 {
-                    //flattened_square[l + 2] = self.field[i + 1][k + l]
-                    //}
-                    flattened_square[2..(2 + 2)].copy_from_slice(&self.field[i + 1][k..(2 + k)]);
-                    if Self::check_array_for_win(&flattened_square) {
-                        return true;
-                    }
+        for pattern in self.patterns.iter() {
+            for placement in pattern.placements() {
+                if Self::check_array_for_win(&placement.map(|pos| self.get(pos))) {
+                    return true;
                 }
             }
         }
@@ -98,11 +331,94 @@ impl Field {
         false
     }
 
+    /// The second win check, for [`Field::verified_win`]: independent of [`Piece::properties`] holding the
+    /// unset properties in its high bits, it takes the set properties of the four pieces of each
+    /// line, the low bits, and checks whether all of them or none of them have a property.
+    fn rescan_for_win(&self) -> bool {
+        self.lines().iter().any(|line| {
+            let Some(pieces) = line
+                .iter()
+                .map(|&pos| self.get(pos).map(Piece::index))
+                .collect::<Option<Vec<u8>>>()
+            else {
+                return false;
+            };
+            let all_have = pieces.iter().fold(0x0F, |acc, &set| acc & set);
+            let some_have = pieces.iter().fold(0, |acc, &set| acc | set);
+            all_have | (some_have ^ 0x0F) != 0
+        })
+    }
+
+    /// Returns all lines (and pattern placements, such as squares) that can fulfill the win condition
+    pub fn lines(&self) -> Vec<[Pos; 4]> {
+        let mut ret: Vec<[Pos; 4]> = (0..Self::SIZE)
+            .flat_map(|i| {
+                [
+                    [(0, i), (1, i), (2, i), (3, i)],
+                    [(i, 0), (i, 1), (i, 2), (i, 3)],
+                ]
+            })
+            .collect();
+        ret.push([(0, 0), (1, 1), (2, 2), (3, 3)]);
+        ret.push([(3, 0), (2, 1), (1, 2), (0, 3)]);
+        for pattern in self.patterns.iter() {
+            ret.extend_from_slice(pattern.placements());
+        }
+        ret
+    }
+
+    /// Returns all lines currently fulfilling the win condition
+    pub fn completed_lines(&self) -> Vec<[Pos; 4]> {
+        self.lines()
+            .into_iter()
+            .filter(|line| Self::check_array_for_win(&line.map(|pos| self.get(pos))))
+            .collect()
+    }
+
+    /// Returns all lines one piece short of fulfilling the win condition: three pieces sharing a
+    /// property, and an empty space
+    pub fn threatened_lines(&self) -> Vec<[Pos; 4]> {
+        self.lines()
+            .into_iter()
+            .filter(|line| {
+                line.iter().filter(|&&pos| self.get(pos).is_some()).count() == 3
+                    && self.line_property_intersection(line) != 0
+            })
+            .collect()
+    }
+
+    /// The property values all pieces in `line` share, as a mask like [`Piece::properties`], see
+    /// [`Piece::mask_values`]. Empty spaces are skipped, a line without pieces shares everything.
+    pub fn line_property_intersection(&self, line: &[Pos; 4]) -> u8 {
+        line.iter()
+            .filter_map(|&pos| self.get(pos))
+            .fold(u8::MAX, |shared, piece| shared & piece.properties)
+    }
+
+    /// Returns all lines that can't be completed with pieces sharing a property anymore, given the
+    /// pieces already in them and `pool`, the pieces not placed yet.
+    pub fn dead_lines(&self, pool: &[Piece]) -> Vec<[Pos; 4]> {
+        // How many pieces of the pool have each of the eight property bits
+        let counts: [usize; 8] = std::array::from_fn(|bit| {
+            pool.iter()
+                .filter(|piece| piece.properties & (1 << bit) != 0)
+                .count()
+        });
+        self.lines()
+            .into_iter()
+            .filter(|line| {
+                let empty = line.iter().filter(|&&pos| self.get(pos).is_none()).count();
+                let shared = self.line_property_intersection(line);
+                (0..8).all(|bit| shared & (1 << bit) == 0 || counts[bit] < empty)
+            })
+            .collect()
+    }
+
     // Associated helper function to determine if a given line of pieces fulfills a win condition
     fn check_array_for_win(ary: &[Option<Piece>]) -> bool {
         assert!(ary.len() == 4);
 
-        let mut ret = std::u8::MAX;
+        let mut ret = u8::MAX;
 
         for piece in ary {
             if let Some(piece) = piece {
@@ -115,6 +431,7 @@ impl Field {
         ret != 0
     }
 
+    /// All empty spaces, column by column
     pub fn empty_spaces(&self) -> Vec<Pos> {
         let mut ret = Vec::with_capacity(16);
 
@@ -130,46 +447,68 @@ impl Field {
     }
 
     /// Render the field in multiple lines
-    pub fn pp(&self, array_base: ArrayBase) {
-        for (y, row) in self.field.iter().enumerate() {
-            for (x, val) in (row).iter().enumerate() {
-                if x == 0 {
-                    if y > 0 {
-                        println!();
-                        println!("  > ---------- + ---------- + ---------- + ---------- <");
-                    } else {
-                        if array_base == ArrayBase::Zero {
-                            println!("        0            1            2            3       ");
-                        } else {
-                            println!("        1            2            3            4       ");
-                        }
-                        println!("  . ---------- . ---------- . ---------- . ---------- .");
-                    }
-                    let based_y = array_base.based(y);
-                    print!("{based_y} | ");
-                } else if x < Self::SIZE {
-                    print!(" | ");
-                }
+    pub fn render(&self, style: RenderStyle) -> String {
+        self.render_highlighted(style, &[])
+    }
+
+    /// Render the field like [`Field::render`], marking the `highlighted` spaces with `*`.
+    pub fn render_highlighted(&self, style: RenderStyle, highlighted: &[Pos]) -> String {
+        // A cell holds a piece and a mark on either side.
+        let width = style.glyphs.width() + 2;
+        let border = |left: char, mid: char, right: char| {
+            let mut line = format!("  {left}");
+            for x in 0..Self::SIZE {
+                let sep = if x == Self::SIZE - 1 { right } else { mid };
+                write!(line, " {} {sep}", "-".repeat(width - 2)).unwrap();
+            }
+            line.push('\n');
+            line
+        };
+        let mut header = "  ".to_string();
+        for x in 0..Self::SIZE {
+            write!(header, " {:^width$}", style.array_base.based(x)).unwrap();
+        }
+        let mut ret = format!("{header:<0$}\n", 3 + Self::SIZE * (width + 1));
+        ret.push_str(&border('.', '.', '.'));
+
+        let mut rows: Vec<usize> = (0..Self::SIZE).collect();
+        if style.flipped {
+            rows.reverse();
+        }
+        for (i, &y) in rows.iter().enumerate() {
+            if i > 0 {
+                ret.push_str(&border('>', '+', '<'));
+            }
+            write!(ret, "{} |", style.array_base.based(y)).unwrap();
+            for (x, val) in self.field[y].iter().enumerate() {
+                let mark = if highlighted.contains(&(x, y)) {
+                    '*'
+                } else {
+                    ' '
+                };
+                ret.push(mark);
                 if let Some(val) = val {
-                    val.pp();
+                    ret.push_str(&val.render(style.glyphs));
                 } else {
-                    print!("          ");
-                }
-                if x == Self::SIZE - 1 {
-                    print!(" |");
+                    ret.push_str(&" ".repeat(width - 2));
                 }
+                ret.push(mark);
+                ret.push('|');
             }
+            ret.push('\n');
         }
-        println!();
-        println!("  ^ ---------- ^ ---------- ^ ---------- ^ ---------- ^");
+        ret.push_str(&border('^', '^', '^'));
+        ret
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        field::Field,
-        piece::{Piece, Property},
+        field::{CellChange, Field, RenderStyle},
+        game::ArrayBase,
+        pattern::WinPattern,
+        piece::{GlyphSet, Piece, Property},
     };
     const TEST_LIGHT_TALL: Piece = Piece::with_props(Property::Tall as u8 | Property::Light as u8);
     const TEST_DARK_SHORT: Piece = Piece::with_props(0);
@@ -179,7 +518,7 @@ mod tests {
     #[test]
     fn test_squares() {
         let mut field = Field::new();
-        field.square_mode = true;
+        field.set_square_mode(true);
 
         field.put((0, 0), TEST_LIGHT_TALL).unwrap();
         field.put((0, 1), TEST_LIGHT_TALL).unwrap();
@@ -195,7 +534,7 @@ mod tests {
     #[test]
     fn test_wrong_prop_square() {
         let mut field = Field::new();
-        field.square_mode = true;
+        field.set_square_mode(true);
 
         field.put((0, 0), TEST_LIGHT_TALL).unwrap();
         field.put((0, 1), TEST_DARK_SHORT).unwrap();
@@ -208,7 +547,7 @@ mod tests {
     #[test]
     fn test_different_square() {
         let mut field = Field::new();
-        field.square_mode = true;
+        field.set_square_mode(true);
 
         field.put((2, 2), TEST_LIGHT_TALL).unwrap();
         field.put((2, 3), TEST_LIGHT_TALL).unwrap();
@@ -323,6 +662,237 @@ mod tests {
         assert!(!field.check_field_for_win());
     }
 
+    #[test]
+    fn test_parse_grid() {
+        let field = Field::parse_grid(
+            "TLQH . . .
+             . . . .
+             . . SDQH .
+             . . . TLQH",
+        )
+        .unwrap();
+        assert_eq!(field.get((0, 0)), Some(TEST_LIGHT_TALL));
+        assert_eq!(field.get((2, 2)), Some(TEST_DARK_SHORT));
+        assert_eq!(field.get((3, 3)), Some(TEST_LIGHT_TALL));
+        assert_eq!(field.empty_spaces().len(), 13);
+        assert_eq!(field.winning_spaces(TEST_DARK_SHORT), vec![(1, 1)]);
+        assert!(field.winning_spaces(TEST_SHORT_FULL_DARK_CIRCLE).is_empty());
+
+        assert!(Field::parse_grid(". . . .\n. . . .\n. . . .").is_err());
+        assert!(Field::parse_grid(". . . .\n. . . .\n. . . .\n. . .").is_err());
+        assert!(Field::parse_grid(". . . .\n. . . .\n. . . .\n. . . XXXX").is_err());
+    }
+
+    #[test]
+    fn test_parse_labeled_grid() {
+        let grid = ". . . -\n. . . .\n. . SDQH .\n. . . TLQH";
+        let field = Field::parse_grid(grid).unwrap();
+        assert_eq!(
+            Field::parse_labeled_grid(grid, ArrayBase::One),
+            Ok(field.clone())
+        );
+        assert_eq!(
+            Field::parse_labeled_grid(
+                "  1 2 3 4
+                 1 . . . .
+                 2 . . . .
+                 3 . . SDQH .
+                 4 . . . TLQH",
+                ArrayBase::One
+            ),
+            Ok(field.clone())
+        );
+        // Seen from the other side
+        assert_eq!(
+            Field::parse_labeled_grid(
+                "  3 2 1 0
+                 3 TLQH . . .
+                 2 . SDQH . .
+                 1 . . . .
+                 0 . . . .",
+                ArrayBase::Zero
+            ),
+            Ok(field)
+        );
+
+        assert!(Field::parse_labeled_grid(grid, ArrayBase::Zero).is_ok());
+        assert!(Field::parse_labeled_grid("1 2 3 4\n1 . . . .", ArrayBase::One).is_err());
+        assert!(Field::parse_labeled_grid(
+            "1 . . . .\n1 . . . .\n3 . . . .\n4 . . . .",
+            ArrayBase::One
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_completed_lines() {
+        let mut field = Field::parse_grid(
+            "TLQH TLQH TLQH .
+             . . . .
+             . . . .
+             TLQH TLQH TLQH TLQH",
+        )
+        .unwrap();
+        assert_eq!(field.lines().len(), 10);
+        assert_eq!(
+            field.completed_lines(),
+            vec![[(0, 3), (1, 3), (2, 3), (3, 3)]]
+        );
+        field.set_square_mode(true);
+        assert_eq!(field.lines().len(), 19);
+        assert_eq!(field.completed_lines().len(), 1);
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let mut field = Field::parse_grid(
+            ". . . .
+             . TLQH TLQH TLQH
+             . . TLQH .
+             . . . .",
+        )
+        .unwrap();
+        assert!(!field.check_field_for_win());
+        field.add_pattern(WinPattern::parse("1,1+2,1+3,1+2,2").unwrap());
+        field.add_pattern(WinPattern::parse("1,1+2,1+3,1+2,2").unwrap());
+        assert_eq!(field.patterns().len(), 1);
+        assert!(field.check_field_for_win());
+        assert!(!field.emptied().check_field_for_win());
+        assert_eq!(field.emptied().patterns(), field.patterns());
+    }
+
+    #[test]
+    fn test_threatened_lines() {
+        let field = Field::parse_grid(
+            "TLQH TDRF TDQH .
+             . . . .
+             SLQF . . .
+             . . . .",
+        )
+        .unwrap();
+        assert_eq!(field.threatened_lines(), [[(0, 0), (1, 0), (2, 0), (3, 0)]]);
+        let row = field.threatened_lines()[0];
+        assert_eq!(
+            Piece::mask_values(field.line_property_intersection(&row)),
+            [(Property::Tall, true)]
+        );
+        assert_eq!(
+            field.line_property_intersection(&[(3, 3), (2, 3), (1, 3), (0, 3)]),
+            u8::MAX
+        );
+        // Nothing is shared by all three anymore.
+        let mut field = field;
+        field.clear((2, 0));
+        field
+            .put((2, 0), Piece::from_code("SDRF").unwrap())
+            .unwrap();
+        assert!(field.threatened_lines().is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = Field::parse_grid("TLRF . . .\n. . . .\n. . . .\n. SDQH . .").unwrap();
+        let after = Field::parse_grid(". . . .\n. . TLQH .\n. . . .\n. SDQF . .").unwrap();
+        let changes = before.diff(&after);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(
+            changes[1],
+            CellChange {
+                pos: (2, 1),
+                before: None,
+                after: Piece::from_code("TLQH").ok(),
+            }
+        );
+        let mut patched = before.clone();
+        patched.apply_patch(&changes).unwrap();
+        assert_eq!(patched, after);
+        assert!(patched.diff(&after).is_empty());
+        // The patch doesn't fit anymore.
+        assert!(patched.apply_patch(&changes).is_err());
+        assert_eq!(patched, after);
+    }
+
+    #[test]
+    fn test_render() {
+        let field = Field::parse_grid(". . . .\n. . . .\n. . . .\nTLRF . . .").unwrap();
+        let style = RenderStyle {
+            flipped: true,
+            ..RenderStyle::new(ArrayBase::One)
+        };
+        let rendered = field.render_highlighted(style, &[(1, 3)]);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 10);
+        assert_eq!(
+            lines[0],
+            "        1            2            3            4       "
+        );
+        assert_eq!(
+            lines[1],
+            "  . ---------- . ---------- . ---------- . ---------- ."
+        );
+        // Flipped, the last row comes first.
+        assert!(lines[2].starts_with("4 | [✋⬜🟠🔴] |*          *|"));
+        assert_eq!(
+            lines[3],
+            "  > ---------- + ---------- + ---------- + ---------- <"
+        );
+        assert!(field
+            .render(RenderStyle::new(ArrayBase::Zero))
+            .lines()
+            .nth(8)
+            .unwrap()
+            .starts_with("3 |"));
+
+        // The cells are as wide as the pieces.
+        let style = RenderStyle {
+            glyphs: GlyphSet::Ascii,
+            ..RenderStyle::new(ArrayBase::One)
+        };
+        let rendered = field.render(style);
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines[0], "      1        2        3        4     ");
+        assert_eq!(lines[1], "  . ------ . ------ . ------ . ------ .");
+        assert_eq!(lines[8], "4 | [TLRF] |        |        |        |");
+    }
+
+    #[test]
+    fn test_dead_lines() {
+        let field = Field::parse_grid(
+            "TLRF SDQH .    .
+             .    .    .    .
+             .    .    .    .
+             TDQF .    .    .",
+        )
+        .unwrap();
+        assert_eq!(field.dead_lines(&[]).len(), 10);
+        let pool = ["SLQH", "TLQH", "SDRH"].map(|code| Piece::from_code(code).unwrap());
+        let dead = field.dead_lines(&pool);
+        // The top row has nothing in common anymore.
+        assert!(dead.contains(&[(0, 0), (1, 0), (2, 0), (3, 0)]));
+        // The left column needs two more tall or full pieces, there is only one.
+        assert!(dead.contains(&[(0, 0), (0, 1), (0, 2), (0, 3)]));
+        // All pieces left are hollow, like the one in the second column.
+        assert!(!dead.contains(&[(1, 0), (1, 1), (1, 2), (1, 3)]));
+        assert_eq!(dead.len(), 9);
+    }
+
+    #[test]
+    fn test_rescan_for_win() {
+        let pieces: Vec<Piece> = Piece::all().collect();
+        for (a, b, c, d) in (0..16).flat_map(|a| {
+            (a + 1..16).flat_map(move |b| {
+                (b + 1..16).flat_map(move |c| (c + 1..16).map(move |d| (a, b, c, d)))
+            })
+        }) {
+            let mut field = Field::new();
+            for (x, piece) in [a, b, c, d].into_iter().enumerate() {
+                field.put((x, 2), pieces[piece]).unwrap();
+            }
+            assert_eq!(field.scan_for_win(), field.rescan_for_win());
+            assert_eq!(field.verified_win(), Ok(field.scan_for_win()));
+        }
+    }
+
     #[test]
     fn test_other_diag() {
         let mut field = Field::new();
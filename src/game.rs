@@ -1,23 +1,39 @@
-use core::fmt::Display;
+//! A game: its status, the moves played and the settings it is played with, packed positions
+//! and the move notation.
+
+use core::{
+    fmt::{Display, Write},
+    time::Duration,
+};
 
 use crate::{
-    field::{Field, Pos},
-    piece::Piece,
+    field::{try_parse_pos, Field, Pos, RenderStyle},
+    piece::{GlyphSet, Piece, Property, PropertyNames},
+    rules::Ruleset,
 };
 
+/// One of the two sides of a game
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Player {
+    /// The player starting the game, unless sides were swapped
     PlayerOne,
+    /// The other player
     PlayerTwo,
 }
 
 impl Player {
+    /// The opponent of this player
     pub fn next(self) -> Self {
         match self {
             Self::PlayerOne => Self::PlayerTwo,
             Self::PlayerTwo => Self::PlayerOne,
         }
     }
+
+    /// 0 for the first player, 1 for the second
+    pub fn index(self) -> usize {
+        usize::from(self == Self::PlayerTwo)
+    }
 }
 
 impl Display for Player {
@@ -29,30 +45,173 @@ impl Display for Player {
     }
 }
 
+/// How far a game has come, see [`Game::phase`]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Phase {
+    /// Few pieces picked, and no line threatened
+    Opening,
+    /// Between the opening and the endgame
+    Midgame,
+    /// Few pieces left to give, or many lines threatened
+    Endgame,
+}
+
+impl Phase {
+    /// The opening lasts while at most this many pieces were picked, and no line is threatened.
+    pub const OPENING_PIECES: usize = 4;
+    /// The endgame starts with at most this many pieces left to give, when searching for forced
+    /// wins becomes affordable.
+    pub const ENDGAME_PIECES: usize = 9;
+
+    /// Parses a phase by its name, `opening`, `midgame` or `endgame`.
+    pub fn from_name(name: &str) -> Result<Self, ()> {
+        [Self::Opening, Self::Midgame, Self::Endgame]
+            .into_iter()
+            .find(|phase| phase.to_string() == name)
+            .ok_or(())
+    }
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Phase::Opening => "opening",
+            Phase::Midgame => "midgame",
+            Phase::Endgame => "endgame",
+        })
+    }
+}
+
+/// What a game waits for, or how it ended
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Status {
+    /// Nothing happened yet, `starting_player` gives the first piece.
     InitialMove {
+        /// The player giving the first piece
         starting_player: Player,
     },
+    /// `next_player` puts `next_piece`, then gives the next piece.
     Move {
+        /// The player to move
         next_player: Player,
+        /// The piece to put
         next_piece: Piece,
     },
+    /// With the pie rule, after the first placement, `next_player` may swap sides instead of
+    /// putting `next_piece`.
+    SwapDecision {
+        /// The player deciding
+        next_player: Player,
+        /// The piece to put, if not swapping
+        next_piece: Piece,
+    },
+    /// `winner` completed a line.
     Won {
+        /// The player completing the line
         winner: Player,
     },
+    /// The field is full, no line was completed.
     Draw {
+        /// The player putting the last piece
         last_player: Player,
     },
 }
 
+/// A move, as it was played
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Move {
+    /// The initial move, giving the first piece to the opponent.
+    Initial {
+        /// The piece given
+        next_piece: Piece,
+    },
+    /// Putting the current piece on the field, then giving `next_piece` to the opponent.
+    /// `next_piece` is `None` if the game ended with this move.
+    Place {
+        /// The space the piece is put on
+        pos: Pos,
+        /// The piece given
+        next_piece: Option<Piece>,
+    },
+    /// The decision whether to swap sides, with the pie rule.
+    Swap {
+        /// If true, the sides were swapped.
+        accepted: bool,
+    },
+}
+
+impl Move {
+    /// The human readable notation of this move, with coordinates in the given base
+    pub fn to_notation(self, array_base: ArrayBase) -> String {
+        match self {
+            Move::Initial { next_piece } => format!("give {}", next_piece.code()),
+            Move::Place { pos, next_piece } => {
+                let (x, y) = (array_base.based(pos.0), array_base.based(pos.1));
+                if let Some(next_piece) = next_piece {
+                    format!("put {x},{y}, give {}", next_piece.code())
+                } else {
+                    format!("put {x},{y}")
+                }
+            }
+            Move::Swap { accepted: true } => "swap".to_string(),
+            Move::Swap { accepted: false } => "keep".to_string(),
+        }
+    }
+
+    /// Parses a move in the [notation](Move::to_notation), with coordinates in the given base
+    pub fn from_notation(s: &str, array_base: ArrayBase) -> Result<Self, String> {
+        let err = || format!("invalid move `{s}`");
+        let piece = |code: &str| Piece::from_code(code.trim()).map_err(|()| err());
+        let s = s.trim();
+        if let Some(code) = s.strip_prefix("give ") {
+            return Ok(Move::Initial {
+                next_piece: piece(code)?,
+            });
+        }
+        match s {
+            "swap" => return Ok(Move::Swap { accepted: true }),
+            "keep" => return Ok(Move::Swap { accepted: false }),
+            _ => {}
+        }
+        let placement = s.strip_prefix("put ").ok_or_else(err)?;
+        let (pos, next_piece) = match placement.split_once(", give ") {
+            Some((pos, code)) => (pos, Some(piece(code)?)),
+            None => (placement, None),
+        };
+        let (x, y) = try_parse_pos(pos).map_err(|()| err())?;
+        let pos = (array_base.unbased(x), array_base.unbased(y));
+        if pos.0 >= Field::SIZE || pos.1 >= Field::SIZE {
+            return Err(err());
+        }
+        Ok(Move::Place { pos, next_piece })
+    }
+}
+
+/// The version of the layout of [packed](Game::pack) positions, increased on incompatible changes
+pub const PACK_VERSION: u8 = 2;
+
+/// The key to pick `piece` with in quick-pick mode, a hex digit
+pub fn quick_key(piece: Piece) -> char {
+    char::from_digit(u32::from(piece.index()), 16).unwrap()
+}
+
+/// The piece picked by a quick-pick `key`, see [`quick_key`]
+pub fn from_quick_key(key: char) -> Option<Piece> {
+    let index = key.to_digit(16)?;
+    Piece::from_index(u8::try_from(index).unwrap()).ok()
+}
+
+/// Whether spaces and pieces are numbered from 0 or from 1 for the user
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ArrayBase {
+    /// Numbered from 0
     Zero,
+    /// Numbered from 1
     One,
 }
 
 impl ArrayBase {
+    /// The user-facing number of the 0-based `zero_based_val`
     #[must_use]
     #[inline]
     pub fn based(self, zero_based_val: usize) -> usize {
@@ -64,6 +223,7 @@ impl ArrayBase {
         }
     }
 
+    /// The 0-based value of the user-facing number `based_val`
     #[must_use]
     #[inline]
     pub fn unbased(self, based_val: usize) -> usize {
@@ -75,44 +235,336 @@ impl ArrayBase {
     }
 }
 
+#[cfg(test)]
+thread_local! {
+    /// How many positions [`Game::wins_within`] searched on this thread, for tests bounding the
+    /// cost of searches
+    pub(crate) static SEARCHED: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// With at most this many empty spaces, the AI solves the game exactly rather than relying on
+/// heuristics, unless configured otherwise with [`Game::solver_spaces`].
+pub const SOLVER_SPACES: usize = 6;
+
+/// The most empty spaces [`Game::solver_spaces`] can be set to: solving a position with this
+/// many takes up to about a second in release builds, each space more about ten times as long.
+pub const MAX_SOLVER_SPACES: usize = 8;
+
+/// A game of Quarto: the field, the remaining pieces, the history and the settings it is played
+/// with
 #[derive(Debug, Clone)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Game {
+    /// How spaces and pieces are numbered for the user
     pub array_base: ArrayBase,
+    /// The field with the pieces put so far
     pub field: Field,
     remaining_pieces: Vec<Piece>,
+    history: Vec<Move>,
+    /// How long each move of the history took, if it was timed
+    move_times: Vec<Option<Duration>>,
+    /// What the game waits for, or how it ended
     pub status: Status,
+    /// If true, the AI explains its moves, for the binary to print.
     pub ai_reasoning: bool,
+    /// The seed of the AI, random if `None`
     pub seed: Option<u64>,
+    /// How the AI picks among moves that don't lose right away: `None` picks uniformly, otherwise
+    /// moves are weighted by `exp(score / temperature)`, so 0 always picks the best.
+    pub ai_temperature: Option<f64>,
+    /// With at most this many empty spaces, the AI plays perfectly by searching the game to the
+    /// end, it uses heuristics before. At most [`MAX_SOLVER_SPACES`].
+    pub solver_spaces: usize,
+    /// How the solving AI picks among drawing moves, from -1 to 1: positive values prefer those
+    /// leaving the opponent more replies that lose within a few moves, hoping for a mistake of a
+    /// weaker opponent, negative values the quiet ones, against a stronger opponent. 0 picks
+    /// uniformly.
+    pub contempt: f64,
+    /// If true, two humans play each other, without the AI.
     pub pvp: bool,
+    /// If true, every win check is [verified](Field::verified_win), panicking if the checks
+    /// disagree, for a crash report rather than a wrongly ended game.
+    pub verify_wins: bool,
+    /// If true, the field is shown from the other side, with the last row on top.
+    pub flipped: bool,
+    /// How pieces are drawn
+    pub glyphs: GlyphSet,
+    /// How pieces are described in words
+    pub property_names: PropertyNames,
+    /// If true, humans see a preview of each placement, and confirm it.
+    pub preview: bool,
+    /// If true, pieces are picked by their [`quick_key`], which stays the same all game.
+    pub quick_pick: bool,
+    /// If true, the player moving after the first placement may swap sides instead.
+    pub pie_rule: bool,
+    /// With Blind Quarto, this property of unplaced pieces is hidden from the player placing,
+    /// it is only revealed once the piece is on the field.
+    pub hidden_property: Option<Property>,
+    /// With the scoring variant, completing a line scores a point and clears it from the field.
+    /// The first player to reach this many points wins, otherwise the game ends when all pieces
+    /// are played.
+    pub race_to: Option<u32>,
+    scores: [u32; 2],
+    starting_player: Player,
+    /// The [packed](Game::pack) position this game was set up from, unless it started on an
+    /// empty field
+    setup: Option<u128>,
 }
 
 impl Game {
     /// Starts a new game
     pub fn new(starting_player: Player) -> Self {
-        let remaining_pieces = (0..16).map(Piece::with_props).collect();
+        let remaining_pieces = Piece::all().collect();
 
         #[allow(clippy::cast_precision_loss)]
         Self {
             array_base: ArrayBase::One,
             remaining_pieces,
+            history: vec![],
+            move_times: vec![],
             field: Field::new(),
             status: Status::InitialMove { starting_player },
             ai_reasoning: false,
             seed: None,
+            ai_temperature: None,
+            solver_spaces: SOLVER_SPACES,
+            contempt: 0.0,
             pvp: false,
+            verify_wins: false,
+            flipped: false,
+            glyphs: GlyphSet::Emoji,
+            property_names: PropertyNames::english(),
+            preview: false,
+            quick_pick: false,
+            pie_rule: false,
+            hidden_property: None,
+            race_to: None,
+            scores: [0; 2],
+            starting_player,
+            setup: None,
+        }
+    }
+
+    /// Starts a new game with the given rules, if they are valid
+    pub fn with_rules(starting_player: Player, rules: &Ruleset) -> Result<Self, String> {
+        rules.validate()?;
+        let mut game = Self::new(starting_player);
+        game.set_rules(rules);
+        Ok(game)
+    }
+
+    /// Plays this game with the given rules from now on, they should be valid.
+    pub fn set_rules(&mut self, rules: &Ruleset) {
+        for pattern in &rules.patterns {
+            self.field.add_pattern(pattern.clone());
+        }
+        self.pie_rule = rules.pie_rule;
+        self.hidden_property = rules.hidden_property;
+        self.race_to = rules.race_to;
+    }
+
+    /// The rules this game is played with
+    pub fn rules(&self) -> Ruleset {
+        Ruleset {
+            patterns: self.field.patterns().to_vec(),
+            pie_rule: self.pie_rule,
+            hidden_property: self.hidden_property,
+            race_to: self.race_to,
+        }
+    }
+
+    /// Sets up a game in the middle of play: `field` holds all pieces placed so far, and it's
+    /// `player`'s turn to put `next_piece`, or to give the first piece if it is `None`.
+    /// Fails if a piece is used twice.
+    pub fn from_position(
+        field: Field,
+        player: Player,
+        next_piece: Option<Piece>,
+    ) -> Result<Self, ()> {
+        let mut game = Self::with_pieces(field, player, next_piece)?;
+        // Only the initial move has no piece to place.
+        if next_piece.is_none() && !game.field.pieces().is_empty() {
+            return Err(());
+        }
+        game.setup = Some(game.pack());
+        Ok(game)
+    }
+
+    /// A game with `field` and `next_piece` to place by `player`, the other pieces remaining, or
+    /// at the initial move without a piece to place, whatever is on the field. Fails if a piece
+    /// is used twice.
+    fn with_pieces(field: Field, player: Player, next_piece: Option<Piece>) -> Result<Self, ()> {
+        let mut used = field.pieces();
+        used.extend(next_piece);
+        let mut game = Self::new(player);
+        game.remaining_pieces.retain(|piece| !used.contains(piece));
+        if game.remaining_pieces.len() + used.len() != Field::SIZE * Field::SIZE {
+            return Err(());
+        }
+        game.field = field;
+        if let Some(next_piece) = next_piece {
+            game.status = Status::Move {
+                next_player: player,
+                next_piece,
+            };
+        }
+        Ok(game)
+    }
+
+    /// Packs the position into 128 bits, for example as a key for positions. The rules and the
+    /// moves that led here are not included. The layout, from the lowest bit, is:
+    ///  * 16 bits: the occupied spaces, bit `y * 4 + x` for space `(x, y)`,
+    ///  * 64 bits: the [index](Piece::index) of the piece on each such space, 4 bits each,
+    ///  * 4 bits: the index of the piece to place, and 1 bit: set if there is one,
+    ///  * 1 bit: set if it's player two's turn, or player two won or moved last,
+    ///  * 3 bits: the status, 0 to 4 for the initial move, a move, a swap decision, won and draw,
+    ///  * 13 bits each: the points of player one and player two, in the scoring variant, enough
+    ///    for any score: each of the 16 placements completes at most the 455 lines through its
+    ///    space,
+    ///
+    /// and [`PACK_VERSION`] in the highest 8 bits. Version 1 had 4 bits per score, positions
+    /// packed with it still unpack.
+    pub fn pack(&self) -> u128 {
+        let mut packed = u128::from(PACK_VERSION) << 120;
+        for (i, pos) in (0..Field::SIZE * Field::SIZE)
+            .map(|i| (i % Field::SIZE, i / Field::SIZE))
+            .enumerate()
+        {
+            if let Some(piece) = self.field.get(pos) {
+                packed |= 1 << i | u128::from(piece.index()) << (16 + 4 * i);
+            }
+        }
+        if let Some(piece) = self.next_piece() {
+            packed |= u128::from(piece.index()) << 80 | 1 << 84;
+        }
+        let status: u128 = match self.status {
+            Status::InitialMove { .. } => 0,
+            Status::Move { .. } => 1,
+            Status::SwapDecision { .. } => 2,
+            Status::Won { .. } => 3,
+            Status::Draw { .. } => 4,
+        };
+        packed |= (self.player().index() as u128) << 85 | status << 86;
+        debug_assert!(self.scores.iter().all(|&score| score < 1 << 13));
+        packed |= u128::from(self.scores[0]) << 89 | u128::from(self.scores[1]) << 102;
+        packed
+    }
+
+    /// Sets up a game from a [packed](Game::pack) position, with the standard rules. Fails if
+    /// the position is not valid.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn unpack(packed: u128) -> Result<Self, ()> {
+        let bits = |shift: u32, len: u32| (packed >> shift) as u32 & ((1 << len) - 1);
+        let score_bits = match bits(120, 8) {
+            1 => 4,
+            2 => 13,
+            _ => return Err(()),
+        };
+        let unused = 89 + 2 * score_bits;
+        if bits(unused, 120 - unused) != 0 {
+            return Err(());
         }
+        let piece = |index: u32| Piece::from_index(u8::try_from(index).unwrap()).unwrap();
+
+        let mut field = Field::new();
+        for i in 0..16 {
+            if bits(i, 1) == 1 {
+                let pos = (i as usize % Field::SIZE, i as usize / Field::SIZE);
+                field.put(pos, piece(bits(16 + 4 * i, 4)))?;
+            }
+        }
+        let next_piece = (bits(84, 1) == 1).then(|| piece(bits(80, 4)));
+        let player = if bits(85, 1) == 0 {
+            Player::PlayerOne
+        } else {
+            Player::PlayerTwo
+        };
+
+        let status = match (bits(86, 3), next_piece) {
+            (0 | 1, _) => None,
+            (2, Some(next_piece)) => Some(Status::SwapDecision {
+                next_player: player,
+                next_piece,
+            }),
+            (3, None) => Some(Status::Won { winner: player }),
+            (4, None) => Some(Status::Draw {
+                last_player: player,
+            }),
+            _ => return Err(()),
+        };
+        let mut game = match status {
+            // Finished games have pieces on the field, but none to place.
+            Some(Status::Won { .. } | Status::Draw { .. }) => {
+                Self::with_pieces(field, player, next_piece)?
+            }
+            _ => Self::from_position(field, player, next_piece)?,
+        };
+        if status.is_none() && game.is_initial_move() != (bits(86, 3) == 0) {
+            return Err(());
+        }
+        if let Some(status) = status {
+            game.status = status;
+        }
+        game.scores = [bits(89, score_bits), bits(89 + score_bits, score_bits)];
+        game.setup = Some(packed);
+        Ok(game)
+    }
+
+    /// The [packed](Game::pack) position this game was set up from, if it didn't start on an
+    /// empty field
+    pub fn setup(&self) -> Option<u128> {
+        self.setup
+    }
+
+    /// Starts a new game with the same settings as this one
+    pub fn rematch(&self, starting_player: Player) -> Self {
+        let mut game = Self::new(starting_player);
+        game.field = self.field.emptied();
+        game.copy_settings(self);
+        Self {
+            pie_rule: self.pie_rule,
+            hidden_property: self.hidden_property,
+            race_to: self.race_to,
+            ..game
+        }
+    }
+
+    /// Takes over the settings of `other` besides the rules, such as those of the AI
+    pub fn copy_settings(&mut self, other: &Game) {
+        self.array_base = other.array_base;
+        self.ai_reasoning = other.ai_reasoning;
+        self.seed = other.seed;
+        self.ai_temperature = other.ai_temperature;
+        self.solver_spaces = other.solver_spaces;
+        self.contempt = other.contempt;
+        self.pvp = other.pvp;
+        self.verify_wins = other.verify_wins;
+        self.flipped = other.flipped;
+        self.glyphs = other.glyphs;
+        self.property_names = other.property_names.clone();
+        self.preview = other.preview;
+        self.quick_pick = other.quick_pick;
+    }
+
+    /// The player that moved first
+    pub fn starting_player(&self) -> Player {
+        self.starting_player
     }
 
+    /// The number of the current round, counting from 1, a round being a move of both players
     pub fn round(&self) -> u8 {
         (((Field::SIZE * Field::SIZE - self.remaining_pieces.len()) / 2) + 1)
             .try_into()
             .unwrap()
     }
 
+    /// The player to move
     pub fn player(&self) -> Player {
         match self.status {
             Status::InitialMove { starting_player } => starting_player,
-            Status::Move { next_player, .. } => next_player,
+            Status::Move { next_player, .. } | Status::SwapDecision { next_player, .. } => {
+                next_player
+            }
             Status::Won { winner } => winner,
             Status::Draw { last_player } => last_player,
         }
@@ -121,7 +573,7 @@ impl Game {
     /// Returns true if the game is running, false if it's over (`Draw` or `Won`)
     pub fn running(&self) -> bool {
         match self.status {
-            Status::InitialMove { .. } | Status::Move { .. } => true,
+            Status::InitialMove { .. } | Status::Move { .. } | Status::SwapDecision { .. } => true,
             Status::Won { .. } | Status::Draw { .. } => false,
         }
     }
@@ -135,61 +587,169 @@ impl Game {
         }
     }
 
+    /// If true, the first piece has to be given.
     pub fn is_initial_move(&self) -> bool {
         matches!(self.status, Status::InitialMove { .. })
     }
 
+    /// Returns true if the current player has to decide whether to swap sides
+    pub fn is_swap_decision(&self) -> bool {
+        matches!(self.status, Status::SwapDecision { .. })
+    }
+
+    /// The piece the player to move has to put, if any
     pub fn next_piece(&self) -> Option<Piece> {
-        if let Status::Move { next_piece, .. } = self.status {
+        if let Status::Move { next_piece, .. } | Status::SwapDecision { next_piece, .. } =
+            self.status
+        {
             Some(next_piece)
         } else {
             None
         }
     }
 
-    pub fn pp(&self) {
-        println!("Quarto, round: {}", self.round());
-        println!();
-        if self.running() {
-            println!("{}, your move.", self.player());
+    /// The property `viewer` can't see on unplaced pieces, if any. With Blind Quarto, that's
+    /// the player about to place a piece.
+    pub fn hidden_from(&self, viewer: Player) -> Option<Property> {
+        let placing = matches!(
+            self.status,
+            Status::Move { .. } | Status::SwapDecision { .. }
+        );
+        self.hidden_property
+            .filter(|_| placing && viewer == self.player())
+    }
+
+    /// The pieces the player to place might be holding, as far as they can tell: all unplaced
+    /// pieces only differing from the actual one in the hidden property.
+    /// Without a hidden property, or without a piece to place, this is just the actual piece.
+    pub fn hand_candidates(&self) -> Vec<Piece> {
+        let Some(hand) = self.next_piece() else {
+            return vec![];
+        };
+        let Some(hidden) = self.hidden_property else {
+            return vec![hand];
+        };
+        let mut candidates = vec![hand];
+        candidates.extend(
+            self.remaining_pieces
+                .iter()
+                .filter(|piece| piece.matches_except(hand, hidden)),
+        );
+        candidates
+    }
+
+    /// How to render the field of this game
+    pub fn render_style(&self) -> RenderStyle {
+        RenderStyle {
+            array_base: self.array_base,
+            flipped: self.flipped,
+            glyphs: self.glyphs,
+        }
+    }
+
+    /// Renders the game as seen by the current player
+    pub fn render(&self) -> String {
+        self.render_for(self.player())
+    }
+
+    /// Renders the game as seen by `viewer`, which matters for Blind Quarto.
+    pub fn render_for(&self, viewer: Player) -> String {
+        let hidden = self.hidden_from(viewer);
+        let mut ret = format!("Quarto, round: {}\n\n", self.round());
+        if self.is_swap_decision() {
+            writeln!(
+                ret,
+                "{}, you may swap sides, leaving the next placement to your opponent.",
+                self.player()
+            )
+            .unwrap();
+        } else if self.running() {
+            writeln!(ret, "{}, your move.", self.player()).unwrap();
         } else if let Some(winner) = self.winner() {
-            println!("{winner} won!");
+            writeln!(ret, "{winner} won!").unwrap();
+        } else if self.field.empty_spaces().is_empty() {
+            ret.push_str("Game ended in a draw!\n");
         } else {
-            println!("Game ended in a draw!");
+            ret.push_str("Game ended in a draw, no line can be completed anymore!\n");
         }
 
         if !self.remaining_pieces().is_empty() {
-            println!("\nRemaining Pieces:");
-            self.pp_remaining_pieces();
+            ret.push_str("\nRemaining Pieces:\n");
+            ret.push_str(&self.render_remaining_pieces_masked(hidden));
+        }
+        ret.push_str("\nField:\n");
+        ret.push_str(&self.field.render(self.render_style()));
+
+        if let Some(target) = self.race_to {
+            writeln!(
+                ret,
+                "\nScore: {} {}, {} {}, first to {target} points wins",
+                Player::PlayerOne,
+                self.score(Player::PlayerOne),
+                Player::PlayerTwo,
+                self.score(Player::PlayerTwo),
+            )
+            .unwrap();
         }
-        println!("\nField:");
-        self.field.pp(self.array_base);
 
         if let Some(piece) = self.next_piece() {
-            println!("\nThe next piece to place is:");
-            print!("       ");
-            piece.pp();
-            println!();
+            ret.push_str("\nThe next piece to place is:\n");
+            writeln!(
+                ret,
+                "       {} {}",
+                piece.render_masked(hidden, self.glyphs),
+                self.property_names.describe(piece, hidden)
+            )
+            .unwrap();
         }
+        ret
     }
 
-    pub fn pp_remaining_pieces(&self) {
+    /// Renders the remaining pieces with their numbers, three per row.
+    pub fn render_remaining_pieces(&self) -> String {
+        self.render_remaining_pieces_masked(None)
+    }
+
+    /// Renders the remaining pieces, showing `hidden` as unknown.
+    pub fn render_remaining_pieces_masked(&self, hidden: Option<Property>) -> String {
+        let mut ret = String::new();
         for (i, piece) in self.remaining_pieces().iter().enumerate() {
             if i > 0 && (i) % 3 == 0 {
-                println!();
+                ret.push('\n');
             }
             let based_i = self.array_base.based(i);
-            print!("  {based_i}: ");
-            if self.array_base.based(i) < 10 {
+            if self.quick_pick {
+                write!(ret, "  {}: ", quick_key(*piece)).unwrap();
+            } else {
+                write!(ret, "  {based_i}: ").unwrap();
+            }
+            if self.quick_pick || based_i < 10 {
                 // padding for low numbers
-                print!(" ");
+                ret.push(' ');
             }
-            piece.pp();
+            ret.push_str(&piece.render_masked(hidden, self.glyphs));
             if i < (Field::SIZE * Field::SIZE) - 1 && (i + 1) % 3 != 0 {
-                print!(",  ");
+                ret.push_str(",  ");
             }
         }
-        println!();
+        ret.push('\n');
+        ret
+    }
+
+    /// The points `player` scored, with the scoring variant
+    pub fn score(&self, player: Player) -> u32 {
+        self.scores[player.index()]
+    }
+
+    /// Scores all completed lines for `player` and clears them, returns true if the player reached
+    /// the points needed to win.
+    fn score_lines(&mut self, player: Player, target: u32) -> bool {
+        let lines = self.field.completed_lines();
+        self.scores[player.index()] += u32::try_from(lines.len()).unwrap();
+        for pos in lines.into_iter().flatten() {
+            self.field.clear(pos);
+        }
+        self.score(player) >= target
     }
 
     /// Returns the list of remaining pieces
@@ -197,6 +757,96 @@ impl Game {
         &self.remaining_pieces
     }
 
+    /// Returns true if no line can be completed anymore with the remaining pieces, so the game
+    /// can only end in a draw.
+    fn is_dead_draw(&self) -> bool {
+        self.field.dead_lines(&self.remaining_pieces).len() == self.field.lines().len()
+    }
+
+    /// The phase of the game: the opening while few pieces were picked and no line is
+    /// threatened, the endgame once few pieces are left to give or at least half the lines that
+    /// can still be completed are threatened, the midgame in between. Finished games are in the
+    /// endgame.
+    pub fn phase(&self) -> Phase {
+        if !self.running() || self.remaining_pieces.len() <= Phase::ENDGAME_PIECES {
+            return Phase::Endgame;
+        }
+        let mut pool = self.remaining_pieces.clone();
+        pool.extend(self.next_piece());
+        let threats = self.field.threatened_lines().len();
+        let open = self.field.lines().len() - self.field.dead_lines(&pool).len();
+        if threats > 0 && 2 * threats >= open {
+            Phase::Endgame
+        } else if threats == 0 && Piece::all().count() - pool.len() <= Phase::OPENING_PIECES {
+            Phase::Opening
+        } else {
+            Phase::Midgame
+        }
+    }
+
+    /// For each remaining piece, in order, the spaces where putting it would win immediately.
+    pub fn danger_matrix(&self) -> Vec<(Piece, Vec<Pos>)> {
+        self.remaining_pieces
+            .iter()
+            .map(|&piece| (piece, self.field.winning_spaces(piece)))
+            .collect()
+    }
+
+    /// Returns all moves played so far
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// Returns all moves played so far, with the player making each
+    pub fn history_with_players(&self) -> Vec<(Player, Move)> {
+        let mut player = self.starting_player;
+        self.history
+            .iter()
+            .map(|&mv| {
+                let ret = (player, mv);
+                // Keeping sides means the same player moves again.
+                if mv != (Move::Swap { accepted: false }) {
+                    player = player.next();
+                }
+                ret
+            })
+            .collect()
+    }
+
+    /// Takes the game back to how it was after the first `ply` moves of the
+    /// [history](Game::history), replaying them from the start, or from the position it was set
+    /// up from. Fails if fewer moves were played.
+    pub fn rollback_to(&mut self, ply: usize) -> Result<(), ()> {
+        if ply > self.history.len() {
+            return Err(());
+        }
+        let mut game = match self.setup {
+            Some(setup) => Self::unpack(setup)?,
+            None => Self::new(self.starting_player),
+        };
+        game.copy_settings(self);
+        game.set_rules(&self.rules());
+        for &mv in &self.history[..ply] {
+            game.make_move(mv)?;
+        }
+        game.move_times = self.move_times.iter().copied().take(ply).collect();
+        *self = game;
+        Ok(())
+    }
+
+    /// Records how long the last move took
+    pub fn set_move_time(&mut self, time: Duration) {
+        self.move_times.resize(self.history.len(), None);
+        if let Some(last) = self.move_times.last_mut() {
+            *last = Some(time);
+        }
+    }
+
+    /// How long the move at `ply` of the [history](Game::history) took, if it was timed
+    pub fn move_time(&self, ply: usize) -> Option<Duration> {
+        self.move_times.get(ply).copied().flatten()
+    }
+
     /// Gives the initial piece to the opponent, as we do not actually put a piece onto the field
     /// in the first turn.
     pub fn initial_move(&mut self, next_piece: Piece) -> Result<(), ()> {
@@ -207,6 +857,7 @@ impl Game {
                 .position(|&x| x == next_piece)
                 .ok_or(())?;
             self.remaining_pieces.remove(i);
+            self.history.push(Move::Initial { next_piece });
             self.status = Status::Move {
                 next_player: starting_player.next(),
                 next_piece,
@@ -217,6 +868,65 @@ impl Game {
         }
     }
 
+    /// Decides whether to swap sides, with the pie rule. Swapping means the opponent, who made
+    /// the first placement, has to place the piece they just gave away.
+    pub fn decide_swap(&mut self, swap: bool) -> Result<(), ()> {
+        let Status::SwapDecision {
+            next_player,
+            next_piece,
+        } = self.status
+        else {
+            return Err(());
+        };
+        self.status = Status::Move {
+            next_player: if swap {
+                next_player.next()
+            } else {
+                next_player
+            },
+            next_piece,
+        };
+        self.history.push(Move::Swap { accepted: swap });
+        Ok(())
+    }
+
+    /// Ends the game, with the opponent of `loser` as the winner, as when a bot breaks the rules
+    pub fn forfeit(&mut self, loser: Player) {
+        self.status = Status::Won {
+            winner: loser.next(),
+        };
+    }
+
+    /// Makes any kind of move, as it would be recorded in the [history](Game::history). A
+    /// placement without a piece to give is only legal if it ends the game, a piece given with a
+    /// placement ending the game is ignored. Nothing changes if the move is illegal.
+    pub fn make_move(&mut self, mv: Move) -> Result<(), ()> {
+        match (mv, &self.status) {
+            (Move::Initial { next_piece }, Status::InitialMove { .. }) => {
+                self.initial_move(next_piece)
+            }
+            (Move::Swap { accepted }, Status::SwapDecision { .. }) => self.decide_swap(accepted),
+            (
+                Move::Place { pos, next_piece },
+                &Status::Move {
+                    next_piece: placed, ..
+                },
+            ) => {
+                let mut after = self.clone();
+                let given = next_piece
+                    .or_else(|| self.remaining_pieces().first().copied())
+                    .unwrap_or(placed);
+                after.do_move(pos, given)?;
+                if next_piece.is_none() && after.running() {
+                    return Err(());
+                }
+                *self = after;
+                Ok(())
+            }
+            _ => Err(()),
+        }
+    }
+
     /// Next move, actually put a piece on the field, and give the next piece to the opponent or
     /// checks if a player won..
     pub fn do_move(&mut self, pos: Pos, next_piece: Piece) -> Result<(), ()> {
@@ -226,38 +936,173 @@ impl Game {
             next_piece: piece,
         } = self.status
         {
+            // The next piece only matters if the game goes on.
+            if !self.remaining_pieces.is_empty() && !self.remaining_pieces.contains(&next_piece) {
+                return Err(());
+            }
             // Actually perform the move on the field.
             self.field.put(pos, piece)?;
 
-            if self.remaining_pieces().is_empty() {
-                // This is a draw
+            // Check if this piece yielded a win for this player.
+            let won = if let Some(target) = self.race_to {
+                self.score_lines(player, target)
+            } else if self.verify_wins {
+                self.field
+                    .verified_win()
+                    .unwrap_or_else(|err| panic!("{err}"))
+            } else {
+                self.field.check_field_for_win()
+            };
+            if won || self.remaining_pieces().is_empty() {
+                let (own, other) = (self.score(player), self.score(player.next()));
+                self.status = if won || own > other {
+                    Status::Won { winner: player }
+                } else if own < other {
+                    Status::Won {
+                        winner: player.next(),
+                    }
+                } else {
+                    // This is a draw
+                    Status::Draw {
+                        last_player: player,
+                    }
+                };
+                self.history.push(Move::Place {
+                    pos,
+                    next_piece: None,
+                });
+                return Ok(());
+            }
+
+            // The piece is only given if the game goes on, like after any other last move.
+            if self.race_to.is_none() && self.is_dead_draw() {
                 self.status = Status::Draw {
                     last_player: player,
                 };
+                self.history.push(Move::Place {
+                    pos,
+                    next_piece: None,
+                });
                 return Ok(());
             }
+
             // remove the piece from `remaining_pieces`.
-            let i = self
-                .remaining_pieces()
-                .iter()
-                .position(|&x| x == next_piece)
-                .ok_or(())?;
-            self.remaining_pieces.remove(i);
-            // Check if this piece yielded a win for this player.
-            if self.field.check_field_for_win() {
-                self.status = Status::Won { winner: player }
+            self.remaining_pieces.retain(|&x| x != next_piece);
+            // Set-up positions start with pieces on the field, and with the scoring variant the
+            // field can be down to one piece again later.
+            let first_placement = self.field.pieces().len() == 1
+                && !self
+                    .history
+                    .iter()
+                    .any(|mv| matches!(mv, Move::Place { .. }));
+            self.status = if self.pie_rule && first_placement {
+                Status::SwapDecision {
+                    next_player: player.next(),
+                    next_piece,
+                }
             } else {
-                self.status = Status::Move {
+                Status::Move {
                     next_player: player.next(),
                     next_piece,
                 }
             };
+            self.history.push(Move::Place {
+                pos,
+                next_piece: Some(next_piece),
+            });
             Ok(())
         } else {
             Err(())
         }
     }
 
+    /// Returns true if the player to move can force a win within `plies` moves, counting the
+    /// moves of both players, but not swap decisions. All pieces are assumed to be visible.
+    pub fn forced_win_in(&self, plies: usize) -> bool {
+        self.wins_within(self.player(), plies)
+    }
+
+    /// Returns true if `player` can force a win within `plies` moves, whoever's turn it is.
+    pub fn wins_within(&self, player: Player, plies: usize) -> bool {
+        #[cfg(test)]
+        SEARCHED.with(|searched| searched.set(searched.get() + 1));
+        let ours = self.player() == player;
+        match self.status {
+            Status::Won { winner } => return winner == player,
+            Status::Draw { .. } => return false,
+            Status::SwapDecision { .. } => {
+                let mut outcomes = [true, false].into_iter().map(|swap| {
+                    let mut game = self.clone();
+                    game.decide_swap(swap).unwrap();
+                    game.wins_within(player, plies)
+                });
+                return if ours {
+                    outcomes.any(|won| won)
+                } else {
+                    outcomes.all(|won| won)
+                };
+            }
+            Status::InitialMove { .. } | Status::Move { .. } => (),
+        }
+        if plies == 0 {
+            return false;
+        }
+
+        // Winning right away ends the search, whatever the piece given would be.
+        let any_piece = self.remaining_pieces.first().copied();
+        if let Some(piece) = self.next_piece() {
+            let wins_now = self.field.empty_spaces().into_iter().any(|pos| {
+                let mut game = self.clone();
+                game.do_move(pos, any_piece.unwrap_or(piece)).unwrap();
+                game.winner() == Some(self.player())
+            });
+            if wins_now {
+                return ours;
+            }
+        }
+        // Otherwise, `player` needs another move to win.
+        if plies < if ours { 3 } else { 2 } {
+            return false;
+        }
+
+        let mut successors = self.successors().into_iter();
+        if ours {
+            successors.any(|game| game.wins_within(player, plies - 1))
+        } else {
+            successors.all(|game| game.wins_within(player, plies - 1))
+        }
+    }
+
+    /// All games reachable with a single move, putting the current piece and giving the next one
+    fn successors(&self) -> Vec<Game> {
+        let mut ret = vec![];
+        match self.status {
+            Status::InitialMove { .. } => {
+                for &piece in self.remaining_pieces() {
+                    let mut game = self.clone();
+                    game.initial_move(piece).unwrap();
+                    ret.push(game);
+                }
+            }
+            Status::Move { next_piece, .. } => {
+                let gives = if self.remaining_pieces.is_empty() {
+                    vec![next_piece]
+                } else {
+                    self.remaining_pieces.clone()
+                };
+                for pos in self.field.empty_spaces() {
+                    for &piece in &gives {
+                        let mut game = self.clone();
+                        game.do_move(pos, piece).unwrap();
+                        ret.push(game);
+                    }
+                }
+            }
+            Status::SwapDecision { .. } | Status::Won { .. } | Status::Draw { .. } => (),
+        }
+        ret
+    }
+
     /// Undo the latest move
     #[cfg(test)]
     pub fn unmove(&mut self, last_pos: Pos) {
@@ -267,7 +1112,9 @@ impl Game {
             }
             Status::Won { winner } => winner,
             Status::Draw { last_player } => last_player,
-            Status::Move { next_player, .. } => next_player,
+            Status::Move { next_player, .. } | Status::SwapDecision { next_player, .. } => {
+                next_player
+            }
         };
 
         // There are only two players, so next is also prev.
@@ -275,6 +1122,8 @@ impl Game {
 
         let last_piece = self.field.clear(last_pos).unwrap();
         self.remaining_pieces.push(last_piece);
+        self.history.pop();
+        self.move_times.truncate(self.history.len());
 
         if self.remaining_pieces.len() == Field::SIZE * Field::SIZE {
             self.status = Status::InitialMove {
@@ -285,13 +1134,22 @@ impl Game {
                 next_piece: last_piece,
                 next_player: prev_player,
             }
-        };
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Game, Player};
+    use core::time::Duration;
+
+    use crate::{
+        field::Field,
+        piece::{Piece, Property},
+    };
+
+    use super::{
+        from_quick_key, quick_key, ArrayBase, Game, Move, Phase, Player, Status, PACK_VERSION,
+    };
 
     #[test]
     fn test_move_unmove() {
@@ -303,4 +1161,429 @@ mod tests {
         game.unmove((0, 0));
         assert_eq!(post_unmove, game.field);
     }
+
+    #[test]
+    fn test_history() {
+        let mut game = Game::new(Player::PlayerOne);
+        let first = game.remaining_pieces()[0];
+        let second = game.remaining_pieces()[1];
+        game.initial_move(first).unwrap();
+        game.do_move((1, 2), second).unwrap();
+        assert_eq!(
+            game.history(),
+            [
+                Move::Initial { next_piece: first },
+                Move::Place {
+                    pos: (1, 2),
+                    next_piece: Some(second)
+                }
+            ]
+        );
+        assert_eq!(game.history()[0].to_notation(ArrayBase::One), "give SDQH");
+        assert_eq!(
+            game.history()[1].to_notation(ArrayBase::One),
+            "put 2,3, give TDQH"
+        );
+        assert_eq!(
+            game.history()[1].to_notation(ArrayBase::Zero),
+            "put 1,2, give TDQH"
+        );
+
+        game.unmove((1, 2));
+        assert_eq!(game.history().len(), 1);
+    }
+
+    #[test]
+    fn test_make_move() {
+        let mut game = Game::new(Player::PlayerOne);
+        let notation = ["give SDQH", "put 2,3, give TDQH", "put 1,1"];
+        let moves: Vec<Move> = notation
+            .iter()
+            .map(|s| Move::from_notation(s, ArrayBase::One).unwrap())
+            .collect();
+        assert_eq!(moves[1].to_notation(ArrayBase::One), notation[1]);
+        assert!(game.make_move(moves[1]).is_err());
+        game.make_move(moves[0]).unwrap();
+        game.make_move(moves[1]).unwrap();
+        // The game goes on, so a piece has to be given.
+        assert!(game.make_move(moves[2]).is_err());
+        assert_eq!(game.history().len(), 2);
+        // The space is taken.
+        assert!(game.make_move(moves[1]).is_err());
+
+        for invalid in [
+            "give",
+            "put 0,1, give SDQH",
+            "put 1,5",
+            "put 1,1 give SDQH",
+            "pass",
+        ] {
+            assert!(
+                Move::from_notation(invalid, ArrayBase::One).is_err(),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_phase() {
+        let position = |grid: &str, hand: &str| {
+            let field = Field::parse_grid(grid).unwrap();
+            Game::from_position(field, Player::PlayerOne, Piece::from_code(hand).ok()).unwrap()
+        };
+        assert_eq!(Game::new(Player::PlayerOne).phase(), Phase::Opening);
+        let quiet = position("TLRF . . .\n. . . .\n. . . .\n. . SDQH .", "TDRH");
+        assert_eq!(quiet.phase(), Phase::Opening);
+        let threatened = position("TLRF SDRH TDRF .\n. . . .\n. . . .\n. . . .", "SLQH");
+        assert_eq!(threatened.phase(), Phase::Midgame);
+        let late = position(
+            "TLRF SDQH . .\nTDRH SLQF . .\nTLQH SDRF . .\n. . . .",
+            "SLRH",
+        );
+        assert_eq!(late.phase(), Phase::Endgame);
+        assert_eq!(Phase::from_name("midgame"), Ok(Phase::Midgame));
+        assert!(Phase::from_name("middle").is_err());
+    }
+
+    #[test]
+    fn test_rollback_to() {
+        let mut game = Game::new(Player::PlayerTwo);
+        game.pie_rule = true;
+        for notation in [
+            "give SDQH",
+            "put 2,3, give TDQH",
+            "swap",
+            "put 1,1, give SDRH",
+        ] {
+            let mv = Move::from_notation(notation, ArrayBase::One).unwrap();
+            game.make_move(mv).unwrap();
+            game.set_move_time(Duration::from_secs(1));
+        }
+        let mut rolled_back = game.clone();
+        rolled_back.rollback_to(2).unwrap();
+        assert_eq!(rolled_back.history(), &game.history()[..2]);
+        assert!(rolled_back.is_swap_decision());
+        assert_eq!(rolled_back.move_time(1), Some(Duration::from_secs(1)));
+        assert_eq!(rolled_back.move_time(2), None);
+        assert!(rolled_back.clone().rollback_to(3).is_err());
+        rolled_back.rollback_to(0).unwrap();
+        assert_eq!(rolled_back.field, Field::new());
+        assert_eq!(rolled_back.starting_player(), Player::PlayerTwo);
+
+        let field = Field::parse_grid("TLRF . . .\n. . . .\n. . . .\n. . . .").unwrap();
+        let mut set_up = Game::from_position(
+            field.clone(),
+            Player::PlayerOne,
+            Piece::from_code("SDQH").ok(),
+        )
+        .unwrap();
+        set_up
+            .do_move((1, 1), Piece::from_code("TDQH").unwrap())
+            .unwrap();
+        set_up.rollback_to(0).unwrap();
+        assert_eq!(set_up.field, field);
+        assert_eq!(set_up.next_piece(), Piece::from_code("SDQH").ok());
+    }
+
+    #[test]
+    fn test_rematch() {
+        let mut game = Game::new(Player::PlayerOne);
+        game.field.set_square_mode(true);
+        game.initial_move(game.remaining_pieces()[0]).unwrap();
+        let rematch = game.rematch(Player::PlayerTwo);
+        assert!(rematch.field.square_mode());
+        assert!(rematch.history().is_empty());
+        assert_eq!(rematch.remaining_pieces().len(), 16);
+        assert_eq!(rematch.player(), Player::PlayerTwo);
+    }
+
+    #[test]
+    fn test_quick_key() {
+        let keys: Vec<char> = Piece::all().map(quick_key).collect();
+        assert_eq!(keys.iter().collect::<String>(), "0123456789abcdef");
+        for key in keys {
+            assert_eq!(from_quick_key(key).map(quick_key), Some(key));
+        }
+        assert_eq!(from_quick_key('F'), from_quick_key('f'));
+        assert_eq!(from_quick_key('g'), None);
+    }
+
+    #[test]
+    fn test_pack() {
+        let mut game = Game::new(Player::PlayerTwo);
+        assert_eq!(Game::unpack(game.pack()).unwrap().status, game.status);
+        game.initial_move(Piece::from_code("TLRF").unwrap())
+            .unwrap();
+        game.do_move((2, 1), Piece::from_code("SDQH").unwrap())
+            .unwrap();
+        let packed = game.pack();
+        assert_eq!(packed >> 120, u128::from(PACK_VERSION));
+        // The occupied space, with the piece on it, and the piece to place
+        assert_eq!(packed & 0xFFFF, 1 << 6);
+        assert_eq!(packed >> (16 + 4 * 6) & 0xF, 15);
+        assert_eq!(packed >> 80 & 0x1F, 0x10);
+
+        let unpacked = Game::unpack(packed).unwrap();
+        assert_eq!(unpacked.field, game.field);
+        assert_eq!(unpacked.status, game.status);
+        assert_eq!(unpacked.remaining_pieces(), game.remaining_pieces());
+        assert_eq!(unpacked.pack(), packed);
+        assert_eq!(unpacked.setup(), Some(packed));
+        assert_eq!(game.setup(), None);
+
+        // Another version, or the same piece twice
+        assert!(Game::unpack(packed ^ 1 << 122).is_err());
+        assert!(Game::unpack(packed | 0xF << 80).is_err());
+
+        // Version 1, with 4 bits per score
+        let version_1 = (packed & !(0xFF << 120)) | 1 << 120 | 3 << 89 | 5 << 93;
+        assert_eq!(Game::unpack(version_1).unwrap().scores, [3, 5]);
+    }
+
+    #[test]
+    fn test_pack_finished() {
+        let field = Field::parse_grid("TLRF SDRH TDRF .\n. . . .\n. . . .\n. . . .").unwrap();
+        let mut won =
+            Game::from_position(field, Player::PlayerOne, Piece::from_code("SLRH").ok()).unwrap();
+        won.do_move((3, 0), Piece::from_code("SDQH").unwrap())
+            .unwrap();
+        assert_eq!(won.winner(), Some(Player::PlayerOne));
+        let unpacked = Game::unpack(won.pack()).unwrap();
+        assert_eq!(unpacked.status, won.status);
+        assert_eq!(unpacked.field, won.field);
+        assert_eq!(unpacked.pack(), won.pack());
+
+        let mut drawn = Game::new(Player::PlayerOne);
+        drawn.status = Status::Draw {
+            last_player: Player::PlayerTwo,
+        };
+        drawn.field = won.field.clone();
+        drawn
+            .remaining_pieces
+            .retain(|piece| !won.field.pieces().contains(piece));
+        let unpacked = Game::unpack(drawn.pack()).unwrap();
+        assert_eq!(unpacked.status, drawn.status);
+        assert_eq!(unpacked.remaining_pieces(), drawn.remaining_pieces());
+
+        // Scores that didn't fit into 4 bits
+        let mut scored = Game::new(Player::PlayerOne);
+        scored.scores = [16, 28];
+        assert_eq!(Game::unpack(scored.pack()).unwrap().scores, [16, 28]);
+    }
+
+    /// The moves of a game that ends in a dead draw with the last one, three spaces still empty
+    const DEAD_DRAW: &str = "give SDRH; put 1,1, give TLRF; put 3,3, give SLRF; \
+        put 1,4, give SLRH; put 2,2, give TLRH; put 3,2, give TDRH; put 3,4, give SLQF; \
+        put 4,1, give TLQH; put 4,3, give SDRF; put 2,3, give TDRF; put 4,2, give TDQH; \
+        put 1,3, give SLQH; put 3,1, give TDQF";
+
+    /// Plays [`DEAD_DRAW`], giving `TLQF` with the placement on 2,1 that ends it.
+    fn dead_draw() -> Game {
+        let mut game = Game::new(Player::PlayerOne);
+        for mv in DEAD_DRAW.split(';') {
+            game.make_move(Move::from_notation(mv.trim(), ArrayBase::One).unwrap())
+                .unwrap();
+        }
+        game.do_move((1, 0), Piece::from_code("TLQF").unwrap())
+            .unwrap();
+        game
+    }
+
+    #[test]
+    fn test_dead_draw() {
+        let game = dead_draw();
+        assert!(matches!(game.status, Status::Draw { .. }));
+        assert_eq!(game.field.empty_spaces().len(), 3);
+        // The piece named with the last placement was never given.
+        assert_eq!(game.remaining_pieces().len(), 3);
+        assert!(game
+            .remaining_pieces()
+            .contains(&Piece::from_code("TLQF").unwrap()));
+
+        let mut replayed = game.clone();
+        replayed.rollback_to(game.history().len()).unwrap();
+        assert_eq!(replayed.remaining_pieces(), game.remaining_pieces());
+        assert_eq!(replayed.status, game.status);
+        let unpacked = Game::unpack(game.pack()).unwrap();
+        assert_eq!(unpacked.remaining_pieces(), game.remaining_pieces());
+        #[cfg(feature = "unstable")]
+        {
+            let shared = Game::from_share_code(&game.to_share_code(), &game).unwrap();
+            assert_eq!(shared.remaining_pieces(), game.remaining_pieces());
+            assert_eq!(shared.history(), game.history());
+        }
+    }
+
+    #[test]
+    fn test_from_position() {
+        let field = Field::parse_grid(
+            "TLRF . . .
+             . . . .
+             . . . .
+             . . . SDQH",
+        )
+        .unwrap();
+        let next_piece = Piece::from_code("TDQH").unwrap();
+        let game = Game::from_position(field.clone(), Player::PlayerTwo, Some(next_piece)).unwrap();
+        assert_eq!(game.remaining_pieces().len(), 13);
+        assert!(!game.remaining_pieces().contains(&next_piece));
+        assert_eq!(game.next_piece(), Some(next_piece));
+        assert_eq!(game.player(), Player::PlayerTwo);
+
+        let used = Piece::from_code("TLRF").unwrap();
+        assert!(Game::from_position(field.clone(), Player::PlayerTwo, Some(used)).is_err());
+        assert!(Game::from_position(field, Player::PlayerTwo, None).is_err());
+        assert!(Game::from_position(Field::new(), Player::PlayerTwo, None)
+            .unwrap()
+            .is_initial_move());
+    }
+
+    #[test]
+    fn test_pie_rule() {
+        let mut game = Game::new(Player::PlayerOne);
+        game.pie_rule = true;
+        let pieces = game.remaining_pieces().to_vec();
+        game.initial_move(pieces[0]).unwrap();
+        assert!(game.decide_swap(true).is_err());
+        game.do_move((0, 0), pieces[1]).unwrap();
+        assert!(game.is_swap_decision());
+        assert_eq!(game.player(), Player::PlayerOne);
+        assert_eq!(game.next_piece(), Some(pieces[1]));
+        assert!(game.do_move((1, 0), pieces[2]).is_err());
+
+        let mut kept = game.clone();
+        kept.decide_swap(false).unwrap();
+        assert_eq!(kept.player(), Player::PlayerOne);
+
+        game.decide_swap(true).unwrap();
+        assert_eq!(game.player(), Player::PlayerTwo);
+        assert_eq!(game.next_piece(), Some(pieces[1]));
+        assert_eq!(game.history()[2].to_notation(ArrayBase::One), "swap");
+
+        // Only the first placement can be swapped.
+        game.do_move((1, 0), pieces[2]).unwrap();
+        assert!(!game.is_swap_decision());
+    }
+
+    #[test]
+    fn test_pie_rule_setup() {
+        let field = Field::parse_grid("TLRF . . .\n. . . .\n. . . .\n. . . SDQH").unwrap();
+        let mut game =
+            Game::from_position(field, Player::PlayerTwo, Piece::from_code("TDQH").ok()).unwrap();
+        game.pie_rule = true;
+        let piece = game.remaining_pieces()[0];
+        game.do_move((1, 1), piece).unwrap();
+        assert!(!game.is_swap_decision());
+        game.rollback_to(1).unwrap();
+        assert!(!game.is_swap_decision());
+        assert_eq!(game.next_piece(), Some(piece));
+
+        // An empty set-up field has its first placement still to come.
+        let mut game = Game::from_position(Field::new(), Player::PlayerOne, None).unwrap();
+        game.pie_rule = true;
+        let pieces = game.remaining_pieces().to_vec();
+        game.initial_move(pieces[0]).unwrap();
+        game.do_move((0, 0), pieces[1]).unwrap();
+        assert!(game.is_swap_decision());
+        game.rollback_to(2).unwrap();
+        assert!(game.is_swap_decision());
+    }
+
+    #[test]
+    fn test_blind() {
+        let mut game = Game::new(Player::PlayerOne);
+        game.hidden_property = Some(Property::Full);
+        let hollow = Piece::from_code("TLRH").unwrap();
+        let full = Piece::from_code("TLRF").unwrap();
+        assert_eq!(game.hidden_from(Player::PlayerTwo), None);
+        game.initial_move(hollow).unwrap();
+        assert_eq!(game.hidden_from(Player::PlayerTwo), Some(Property::Full));
+        assert_eq!(game.hidden_from(Player::PlayerOne), None);
+        assert_eq!(game.hand_candidates(), [hollow, full]);
+
+        game.do_move((0, 0), full).unwrap();
+        assert_eq!(game.hand_candidates(), [full]);
+    }
+
+    #[test]
+    fn test_last_piece_wins() {
+        let field = Field::parse_grid(
+            "TLRF TLRH TLQF TLQH
+             TDRF TDRH TDQF TDQH
+             SLRF SLRH SLQF SLQH
+             SDRF SDRH SDQF .",
+        )
+        .unwrap();
+        let last = Piece::from_code("SDQH").unwrap();
+        let mut game = Game::from_position(field, Player::PlayerTwo, Some(last)).unwrap();
+        game.do_move((3, 3), last).unwrap();
+        assert_eq!(game.winner(), Some(Player::PlayerTwo));
+    }
+
+    #[test]
+    fn test_race_to() {
+        let mut game = Game::new(Player::PlayerOne);
+        game.race_to = Some(2);
+        let piece = |code| Piece::from_code(code).unwrap();
+        game.initial_move(piece("TLRF")).unwrap();
+        game.do_move((0, 0), piece("TLRH")).unwrap();
+        game.do_move((1, 0), piece("TLQF")).unwrap();
+        game.do_move((2, 0), piece("TLQH")).unwrap();
+        game.do_move((3, 0), piece("SDQH")).unwrap();
+        // The row scored for the first player and was cleared, the game goes on.
+        assert!(game.running());
+        assert_eq!(game.score(Player::PlayerOne), 1);
+        assert_eq!(game.score(Player::PlayerTwo), 0);
+        assert!(game.field.pieces().is_empty());
+        assert_eq!(game.player(), Player::PlayerTwo);
+        assert!(game.do_move((0, 0), piece("TLRF")).is_err());
+    }
+
+    #[test]
+    fn test_forced_win_in() {
+        let field = Field::parse_grid(
+            "TLRF TLRH TLQF .
+             .    .    .    .
+             .    .    .    .
+             .    .    .    .",
+        )
+        .unwrap();
+        let game =
+            Game::from_position(field, Player::PlayerOne, Piece::from_code("TDQH").ok()).unwrap();
+        assert!(game.forced_win_in(1));
+        assert!(!game.forced_win_in(0));
+
+        let field = Field::parse_grid(
+            "SDQF .    .    .
+             SLQH .    SLRH TLQH
+             TDRF .    .    TLRH
+             SDQH .    .    .",
+        )
+        .unwrap();
+        let game =
+            Game::from_position(field, Player::PlayerOne, Piece::from_code("SDRF").ok()).unwrap();
+        assert!(!game.forced_win_in(2));
+        assert!(game.forced_win_in(3));
+        assert!(!game.wins_within(Player::PlayerTwo, 4));
+    }
+
+    #[test]
+    fn test_danger_matrix() {
+        let field = Field::parse_grid(
+            "TLRF TLRH TLQF .
+             .    .    .    .
+             .    .    .    .
+             .    .    .    .",
+        )
+        .unwrap();
+        let game =
+            Game::from_position(field, Player::PlayerOne, Piece::from_code("SDQH").ok()).unwrap();
+        let matrix = game.danger_matrix();
+        assert_eq!(matrix.len(), game.remaining_pieces().len());
+        for (piece, spaces) in matrix {
+            // Only tall or light pieces complete the top row.
+            let shares = piece.code().starts_with('T') || piece.code()[1..].starts_with('L');
+            assert_eq!(spaces, if shares { vec![(3, 0)] } else { vec![] });
+        }
+    }
 }
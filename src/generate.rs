@@ -0,0 +1,295 @@
+//! Random positions for benchmarks, puzzles and datasets (`quarto generate`). Positions are
+//! reached by random playouts from the start, so every one of them can occur in a game, and are
+//! kept if they match the [`Filters`]. They are written as lines of a [suite](crate::suite).
+//!
+//! With the `win` filter, the positions are puzzles: they are [graded](crate::puzzle), with the
+//! winning moves as best moves, and sorted from easy to hard.
+
+use std::ops::RangeInclusive;
+
+use crate::{
+    field::Field,
+    game::{Game, Move, Phase, Player},
+    piece::{Piece, Property},
+    puzzle::{Difficulty, Grade},
+    rng::RomuDuoJrRand,
+    suite::{MovePattern, TestPosition},
+};
+
+/// How many playouts may fail the filters per position, before giving up
+const ATTEMPTS: usize = 1000;
+
+/// The deepest forced win puzzles may have, deeper searches take too long
+const MAX_WIN: usize = 5;
+
+/// What generated positions have to match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filters {
+    /// The number of pieces placed so far
+    pub plies: RangeInclusive<usize>,
+    /// The piece to place can't complete a line right away
+    pub quiet: bool,
+    /// Each property is set on as many pieces on the field as it is unset, give or take one
+    pub balanced: bool,
+    /// The side to move can force a win within this many moves of both players
+    pub win: Option<usize>,
+    /// The [phase](Game::phase) of the game
+    pub phase: Option<Phase>,
+}
+
+impl Default for Filters {
+    fn default() -> Self {
+        Self {
+            plies: 1..=Field::SIZE * Field::SIZE - 1,
+            quiet: false,
+            balanced: false,
+            win: None,
+            phase: None,
+        }
+    }
+}
+
+impl Filters {
+    /// Parses filters such as `plies=4-8 quiet balanced`, or `plies=6` for an exact ply,
+    /// `phase=endgame`, and `win=3` for puzzles.
+    pub fn parse<'a>(words: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut filters = Self::default();
+        for word in words {
+            match word.split_once('=') {
+                None if word == "quiet" => filters.quiet = true,
+                None if word == "balanced" => filters.balanced = true,
+                Some(("plies", range)) => {
+                    let err = || format!("invalid plies `{range}`, such as 4-8");
+                    let (min, max) = range.split_once('-').unwrap_or((range, range));
+                    let min: usize = min.parse().map_err(|_| err())?;
+                    let max: usize = max.parse().map_err(|_| err())?;
+                    if min == 0 || min > max || max >= Field::SIZE * Field::SIZE {
+                        return Err(err());
+                    }
+                    filters.plies = min..=max;
+                }
+                Some(("win", plies)) => match plies.parse() {
+                    Ok(plies @ 1..=MAX_WIN) => filters.win = Some(plies),
+                    _ => return Err(format!("invalid win `{plies}`, from 1 to {MAX_WIN} moves")),
+                },
+                Some(("phase", phase)) => {
+                    filters.phase = Some(Phase::from_name(phase).map_err(|()| {
+                        format!("invalid phase `{phase}`, opening, midgame or endgame")
+                    })?);
+                }
+                _ => return Err(format!("unknown filter `{word}`")),
+            }
+        }
+        Ok(filters)
+    }
+
+    /// Returns true if the game, with a piece to place rather than a swap decision, matches all
+    /// filters.
+    pub fn matches(&self, game: &Game) -> bool {
+        let Some(hand) = game.next_piece().filter(|_| !game.is_swap_decision()) else {
+            return false;
+        };
+        if !self.plies.contains(&placements(game)) {
+            return false;
+        }
+        if self.quiet && !game.field.winning_spaces(hand).is_empty() {
+            return false;
+        }
+        if self.phase.is_some_and(|phase| game.phase() != phase) {
+            return false;
+        }
+        let pieces = game.field.pieces();
+        let balanced = !self.balanced
+            || [
+                Property::Tall,
+                Property::Round,
+                Property::Full,
+                Property::Light,
+            ]
+            .into_iter()
+            .all(|prop| {
+                let set = pieces.iter().filter(|piece| piece.get(prop)).count();
+                (2 * set).abs_diff(pieces.len()) <= 1
+            });
+        balanced && self.win.is_none_or(|plies| game.forced_win_in(plies))
+    }
+}
+
+/// The number of pieces placed so far, including those cleared in the scoring variant
+pub fn placements(game: &Game) -> usize {
+    game.history()
+        .iter()
+        .filter(|mv| matches!(mv, Move::Place { .. }))
+        .count()
+}
+
+/// Plays random moves with the rules of `base` until `plies` pieces are placed or the game ends,
+/// starting with a random player.
+pub fn playout(base: &Game, plies: usize, rng: &mut RomuDuoJrRand) -> Game {
+    let mut game = base.rematch(rng.choose([Player::PlayerOne, Player::PlayerTwo]));
+    game.initial_move(rng.choose(game.remaining_pieces().to_vec()))
+        .unwrap();
+    while placements(&game) < plies && game.running() {
+        if game.is_swap_decision() {
+            game.decide_swap(rng.below(2) == 1).unwrap();
+            continue;
+        }
+        let pos = rng.choose(game.field.empty_spaces());
+        let next_piece: Piece = match game.remaining_pieces() {
+            [] => game.next_piece().unwrap(),
+            remaining => rng.choose(remaining.to_vec()),
+        };
+        game.do_move(pos, next_piece).unwrap();
+    }
+    game
+}
+
+/// Generates `count` positions with the rules of `base` matching `filters`. Not for the
+/// scoring variant, as suites have no place for the points.
+pub fn generate(
+    base: &Game,
+    filters: &Filters,
+    count: usize,
+    rng: &mut RomuDuoJrRand,
+) -> Result<Vec<TestPosition>, String> {
+    if base.race_to.is_some() {
+        return Err("Positions of the scoring variant can't be written to a suite".to_string());
+    }
+    let plies: Vec<usize> = filters.plies.clone().collect();
+    let mut ret = vec![];
+    let mut failed = 0;
+    while ret.len() < count {
+        let plies = rng.choose(plies.iter().copied());
+        let game = playout(base, plies, rng);
+        match Some(game).filter(|game| game.running() && filters.matches(game)) {
+            Some(game) => {
+                failed = 0;
+                ret.push(TestPosition {
+                    game,
+                    best_moves: vec![],
+                    avoid_moves: vec![],
+                    safe: false,
+                    forced_win: None,
+                    metadata: vec![],
+                });
+            }
+            None if failed < ATTEMPTS => failed += 1,
+            None => {
+                return Err(format!(
+                    "No position matching the filters found in {ATTEMPTS} playouts"
+                ))
+            }
+        }
+    }
+    if let Some(plies) = filters.win {
+        let mut graded: Vec<_> = ret
+            .into_iter()
+            .map(|position| {
+                let grade = Grade::of(&position.game, plies).expect("puzzles have a forced win");
+                annotate(position, &grade)
+            })
+            .collect();
+        graded.sort_by_key(|(_, difficulty)| *difficulty);
+        ret = graded.into_iter().map(|(position, _)| position).collect();
+    }
+    for (i, position) in ret.iter_mut().enumerate() {
+        position
+            .metadata
+            .insert(0, ("id".to_string(), format!("generated {}", i + 1)));
+    }
+    Ok(ret)
+}
+
+/// Adds the winning moves and the difficulty of a puzzle
+fn annotate(mut position: TestPosition, grade: &Grade) -> (TestPosition, Difficulty) {
+    position.forced_win = Some(grade.depth);
+    position.best_moves = grade
+        .winning_moves
+        .iter()
+        .map(|&((x, y), next_piece)| MovePattern {
+            x: Some(x),
+            y: Some(y),
+            next_piece,
+        })
+        .collect();
+    let difficulty = grade.difficulty();
+    position
+        .metadata
+        .push(("difficulty".to_string(), difficulty.to_string()));
+    position.metadata.push(("c0".to_string(), grade.describe()));
+    (position, difficulty)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::{Game, Phase, Player},
+        rng::RomuDuoJrRand,
+        rules::Ruleset,
+        suite::TestPosition,
+    };
+
+    use super::{generate, placements, Filters};
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(Filters::parse([]).unwrap(), Filters::default());
+        let filters = Filters::parse(["plies=4-8", "quiet"]).unwrap();
+        assert_eq!(filters.plies, 4..=8);
+        assert!(filters.quiet && !filters.balanced);
+        assert_eq!(Filters::parse(["plies=6"]).unwrap().plies, 6..=6);
+        assert_eq!(Filters::parse(["win=3"]).unwrap().win, Some(3));
+        assert_eq!(
+            Filters::parse(["phase=opening"]).unwrap().phase,
+            Some(Phase::Opening)
+        );
+        for invalid in [
+            "plies=8-4",
+            "plies=0",
+            "plies=16",
+            "plies=x",
+            "loud",
+            "win=0",
+            "win=9",
+            "phase=late",
+        ] {
+            assert!(Filters::parse([invalid]).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        let rules = Ruleset::from_notation("s").unwrap();
+        let base = Game::with_rules(Player::PlayerOne, &rules).unwrap();
+        let filters = Filters::parse(["plies=6-9", "quiet", "balanced"]).unwrap();
+        let mut rng = RomuDuoJrRand::with_seed(1337);
+        let positions = generate(&base, &filters, 20, &mut rng).unwrap();
+        assert_eq!(positions.len(), 20);
+        for position in positions {
+            assert!(filters.matches(&position.game));
+            assert!((6..=9).contains(&placements(&position.game)));
+            let parsed = TestPosition::parse(&position.to_line()).unwrap();
+            assert_eq!(parsed.game.field, position.game.field);
+            assert_eq!(parsed.game.rules(), rules);
+        }
+
+        let puzzles = Filters::parse(["plies=8-10", "win=3"]).unwrap();
+        let positions = generate(&base, &puzzles, 5, &mut rng).unwrap();
+        let difficulties: Vec<&str> = positions
+            .iter()
+            .map(|position| {
+                assert!(position.game.forced_win_in(3));
+                assert!(!position.best_moves.is_empty());
+                position.metadata[1].1.as_str()
+            })
+            .collect();
+        let order = ["easy", "medium", "hard"];
+        assert!(difficulties.is_sorted_by_key(|d| order.iter().position(|o| o == d)));
+
+        let race = Game::with_rules(
+            Player::PlayerOne,
+            &Ruleset::from_notation("race=2").unwrap(),
+        );
+        assert!(generate(&race.unwrap(), &filters, 1, &mut rng).is_err());
+    }
+}
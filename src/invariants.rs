@@ -0,0 +1,188 @@
+//! Invariants any analysis of a position has to keep: symmetric positions, and positions with
+//! the properties relabeled, have the same outcome. The helpers here transform positions, the
+//! checks test the win detection, the forced win search and the canonical keys of
+//! [`tree`] against them, on any position, such as those from [`positions`].
+//!
+//! A relabeling renames the properties, such as calling tall pieces light and light pieces tall,
+//! and may swap the values of some, such as calling tall pieces short. Lines share a property
+//! after relabeling whenever they did before.
+//!
+//! The crate's own tests run the checks on generated positions. Analysis code added to the
+//! crate should be checked here too, and code built on the crate can run them on its own
+//! positions.
+
+use crate::{
+    field::Field,
+    game::{Game, Player},
+    generate::{self, Filters},
+    piece::Piece,
+    rng::RomuDuoJrRand,
+    rules::Ruleset,
+    tree::{self, Symmetry},
+};
+
+/// A relabeling of the properties: bit `i` of a piece index moves to bit `order[i]`, then the
+/// bits in `flipped` are inverted
+#[derive(Debug, Copy, Clone)]
+pub struct Relabeling {
+    /// Where each bit of the piece index moves to, a permutation of 0 to 3
+    pub order: [usize; 4],
+    /// The bits inverted after moving them
+    pub flipped: u8,
+}
+
+impl Relabeling {
+    /// All 384 relabelings
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..256_usize)
+            .map(|n| std::array::from_fn(|i| n >> (2 * i) & 3))
+            .filter(|order: &[usize; 4]| (0..4).all(|i| order.contains(&i)))
+            .flat_map(|order| (0..16).map(move |flipped| Self { order, flipped }))
+    }
+
+    /// The piece `piece` is called after the relabeling
+    pub fn apply(self, piece: Piece) -> Piece {
+        let index = (0..4).fold(0, |acc, i| acc | (piece.index() >> i & 1) << self.order[i]);
+        Piece::from_index(index ^ self.flipped).unwrap()
+    }
+}
+
+/// The position with the spaces rearranged by `symmetry` and the pieces relabeled
+pub fn transform(game: &Game, symmetry: &Symmetry, relabeling: Relabeling) -> Game {
+    let mut field = game.field.emptied();
+    for (x, y) in (0..Field::SIZE).flat_map(|y| (0..Field::SIZE).map(move |x| (x, y))) {
+        if let Some(piece) = game.field.get((x, y)) {
+            let space = symmetry[y * Field::SIZE + x];
+            field
+                .put(
+                    (space % Field::SIZE, space / Field::SIZE),
+                    relabeling.apply(piece),
+                )
+                .unwrap();
+        }
+    }
+    let hand = game.next_piece().map(|piece| relabeling.apply(piece));
+    let mut ret = Game::from_position(field, game.player(), hand).unwrap();
+    ret.set_rules(&game.rules());
+    ret
+}
+
+/// `count` generated positions to check the invariants on, with the rules in `rules` notation
+/// and the plies in the filter notation of [`Filters`], like `plies=6-12`
+pub fn positions(rules: &str, plies: &str, count: usize) -> Result<Vec<Game>, String> {
+    let rules = Ruleset::from_notation(rules)?;
+    let base = Game::with_rules(Player::PlayerOne, &rules)?;
+    let filters = Filters::parse([plies])?;
+    let mut rng = RomuDuoJrRand::with_seed(1337);
+    Ok(generate::generate(&base, &filters, count, &mut rng)?
+        .into_iter()
+        .map(|position| position.game)
+        .collect())
+}
+
+/// The error for `game` breaking an invariant, with the share code to reproduce it
+fn broken(what: &str, game: &Game, other: &Game) -> String {
+    format!(
+        "{what} differs between {} and its transformation {}",
+        game.to_share_code(),
+        other.to_share_code()
+    )
+}
+
+/// Checks that the win detection agrees on `game` and its symmetries, each with a relabeling
+/// picked by `rng`: whether the field is won, and the spaces winning with the piece in hand
+pub fn check_win_detection(game: &Game, rng: &mut RomuDuoJrRand) -> Result<(), String> {
+    let relabelings: Vec<Relabeling> = Relabeling::all().collect();
+    let wins = game
+        .next_piece()
+        .map(|hand| game.field.winning_spaces(hand).len());
+    for symmetry in tree::symmetries(&game.field) {
+        let other = transform(game, &symmetry, rng.choose(relabelings.iter().copied()));
+        if other.field.check_field_for_win() != game.field.check_field_for_win() {
+            return Err(broken("The win", game, &other));
+        }
+        let other_wins = other
+            .next_piece()
+            .map(|hand| other.field.winning_spaces(hand).len());
+        if other_wins != wins {
+            return Err(broken("The number of winning spaces", game, &other));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the forced win search within `plies` agrees on `game` and a symmetric and
+/// relabeled position picked by `rng`
+pub fn check_forced_win(game: &Game, plies: usize, rng: &mut RomuDuoJrRand) -> Result<(), String> {
+    let symmetries = tree::symmetries(&game.field);
+    let relabelings: Vec<Relabeling> = Relabeling::all().collect();
+    let other = transform(
+        game,
+        &rng.choose(symmetries.iter().copied()),
+        rng.choose(relabelings.iter().copied()),
+    );
+    if other.forced_win_in(plies) == game.forced_win_in(plies) {
+        Ok(())
+    } else {
+        Err(broken("The forced win", game, &other))
+    }
+}
+
+/// Checks that the canonical key of `game` is the same for all its symmetries
+pub fn check_canonical(game: &Game) -> Result<(), String> {
+    let identity = Relabeling {
+        order: [0, 1, 2, 3],
+        flipped: 0,
+    };
+    let symmetries = tree::symmetries(&game.field);
+    let canonical = tree::canonical(&game.field, &symmetries);
+    for symmetry in &symmetries {
+        let other = transform(game, symmetry, identity);
+        if tree::canonical(&other.field, &symmetries) != canonical {
+            return Err(broken("The canonical key", game, &other));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{piece::Piece, rng::RomuDuoJrRand};
+
+    use super::{check_canonical, check_forced_win, check_win_detection, positions, Relabeling};
+
+    #[test]
+    fn test_relabeling() {
+        assert_eq!(Relabeling::all().count(), 384);
+        for relabeling in Relabeling::all() {
+            let mut pieces: Vec<u8> = Piece::all().map(|p| relabeling.apply(p).index()).collect();
+            pieces.sort_unstable();
+            assert_eq!(pieces, (0..16).collect::<Vec<u8>>());
+        }
+    }
+
+    #[test]
+    fn test_win_detection_invariant() {
+        let mut rng = RomuDuoJrRand::with_seed(42);
+        for rules in ["-", "s", "plus"] {
+            for game in positions(rules, "plies=6-12", 10).unwrap() {
+                check_win_detection(&game, &mut rng).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_forced_win_invariant() {
+        let mut rng = RomuDuoJrRand::with_seed(42);
+        for game in positions("s", "plies=10-12", 10).unwrap() {
+            check_forced_win(&game, 3, &mut rng).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_canonical_invariant() {
+        for game in positions("-", "plies=3-9", 10).unwrap() {
+            check_canonical(&game).unwrap();
+        }
+    }
+}
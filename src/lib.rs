@@ -0,0 +1,124 @@
+/*!
+The rules of Quarto and its variants, and the engine playing them, without any input or output
+of their own: games, positions and their formats, the AI, puzzles and the analyses built on them.
+The `quarto` binary is the terminal front end to this library.
+
+The API comes in tiers:
+ * the stable core, always built and following semver: the [pieces](piece), the [field],
+   [games](game) with their [moves](Move), and the [rules] with their winning
+   [patterns](pattern). The most used types are re-exported here. Fallible functions return a
+   `String` error to show to the user, or `()` where there is only one way to fail,
+ * the formats, series, suites and analyses, [variation trees](variations), and the cached
+   [view](view::GameView) of a game for frontends, behind the `unstable` feature, which may
+   change in any release,
+ * the AI and everything built on it, puzzles, the position generator, the balance analyses,
+   the benchmark and the [invariants] checks, behind the `engine` feature, which implies
+   `unstable`.
+
+`engine` is a default feature, without default features only the stable core is built, for
+consumers that only need to play or check games.
+*/
+
+#![warn(clippy::cargo)]
+#![deny(clippy::cargo_common_metadata)]
+#![deny(rustdoc::broken_intra_doc_links)]
+#![deny(clippy::all)]
+#![deny(clippy::pedantic)]
+#![deny(unsafe_code)]
+#![allow(
+    clippy::unreadable_literal,
+    clippy::type_repetition_in_bounds,
+    clippy::missing_errors_doc,
+    clippy::missing_panics_doc,
+    clippy::missing_docs_in_private_items,
+    clippy::must_use_candidate,
+    clippy::return_self_not_must_use,
+    clippy::result_unit_err
+)]
+#![deny(
+    missing_debug_implementations,
+    missing_docs,
+    //trivial_casts,
+    trivial_numeric_casts,
+    unused_extern_crates,
+    unused_import_braces,
+    unused_qualifications,
+    unused_must_use,
+    //unused_results
+)]
+#![cfg_attr(
+    not(debug_assertions),
+    deny(
+        bad_style,
+        improper_ctypes,
+        non_shorthand_field_patterns,
+        no_mangle_generic_items,
+        overflowing_literals,
+        path_statements,
+        patterns_in_fns_without_body,
+        unconditional_recursion,
+        unused_allocation,
+        unused_comparisons,
+        unused_parens,
+        while_true
+    )
+)]
+
+// The stable core, following semver
+pub mod field;
+pub mod game;
+pub mod pattern;
+pub mod piece;
+pub mod rules;
+
+// May change in any release
+#[cfg(feature = "unstable")]
+pub mod convert;
+#[cfg(feature = "unstable")]
+pub mod facts;
+#[cfg(feature = "unstable")]
+pub mod options;
+#[cfg(feature = "unstable")]
+pub mod results;
+#[cfg(feature = "unstable")]
+pub mod series;
+#[cfg(feature = "unstable")]
+pub mod share;
+#[cfg(feature = "unstable")]
+pub mod suite;
+#[cfg(feature = "unstable")]
+pub mod tray;
+#[cfg(feature = "unstable")]
+pub mod tree;
+#[cfg(feature = "unstable")]
+pub mod validate;
+#[cfg(feature = "unstable")]
+pub mod variations;
+#[cfg(feature = "unstable")]
+pub mod version;
+#[cfg(feature = "unstable")]
+pub mod view;
+
+// The AI and what is built on it
+#[cfg(feature = "engine")]
+pub mod ai;
+#[cfg(feature = "engine")]
+pub mod balance;
+#[cfg(feature = "engine")]
+pub mod bench;
+#[cfg(feature = "engine")]
+pub mod generate;
+#[cfg(feature = "engine")]
+pub mod invariants;
+#[cfg(feature = "engine")]
+pub mod puzzle;
+#[cfg(feature = "engine")]
+pub mod rng;
+
+pub use crate::{
+    field::{Field, Pos},
+    game::{ArrayBase, Game, Move, Player, Status},
+    pattern::WinPattern,
+    piece::{GlyphSet, Piece, Property, PropertyNames},
+    rules::Ruleset,
+};
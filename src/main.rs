@@ -24,7 +24,6 @@ Welcome to `quarto_rs`
     unused_import_braces,
     unused_qualifications,
     unused_must_use,
-    missing_docs,
     //unused_results
 )]
 #![cfg_attr(
@@ -37,7 +36,6 @@ Welcome to `quarto_rs`
         overflowing_literals,
         path_statements,
         patterns_in_fns_without_body,
-        private_in_public,
         unconditional_recursion,
         unused_allocation,
         unused_comparisons,
@@ -46,30 +44,217 @@ Welcome to `quarto_rs`
     )
 )]
 
-mod ai;
-mod field;
-mod game;
-mod piece;
-mod rng;
-
-use std::{env::args, io::stdin};
+mod autosave;
+mod crash;
+mod dictate;
+mod engine_test;
+mod profile;
+mod quiz;
+mod referee;
+mod terminal;
+mod tutorial;
 
-use game::ArrayBase;
+use std::{
+    env::args,
+    fs::File,
+    io::{stdin, BufRead, BufReader, BufWriter, Write},
+    time::{Duration, Instant},
+};
 
-use crate::{
+use quarto::{
     ai::SimpleAi,
-    field::{try_parse_pos, Field},
-    game::{Game, Player, Status},
-    piece::Piece,
+    balance, bench, convert, facts,
+    field::{try_parse_pos, CellChange, Field, Pos, RenderStyle},
+    game::{from_quick_key, ArrayBase, Game, Player, Status},
+    generate, options,
+    pattern::WinPattern,
+    piece::{GlyphSet, Piece, Property},
+    results,
     rng::{time_nanos, RomuDuoJrRand},
+    rules::{self, Ruleset},
+    series::Series,
+    suite,
+    tray::TrayView,
+    tree, validate,
 };
 
+use crate::{
+    autosave::Autosave,
+    profile::{Outcome, Profile, DEFAULT_RATING},
+    referee::Bot,
+    tutorial::Lesson,
+};
+
+/// Sets the [options](options::OPTIONS) of the AI, first from the config file, then from the
+/// arguments. The config file is given with `--config`, or else `config` in the data dir, if
+/// there is one.
+fn parse_options(game: &mut Game) -> Result<(), String> {
+    let config = args().find_map(|x| x.strip_prefix("--config=").map(std::path::PathBuf::from));
+    let path = config
+        .clone()
+        .or_else(|| profile::data_dir().map(|dir| dir.join("config")));
+    if let Some(path) = path {
+        match std::fs::read_to_string(&path) {
+            Ok(text) => options::parse_config(game, &text)
+                .map_err(|err| format!("Invalid config {}: {err}", path.display()))?,
+            // Only a config file that was asked for has to exist.
+            Err(err) if config.is_some() || err.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!("Can't read config {}: {err}", path.display()));
+            }
+            Err(_) => (),
+        }
+    }
+    for option in &options::OPTIONS {
+        if let Some(value) = arg_value(&format!("--{}", option.name), option.short) {
+            option.set(game, &value)?;
+        }
+    }
+    Ok(())
+}
+
 fn main() {
+    crash::install_hook();
     if args().any(|x| x.contains("help") || x == "-h") {
-        let current_exe = std::env::current_exe().unwrap();
-        let current_exe_name = current_exe.file_name().unwrap().to_string_lossy();
-        println!(
-            "Your friendly Quarto game.
+        print_help();
+        return;
+    }
+
+    let pvp = args().any(|x| x == "--pvp" || x == "-p");
+    let mut profiles = vec![];
+    if let Some(names) = arg_value("--profile", "-n") {
+        for name in names.split(',') {
+            match Profile::load_or_create(name) {
+                Ok(profile) => profiles.push(profile),
+                Err(err) => {
+                    println!("{err}");
+                    return;
+                }
+            }
+        }
+        if profiles.len() > if pvp { 2 } else { 1 } {
+            println!("Too many profiles, one per human player.");
+            return;
+        }
+    }
+
+    // Before anything is parsed with it: as requested for this run, else the first player's
+    // preferred base, which only `quarto profile` changes.
+    let array_base = if args().any(|x| x == "--base0" || x == "-0") {
+        ArrayBase::Zero
+    } else {
+        profiles
+            .first()
+            .map_or(ArrayBase::One, |profile| profile.array_base)
+    };
+    let rules = parse_rules(array_base);
+    let mut game = match rules.and_then(|rules| Game::with_rules(Player::PlayerOne, &rules)) {
+        Ok(game) => game,
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+    game.array_base = array_base;
+
+    game.glyphs = if args().any(|x| x == "--ascii" || x == "-x") {
+        GlyphSet::Ascii
+    } else if args().any(|x| x == "--emoji" || x == "-u") {
+        GlyphSet::Emoji
+    } else {
+        terminal::glyphs()
+    };
+
+    if args().any(|x| x == "--paranoid" || x == "-z") {
+        game.verify_wins = true;
+    }
+
+    if args().any(|x| x == "--ai-reasoning" || x == "-r") {
+        game.ai_reasoning = true;
+    }
+
+    if let Err(err) = parse_options(&mut game) {
+        println!("{err}");
+        return;
+    }
+
+    let mut best_of = None;
+    if let Some(best_of_str) = arg_value("--best-of", "-b") {
+        match best_of_str.parse() {
+            Ok(n) if n > 0 => best_of = Some(n),
+            _ => {
+                println!("Invalid number of games: {best_of_str}");
+                return;
+            }
+        }
+    }
+
+    let transcript = arg_value("--transcript", "-t");
+
+    if run_command(&game) {
+        return;
+    }
+
+    game.pvp = pvp;
+    game.flipped = args().any(|x| x == "--flip" || x == "-f");
+    game.preview = args().any(|x| x == "--preview" || x == "-v");
+    game.quick_pick = args().any(|x| x == "--quick-pick" || x == "-k");
+
+    if args().any(|x| x == "--ai-simulation" || x == "-a") {
+        if game.pvp {
+            println!("PvP mode and ai-simulation don't match.. :)");
+        } else {
+            ai_simulation(&game, arg_value("--results", "-o").as_deref());
+        }
+        return;
+    }
+
+    let Ok((opened, human)) = open_game(&game).inspect_err(|err| println!("{err}")) else {
+        return;
+    };
+
+    play_series(
+        &game,
+        opened,
+        human,
+        best_of,
+        transcript.as_deref(),
+        profiles,
+    );
+}
+
+/// The game to start with, if not a new one: an imported position, a share code, or an
+/// interrupted game, with the side the human played.
+fn open_game(game: &Game) -> Result<(Option<Game>, Option<Player>), String> {
+    if args().nth(1).as_deref() == Some("import") {
+        return Ok((Some(import_position(game)?), None));
+    }
+    if let Some(code) = arg_value("--open", "-c") {
+        return Ok((Some(Game::from_share_code(&code, game)?), None));
+    }
+    let autosave = Autosave::load().unwrap_or_else(|err| {
+        println!("Could not resume the interrupted game: {err}");
+        None
+    });
+    let Some(autosave) = autosave else {
+        return Ok((None, None));
+    };
+    println!("An interrupted game was found. Resume it? [Y/n]");
+    if read_input().eq_ignore_ascii_case("n") {
+        Autosave::clear();
+        return Ok((None, None));
+    }
+    match Game::from_share_code(&autosave.code, game) {
+        Ok(opened) => Ok((Some(opened), Some(autosave.human))),
+        Err(err) => {
+            println!("Could not resume the game: {err}");
+            Autosave::clear();
+            Ok((None, None))
+        }
+    }
+}
+
+/// The rules, as the start of the help screen
+const ABOUT: &str = "Your friendly Quarto game.
 
 The game is played on a 4x4 board with 16 pieces. Each piece has four distinct
 characteristics: size (large/✋ or small/🤏), color (light/⬜ or dark/⬛),
@@ -84,161 +269,1204 @@ In the harder square mode, (-q), a square of 4 is also considered a win.
 
 If all 16 pieces have been placed and there is no winner, the game is a tie.
 
-Usage: {current_exe_name} <Options>
+";
+
+/// The commands and options, as the end of the help screen
+const COMMANDS: &str = "
+Commands:
+    learn:              Step-by-step tutorial teaching the rules, using the
+                        built-in lessons or the given lesson files.
+    profiles:           List all player profiles, with ratings.
+    profile <name> [base=0|base=1]: Show a player profile, with the results of
+                        all games, optionally changing the preferred base.
+    engine-test:        Run the AI against a suite of tactical positions, the
+                        built-in one or the given .qpd file (see suites/).
+    facts:              Show the known result with perfect play, for the given
+                        rules, such as - or s;pie (see src/rules.rs), or for
+                        the rules given by the options.
+    rules:              Describe the given rules, or those given by the
+                        options. With --json, as JSON for frontends: the board,
+                        the properties, the winning lines and patterns, and the
+                        variants (see src/rules.rs).
+    import:             Play on from a position, such as one from a physical
+                        board: four rows of piece codes, or . for empty
+                        spaces, optionally with the row and column numbers,
+                        then the piece to place. From the file, or typed in.
+                        With dictate, asks for each space in turn instead.
+    tree:               Count the positions reachable within depth placements,
+                        up to symmetry, for the rules given by the options,
+                        writing those ending the game to the file, if given.
+    generate:           Print random positions reachable in a game, for the
+                        rules given by the options, as a .qpd suite. Filters:
+                        plies=<n>-<m> pieces placed, quiet for no immediate
+                        win, balanced for as many pieces with each property
+                        as without it, phase=<opening|midgame|endgame>,
+                        win=<n> for puzzles with a forced win within n moves,
+                        graded and sorted from easy to hard.
+    bench:              Time searching positions for forced wins within plies
+                        moves, 3 by default, the hot path of the AI: 20
+                        positions from the seed, or the .qpd position given.
+                        Build with --profile profiling to run it under a
+                        profiler, for flamegraphs (see src/bench.rs).
+    quiz:               Drill recognizing lines: which property the pieces in a
+                        line share, and which pieces are unsafe to give, on
+                        random positions. Scored and timed, 10 questions by
+                        default.
+    balance:            Statistics for judging the rules given by the options:
+                        the ways to win, the length of games and the
+                        advantage of starting, from 1000 or the given number
+                        of random games, and a tenth as many AI games.
+    validate:           Referee a game played elsewhere, such as by bots: its
+                        moves, one per line as in transcripts, from the file
+                        or stdin. Prints a verdict per move and the final
+                        status, exits with 1 at the first illegal move.
+    convert:            Convert games or positions, one per line from the file
+                        or stdin, between the formats code (share codes),
+                        moves (moves separated by ;, as in results files)
+                        and qpd (positions, as in suites). Games convert to
+                        qpd as all their positions. Lines that don't convert
+                        are reported, and make it exit with 1.
+    referee:            Play a match between two bots, relaying their moves
+                        over stdin and stdout (see src/referee.rs), and print
+                        the transcript. Illegal moves, crashes and the time
+                        limit lose the game.
 
 Options:
     --square-mode|-q:   Enable harder rules: not only 4 of the same in a row,
                         but also a square of 4 is considered a win.
-    --base0|-0:         Starts to count at 0 instead of 1 (programmer style)
+    --base0|-0:         Starts to count at 0 instead of 1 (programmer style).
+                        Files, such as transcripts, always count from 1.
+    --flip|-f:          Show the field from the other side, with the last row
+                        on top. Type flip when placing a piece to turn it.
+    --ascii|-x:         Draw pieces with the letters of their codes, such as
+                        [TLRF], for terminals without emoji. By default, this
+                        is picked from TERM and the locale.
+    --emoji|-u:         Draw pieces with emoji, even if the terminal seems to
+                        lack them.
+    --property-names=<>|-y=<>: The words describing pieces, as four pairs in
+                        the order of the code letters, such as
+                        tall/short,light/dark,round/square,solid/hollow.
+    --preview|-v:       Preview each placement, marking the lines it completes
+                        or leaves a piece short, before confirming it.
+    --quick-pick|-k:    Pick pieces by a fixed key, a hex digit, and type a whole
+                        move at once: x, y and the key, such as 23f.
+    --pie-rule|-w:      After the first placement, the next player may swap
+                        sides, so the first player has to place the piece
+                        they gave away.
+    --blind=<prop>|-l=<prop>: Blind Quarto, the player placing a piece can't see
+                        its tall, light, round or full property until it's
+                        on the field.
+    --race-to=<n>|-g=<n>: Scoring variant, completing a line scores a point and
+                        clears it from the field. The first player to score n
+                        points wins, else the one with more points when all
+                        pieces are played.
+    --patterns=<list>|-m=<list>: Additional winning patterns, separated by ';'.
+                        Either a house rule: squares, corners, center or plus,
+                        or four x,y spaces separated by '+', winning wherever
+                        the shape fits, e.g. 1,1+2,1+3,1+2,2 for T shapes.
+                        Prefix the spaces with @ to only win right there.
+                        With -0, the spaces count from 0 as well.
     --ai-reasoning|-r:  Print information about what the AI is doing, and why,
                         during the game.
+    --paranoid|-z:      Check every win detection against a second one, working
+                        on the pieces' properties differently, stopping with a
+                        crash report if they disagree.
     --ai-simulation|-a: Simulate a bunch of AI battles.
-    --seed=<>|-s=<>:    Seed the AI RNG
+    --results=<>|-o=<>: With -a, write every game to the given file, as CSV, or
+                        as JSON if it ends in .json.
+    --seed=<>|-s=<>:    Seed the AI RNG, the same seed and options give the same
+                        games on every platform.
+    --temperature=<>|-e=<>: How the AI picks among moves that don't lose right
+                        away: 0 always picks the one leaving the most safe
+                        pieces, higher values pick more randomly. Without it,
+                        the AI picks uniformly.
+    --solver=<>|-d=<>:  With this many empty spaces or fewer, the AI plays
+                        perfectly by searching the game to the end, it uses
+                        heuristics before. From 0 to 8, defaults to 6.
+    --contempt=<>|-j=<>: How the solving AI picks among drawing moves, from -1
+                        to 1: positive values prefer those leaving the opponent
+                        more ways to go wrong, against weaker opponents,
+                        negative values the quiet ones, against stronger ones.
+    --config=<file>:    Read --property-names, --seed, --temperature, --solver
+                        and --contempt from a file, one `name = value` per
+                        line, such as `contempt = 0.5`. Defaults to
+                        `$QUARTO_HOME/config`, if it exists. Flags override it.
     --pvp|-p            No AI, just humans (player vs player)
+    --best-of=<>|-b=<>: Play a series of games, the players take turns in
+                        starting. Without it, a rematch is offered each game.
+    --time-limit=<>|-i=<>: The seconds a bot may take per move, with referee.
+    --transcript=<>|-t=<>: Write the moves of all games to the given file, with
+                        the time each move took.
+    --open=<code>|-c=<code>: Continue or review a game from its share code, as
+                        printed at the end of each game.
+    --profile=<>|-n=<>: Play as the named profile, keeping your rating and
+                        statistics. In PvP mode, give two names: -n=alice,bob
+                        The first profile's preferred base applies, unless -0
+                        is given.
     --help|-h:          Print this help screen.
 
 Good luck!
+";
+
+fn print_help() {
+    let current_exe = std::env::current_exe().unwrap();
+    let current_exe_name = current_exe.file_name().unwrap().to_string_lossy();
+    print!("{ABOUT}");
+    print!(
+        "Usage: {current_exe_name} <Options>
+       {current_exe_name} learn [<lesson files>] <Options>
+       {current_exe_name} profiles | profile <name> [base=0|base=1]
+       {current_exe_name} engine-test [<suite file>]
+       {current_exe_name} facts [<rules>] <Options>
+       {current_exe_name} rules [<rules>] [--json] <Options>
+       {current_exe_name} import [<board file>|dictate] <Options>
+       {current_exe_name} tree <depth> [<file>] <Options>
+       {current_exe_name} generate <count> [<filters>] <Options>
+       {current_exe_name} bench [<plies>] [<position>] <Options>
+       {current_exe_name} quiz [<questions>] <Options>
+       {current_exe_name} balance [<games>] <Options>
+       {current_exe_name} validate [<file>|-] <Options>
+       {current_exe_name} convert <from> <to> [<file>|-] <Options>
+       {current_exe_name} referee <bot command> <bot command> <Options>
 "
+    );
+    println!("{COMMANDS}");
+}
+
+/// Runs the subcommand given as the first argument, if it doesn't play games. Returns true if
+/// there was one.
+fn run_command(game: &Game) -> bool {
+    match args().nth(1).as_deref() {
+        Some("learn") => learn(game.render_style()),
+        Some("engine-test") => {
+            if !engine_test() {
+                std::process::exit(1);
+            }
+        }
+        Some("referee") => referee_match(game),
+        Some("validate") => {
+            if !validate_game(game) {
+                std::process::exit(1);
+            }
+        }
+        Some("facts") => {
+            let notation = args().nth(2).filter(|x| x == "-" || !x.starts_with('-'));
+            match notation.map_or_else(|| Ok(game.rules()), |n| Ruleset::from_notation(&n)) {
+                Ok(rules) => println!("{}", facts::describe(&rules)),
+                Err(err) => println!("{err}"),
+            }
+        }
+        Some("rules") => print_rules(game),
+        Some("tree") => count_tree(game),
+        Some("generate") => generate_positions(game),
+        Some("bench") => run_bench(game),
+        Some("quiz") => run_quiz(game),
+        Some("balance") => print_balance(game),
+        Some("profiles") => list_profiles(),
+        Some("profile") => edit_profile(),
+        Some("convert") => {
+            if !convert_records(game) {
+                std::process::exit(1);
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// Describes the rules given as argument, or those of `game`, as JSON with `--json`.
+fn print_rules(game: &Game) {
+    let notation = args().nth(2).filter(|x| x == "-" || !x.starts_with('-'));
+    let rules = match notation.map_or_else(|| Ok(game.rules()), |n| Ruleset::from_notation(&n)) {
+        Ok(rules) => rules,
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+    if let Err(err) = rules.validate() {
+        println!("{err}");
+        return;
+    }
+    if args().any(|x| x == "--json") {
+        print!("{}", rules.to_json(&game.property_names));
+        return;
+    }
+    println!("Rules {}", rules.notation());
+    let deviations = rules.deviations();
+    if deviations.is_empty() {
+        println!("The tournament-standard rules");
+    } else {
+        println!("Standard Quarto with {}", deviations.join(", "));
+    }
+    let (lines, combinations) =
+        balance::winning_combinations(&Game::with_rules(Player::PlayerOne, &rules).unwrap());
+    println!("Winning lines: {lines}, with a shared property: {combinations}");
+}
+
+/// Counts the positions reachable within the number of placements given as argument, writing
+/// those ending the game to the file given as second argument, in the board format of suites.
+fn count_tree(game: &Game) {
+    let mut params = args().skip(2).filter(|x| !x.starts_with('-'));
+    let Some(Ok(depth)) = params.next().map(|depth| depth.parse()) else {
+        println!("Please give the number of placements to count to, such as: tree 3");
+        return;
+    };
+    if game.race_to.is_some() {
+        println!("The scoring variant clears lines, counting positions doesn't support it.");
+        return;
+    }
+    let path = params.next();
+    let mut out = match path.as_deref().map(File::create).transpose() {
+        Ok(file) => file.map(BufWriter::new),
+        Err(err) => {
+            println!("Could not create {}: {err}", path.unwrap());
+            return;
+        }
+    };
+    let mut result = Ok(());
+    let counts = tree::count(&game.field, depth, |field| {
+        if let (Some(out), Ok(())) = (&mut out, &result) {
+            result = writeln!(out, "{}", suite::format_board(field));
+        }
+    });
+    println!("Ply  Positions  Ending the game");
+    for count in counts {
+        println!(
+            "{:>3}  {:>9}  {:>15}",
+            count.ply, count.positions, count.terminal
+        );
+    }
+    if let Err(err) = result.and_then(|()| out.map_or(Ok(()), |mut out| out.flush())) {
+        println!("Could not write to {}: {err}", path.unwrap());
+    }
+}
+
+/// Prints the balance statistics of the rules, from as many random games as given as argument,
+/// or 1000, and a tenth as many games of the AI against itself.
+fn print_balance(game: &Game) {
+    let games = match args().nth(2).filter(|x| !x.starts_with('-')) {
+        Some(games) => match games.parse() {
+            Ok(games) if games > 0 => games,
+            _ => {
+                println!("Invalid number of games: {games}");
+                return;
+            }
+        },
+        None => 1000,
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let seed = game.seed.unwrap_or_else(|| time_nanos() as u64);
+    let mut rng = RomuDuoJrRand::with_seed(seed);
+    let (lines, combinations) = balance::winning_combinations(game);
+    println!("Rules {} (seed {seed})", game.rules().notation());
+    println!("Winning lines: {lines}, with a shared property: {combinations}");
+    let random = balance::random_play(game, games, &mut rng);
+    println!("Random play: {}", random.describe());
+    let ai = balance::self_play(game, games.div_ceil(10), &mut rng);
+    println!("AI self-play: {}", ai.describe());
+}
+
+/// Runs the property recognition drill, with as many questions as given as argument, or 10.
+fn run_quiz(game: &Game) {
+    let count = match args().nth(2).filter(|x| !x.starts_with('-')) {
+        Some(count) => match count.parse() {
+            Ok(count) if count > 0 => count,
+            _ => {
+                println!("Invalid number of questions: {count}");
+                return;
+            }
+        },
+        None => 10,
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let mut rng = RomuDuoJrRand::with_seed(game.seed.unwrap_or_else(|| time_nanos() as u64));
+    if let Err(err) = quiz::run(game, count, &mut rng) {
+        println!("{err}");
+    }
+}
+
+/// Plays a match between the bots whose commands are given after `referee`, printing the result
+/// of each game, then the transcript as the official record.
+fn referee_match(game: &Game) {
+    let commands: Vec<String> = args().skip(2).filter(|x| !x.starts_with('-')).collect();
+    let [one, two] = &commands[..] else {
+        println!(
+            "Please give the commands of both bots, such as: referee \"./bot --fast\" ./other"
         );
         return;
+    };
+    let limit = match arg_value("--time-limit", "-i").map(|secs| secs.parse::<f64>()) {
+        None => None,
+        Some(Ok(secs)) if secs > 0.0 => Some(Duration::from_secs_f64(secs)),
+        Some(_) => {
+            println!("Invalid time limit, give the seconds per move, such as -i=0.5");
+            return;
+        }
+    };
+    let best_of = arg_value("--best-of", "-b").map_or(1, |n| n.parse().unwrap_or(0));
+    if best_of == 0 {
+        println!("Invalid number of games");
+        return;
     }
+    let mut bots = match Bot::spawn(one).and_then(|one| Ok([one, Bot::spawn(two)?])) {
+        Ok(bots) => bots,
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
 
-    let mut game = Game::new(Player::PlayerOne);
+    let mut series = Series::new(
+        Some(best_of),
+        bots.each_ref().map(|bot| bot.command.clone()),
+    );
+    for number in 1.. {
+        if series.is_decided() {
+            break;
+        }
+        let next = game.rematch(series.next_starting_player());
+        let (finished, forfeit) = referee::play(next, &mut bots, limit);
+        let result = finished.winner().map_or_else(
+            || "draw".to_string(),
+            |winner| format!("{} won", series.label(winner)),
+        );
+        if let Some(forfeit) = forfeit {
+            println!("Game {number}: {result}, {forfeit}");
+            series.record_forfeit(finished, forfeit);
+        } else {
+            println!("Game {number}: {result}");
+            series.record(finished);
+        }
+    }
+    bots.iter_mut().for_each(Bot::quit);
 
-    if args().any(|x| x == "--ai-reasoning" || x == "-r") {
-        game.ai_reasoning = true;
+    println!();
+    print!("{}", series.transcript());
+    if let Some(path) = arg_value("--transcript", "-t") {
+        if let Err(err) = std::fs::write(&path, series.transcript()) {
+            println!("Could not write transcript to {path}: {err}");
+        }
+    }
+}
+
+/// Checks the game in the file given after `validate`, or on stdin with `-` or no file, printing
+/// a verdict per move and the final status. Returns false at the first illegal move.
+fn validate_game(game: &Game) -> bool {
+    let path = args().nth(2).filter(|x| x != "-" && !x.starts_with('-'));
+    let lines: Box<dyn Iterator<Item = String>> = match &path {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(s) => Box::new(
+                s.lines()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+            Err(err) => {
+                println!("Could not read {path}: {err}");
+                return false;
+            }
+        },
+        None => Box::new(stdin().lines().map_while(Result::ok)),
+    };
+    let result = validate::validate(game, lines, |mv, after| {
+        let player = after.history_with_players().last().unwrap().0;
+        let ply = after.history().len();
+        println!(
+            "{ply}. {player}: {}: legal",
+            mv.to_notation(validate::FILE_BASE)
+        );
+    });
+    match result {
+        Ok(after) => {
+            if after.running() {
+                println!(
+                    "Status: {}, in the {}",
+                    validate::describe(&after),
+                    after.phase()
+                );
+            } else {
+                println!("Status: {}", validate::describe(&after));
+            }
+            true
+        }
+        Err(illegal) => {
+            println!("Line {}: illegal, {}", illegal.line, illegal.reason);
+            false
+        }
     }
+}
 
-    if let Some(seed) = args().find(|x| x.starts_with("--seed") || x.starts_with("-s=")) {
-        let mut seed = seed.split('=');
-        let _ = seed.next();
-        let seed_str = seed.next().unwrap();
-        let Ok(seed) = seed_str.parse() else {
-            println!("Invalid seed: {seed_str}");
+/// Converts the lines of the file given as third argument, or stdin, from the format given as
+/// first argument to the one given as second, printing the converted lines and, at the end, how
+/// many converted. Returns false if any line didn't.
+fn convert_records(game: &Game) -> bool {
+    let params: Vec<String> = args().skip(2).filter(|x| !x.starts_with('-')).collect();
+    let formats = match &params[..] {
+        [from, to, ..] => convert::Format::parse(from).and_then(|from| {
+            let to = convert::Format::parse(to)?;
+            convert::check(from, to).map(|()| (from, to))
+        }),
+        _ => Err(
+            "Please give the formats to convert from and to, such as: convert code qpd".to_string(),
+        ),
+    };
+    let (from, to) = match formats {
+        Ok(formats) => formats,
+        Err(err) => {
+            eprintln!("{err}");
+            return false;
+        }
+    };
+    let lines: Box<dyn BufRead> = match params.get(2).filter(|&x| x != "-") {
+        Some(path) => match File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(err) => {
+                eprintln!("Could not read {path}: {err}");
+                return false;
+            }
+        },
+        None => Box::new(stdin().lock()),
+    };
+    let (mut records, mut errors) = (0, 0);
+    let mut out = BufWriter::new(std::io::stdout().lock());
+    for (idx, line) in lines.lines().map_while(Result::ok).enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        records += 1;
+        match convert::convert_line(game, from, to, &line, idx + 1) {
+            Ok(converted) => {
+                for converted in converted {
+                    if writeln!(out, "{converted}").is_err() {
+                        return false;
+                    }
+                }
+            }
+            Err(err) => {
+                errors += 1;
+                eprintln!("Line {}: {err}", idx + 1);
+            }
+        }
+    }
+    if out.flush().is_err() {
+        return false;
+    }
+    eprintln!(
+        "Converted {} of {records} lines, {errors} errors",
+        records - errors
+    );
+    errors == 0
+}
+
+/// Prints as many random positions as given as argument, matching the filters given after it.
+fn generate_positions(game: &Game) {
+    let params: Vec<String> = args().skip(2).filter(|x| !x.starts_with('-')).collect();
+    let Some(Ok(count)) = params.first().map(|count| count.parse()) else {
+        println!("Please give the number of positions to generate, such as: generate 10 quiet");
+        return;
+    };
+    let filters = match generate::Filters::parse(params[1..].iter().map(String::as_str)) {
+        Ok(filters) => filters,
+        Err(err) => {
+            println!("{err}");
             return;
-        };
-        game.seed = Some(seed);
+        }
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let seed = game.seed.unwrap_or_else(|| time_nanos() as u64);
+    let mut rng = RomuDuoJrRand::with_seed(seed);
+    match generate::generate(game, &filters, count, &mut rng) {
+        Ok(positions) => {
+            println!("# Generated with seed {seed}");
+            for position in positions {
+                println!("{}", position.to_line());
+            }
+        }
+        Err(err) => {
+            println!("{err}");
+            std::process::exit(1);
+        }
     }
+}
 
+/// Times the forced win search, within as many plies as given as argument, or 3, on the .qpd
+/// position given after it, or on the workload of the seed.
+fn run_bench(game: &Game) {
+    let params: Vec<String> = args().skip(2).filter(|x| !x.starts_with('-')).collect();
+    let plies = match params.first().map(|plies| plies.parse()) {
+        None => 3,
+        Some(Ok(plies)) => plies,
+        Some(Err(_)) => {
+            println!("Invalid number of plies: {}", params[0]);
+            return;
+        }
+    };
+    let positions = if let Some(line) = params.get(1) {
+        suite::TestPosition::parse(line).map(|position| vec![position])
+    } else {
+        let seed = game.seed.unwrap_or(1337);
+        println!("Workload of seed {seed}");
+        bench::workload(game, &mut RomuDuoJrRand::with_seed(seed))
+    };
+    let positions = match positions {
+        Ok(positions) => positions,
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+    let samples = bench::run(positions, plies, |sample| {
+        println!(
+            "{:>8.1}ms  {:<8}  {}",
+            sample.took.as_secs_f64() * 1000.0,
+            if sample.forced_win { "win" } else { "no win" },
+            sample.position.to_line()
+        );
+    });
+    println!("{}", bench::describe(&samples));
+}
+
+/// Runs the tutorial, with the lesson files given as arguments or the built-in ones.
+fn learn(style: RenderStyle) {
+    let lesson_files: Vec<String> = args().skip(2).filter(|x| !x.starts_with('-')).collect();
+    let lessons = if lesson_files.is_empty() {
+        Lesson::builtin()
+    } else {
+        let mut lessons = vec![];
+        for file in lesson_files {
+            let lesson = std::fs::read_to_string(&file)
+                .map_err(|e| e.to_string())
+                .and_then(|s| Lesson::parse(&s));
+            match lesson {
+                Ok(lesson) => lessons.push(lesson),
+                Err(e) => {
+                    println!("Invalid lesson file {file}: {e}");
+                    return;
+                }
+            }
+        }
+        lessons
+    };
+    tutorial::run(&lessons, style);
+}
+
+/// Parses the rule variants given on the command line, coordinates start at `array_base`.
+fn parse_rules(array_base: ArrayBase) -> Result<Ruleset, String> {
+    let mut rules = Ruleset::standard();
     if args().any(|x| x == "--square-mode" || x == "-q") {
-        game.field.square_mode = true;
+        rules.patterns.push(WinPattern::square());
     }
 
-    if args().any(|x| x == "--base0" || x == "-0") {
-        game.array_base = ArrayBase::Zero;
+    if args().any(|x| x == "--pie-rule" || x == "-w") {
+        rules.pie_rule = true;
     }
 
-    if args().any(|x| x == "--pvp" || x == "-p") {
-        game.pvp = true;
+    if let Some(patterns) = arg_value("--patterns", "-m") {
+        rules
+            .patterns
+            .extend(WinPattern::parse_list(&patterns, array_base)?);
     }
 
-    if args().any(|x| x == "--ai-simulation" || x == "-a") {
-        if game.pvp {
-            println!("PvP mode and ai-simulation don't match.. :)");
-        } else {
-            ai_simulation(&game);
+    if let Some(target) = arg_value("--race-to", "-g") {
+        match target.parse() {
+            Ok(n) => rules.race_to = Some(n),
+            _ => return Err(format!("Invalid number of points: {target}")),
+        }
+    }
+
+    if let Some(property) = arg_value("--blind", "-l") {
+        let property = Property::from_name(&property).map_err(|()| {
+            format!("Invalid property: {property}, use tall, light, round or full")
+        })?;
+        rules.hidden_property = Some(property);
+    }
+    Ok(rules)
+}
+
+/// Runs the given suite file, or the built-in suite, returns `true` if all positions passed.
+fn engine_test() -> bool {
+    let positions = if let Some(file) = args().nth(2).filter(|x| !x.starts_with('-')) {
+        match std::fs::read_to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|s| suite::parse_suite(&s))
+        {
+            Ok(positions) => positions,
+            Err(e) => {
+                println!("Invalid suite {file}: {e}");
+                return false;
+            }
         }
+    } else {
+        engine_test::builtin_suite()
+    };
+    engine_test::run_and_report(&positions)
+}
+
+/// Returns the value of a `--long=value` or `-s=value` argument
+fn arg_value(long: &str, short: &str) -> Option<String> {
+    args().find_map(|x| {
+        let (key, value) = x.split_once('=')?;
+        (key == long || key == short).then(|| value.to_string())
+    })
+}
+
+fn list_profiles() {
+    match Profile::list() {
+        Ok(profiles) if profiles.is_empty() => {
+            println!("No profiles yet, start a game with --profile=<name> to create one.");
+        }
+        Ok(profiles) => profiles
+            .iter()
+            .for_each(|profile| println!("{}", profile.describe())),
+        Err(err) => println!("{err}"),
+    }
+}
+
+/// Shows a profile, creating it if needed, and sets its preferences given as `key=value`.
+fn edit_profile() {
+    let Some(name) = args().nth(2) else {
+        println!("Usage: profile <name> [base=0|base=1]");
         return;
+    };
+    let mut profile = match Profile::load_or_create(&name) {
+        Ok(profile) => profile,
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+    for setting in args().skip(3) {
+        match setting.as_str() {
+            "base=0" => profile.array_base = ArrayBase::Zero,
+            "base=1" => profile.array_base = ArrayBase::One,
+            _ => {
+                println!("Unknown setting: {setting}");
+                return;
+            }
+        }
+    }
+    if let Err(err) = profile.save() {
+        println!("Could not save profile {name}: {err}");
+        return;
+    }
+    println!("{}", profile.describe());
+    for result in &profile.results {
+        println!(
+            "  {:?} against {} ({})",
+            result.outcome, result.opponent, result.opponent_rating
+        );
+    }
+}
+
+/// Records the result of a game in the profiles of the players, if they have one.
+fn record_results(profiles: &mut [Option<Profile>; 2], game: &Game, names: &[String; 2]) {
+    let ratings = profiles
+        .each_ref()
+        .map(|profile| profile.as_ref().map_or(DEFAULT_RATING, |p| p.rating));
+    for (idx, player) in [Player::PlayerOne, Player::PlayerTwo]
+        .into_iter()
+        .enumerate()
+    {
+        let Some(profile) = &mut profiles[idx] else {
+            continue;
+        };
+        let outcome = match game.winner() {
+            None => Outcome::Draw,
+            Some(winner) if winner == player => Outcome::Win,
+            Some(_) => Outcome::Loss,
+        };
+        profile.record(outcome, &names[1 - idx], ratings[1 - idx]);
+        if let Err(err) = profile.save() {
+            println!("Could not save profile {}: {err}", profile.name);
+        }
     }
+}
+
+/// Reads a position from a physical board, [dictated](dictate) with `import dictate`, or else
+/// typed as a grid, see [`read_position`]. Player 1 is to move.
+fn import_position(base: &Game) -> Result<Game, String> {
+    let (field, hand) = if args().nth(2).as_deref() == Some("dictate") {
+        dictate::dictate(base, || stdin().lines().next().and_then(Result::ok))?
+    } else {
+        read_position(base)?
+    };
 
-    play(game);
+    let mut pieces = field.pieces();
+    pieces.extend(hand);
+    let mut game = Game::from_position(field, Player::PlayerOne, hand).map_err(|()| {
+        let unused = Piece::all().filter(|piece| !pieces.contains(piece));
+        let deviations =
+            rules::audit_pieces(&pieces.iter().copied().chain(unused).collect::<Vec<_>>());
+        if deviations.is_empty() {
+            "Invalid position: a piece to place is needed".to_string()
+        } else {
+            format!("Invalid position: {}", deviations.join(", "))
+        }
+    })?;
+    game.copy_settings(base);
+    game.set_rules(&base.rules());
+    if game.field.check_field_for_win() {
+        return Err("Invalid position: it is already won".to_string());
+    }
+    let deviations = rules::audit(&game);
+    if !deviations.is_empty() {
+        println!(
+            "Note: not tournament-standard Quarto: {}",
+            deviations.join(", ")
+        );
+    }
+    Ok(game)
 }
 
-fn play(mut game: Game) {
-    let mut buf = String::new();
+/// Reads a position from the file given after `import`, or else from stdin: the grid, see
+/// [`Field::parse_labeled_grid`], and the piece to place on a line of its own, unless the board
+/// is empty.
+fn read_position(base: &Game) -> Result<(Field, Option<Piece>), String> {
+    let input = if let Some(path) = args().nth(2).filter(|x| !x.starts_with('-')) {
+        std::fs::read_to_string(&path).map_err(|err| format!("Could not read {path}: {err}"))?
+    } else {
+        println!(
+            "Type the board, a row per line with . for empty spaces, then the piece to place,"
+        );
+        println!("and finish with an empty line:");
+        let mut input = String::new();
+        for line in stdin().lines() {
+            let line = line.map_err(|err| err.to_string())?;
+            if line.trim().is_empty() && !input.trim().is_empty() {
+                break;
+            }
+            input.push_str(&line);
+            input.push('\n');
+        }
+        input
+    };
+
+    let mut lines: Vec<&str> = input.lines().filter(|l| !l.trim().is_empty()).collect();
+    // Rows of the board always have several spaces.
+    let hand = match lines.last() {
+        Some(line) if line.split_whitespace().count() == 1 => Some(lines.pop().unwrap().trim()),
+        _ => None,
+    };
+    let field = Field::parse_labeled_grid(&lines.join("\n"), base.array_base)
+        .map_err(|err| format!("Invalid board: {err}"))?;
+    let hand = hand
+        .map(|code| Piece::from_code(code).map_err(|()| format!("Invalid piece: {code}")))
+        .transpose()?;
+    Ok((field, hand))
+}
+
+/// Plays games until the series is decided, or the players don't want a rematch. The first game
+/// is `opened`, if given.
+fn play_series(
+    base_game: &Game,
+    mut opened: Option<Game>,
+    resumed_human: Option<Player>,
+    best_of: Option<u32>,
+    transcript: Option<&str>,
+    profiles: Vec<Profile>,
+) {
     #[allow(clippy::cast_possible_truncation)]
-    let seed = game.seed.unwrap_or_else(|| time_nanos() as u64);
+    let seed = base_game.seed.unwrap_or_else(|| time_nanos() as u64);
 
     println!("Game Seed: {seed}");
+    crash::track_seed(seed);
 
-    let human = RomuDuoJrRand::with_seed(seed).choose([Player::PlayerOne, Player::PlayerTwo]);
-    let mut ai = SimpleAi::with_seed(human.next(), seed);
+    let mut rng = RomuDuoJrRand::with_seed(seed);
+    let human = rng.choose([Player::PlayerOne, Player::PlayerTwo]);
+    let human = resumed_human.unwrap_or(human);
+    let mut ai_seed = seed;
 
-    if !game.pvp {
+    // Profiles belong to the human players, in order.
+    let mut profiles = profiles.into_iter();
+    let mut seats = [Player::PlayerOne, Player::PlayerTwo].map(|player| {
+        if base_game.pvp || player == human {
+            profiles.next()
+        } else {
+            None
+        }
+    });
+    let names = [Player::PlayerOne, Player::PlayerTwo].map(|player| {
+        if let Some(profile) = &seats[player.index()] {
+            profile.name.clone()
+        } else if base_game.pvp || player == human {
+            "Human".to_string()
+        } else {
+            "AI".to_string()
+        }
+    });
+    let mut series = Series::new(best_of, names.clone());
+
+    if !base_game.pvp {
         println!("You are {human}.");
     }
+    if let Some(best_of) = best_of {
+        println!("Best of {best_of} games.");
+    }
+
+    loop {
+        let mut ai = SimpleAi::with_seed(human.next(), ai_seed);
+        let game = opened
+            .take()
+            .unwrap_or_else(|| base_game.rematch(series.next_starting_player()));
+        let game = play(game, human, &mut ai);
+        record_results(&mut seats, &game, &names);
+        series.record(game);
+
+        println!();
+        println!("Score: {}", series.score());
+        if let Some(times) = series.thinking_times() {
+            println!("{times}");
+        }
+
+        if let Some(path) = transcript {
+            if let Err(err) = std::fs::write(path, series.transcript()) {
+                println!("Could not write transcript to {path}: {err}");
+            }
+        }
 
+        if series.is_decided() {
+            match series.winner() {
+                Some(winner) => println!("{} won the series!", series.label(winner)),
+                None => println!("The series ended in a draw!"),
+            }
+            return;
+        }
+
+        println!("Rematch? [Y/n]");
+        let mut buf = String::new();
+        if stdin().read_line(&mut buf).unwrap_or(0) == 0 || buf.trim().eq_ignore_ascii_case("n") {
+            return;
+        }
+        ai_seed = rng.next();
+    }
+}
+
+/// Plays a single game, returns the finished game.
+fn play(mut game: Game, human: Player, ai: &mut SimpleAi) -> Game {
     println!();
     println!("Let the games begin!");
 
-    loop {
-        game.pp();
+    'game: loop {
+        crash::track(&game);
+        if game.running() {
+            if let Err(err) = Autosave::save(&game, human) {
+                println!("Could not autosave the game: {err}");
+            }
+        } else {
+            Autosave::clear();
+        }
+        print!(
+            "{}",
+            game.render_for(if game.pvp { game.player() } else { human })
+        );
         if !game.running() {
-            return;
+            println!("Share this game with --open={}", game.to_share_code());
+            return game;
         }
+        let started = Instant::now();
 
         if game.pvp || game.player() == human {
-            if game.is_initial_move() {
+            if game.is_swap_decision() {
+                println!("Swap sides? [y/N]");
+                game.decide_swap(read_input().eq_ignore_ascii_case("y"))
+                    .unwrap();
+            } else if game.is_initial_move() {
                 let next_piece = read_piece(&game);
                 game.initial_move(next_piece).unwrap();
             } else {
                 loop {
-                    println!("Select x,y to put the piece to:");
-                    buf.clear();
-                    stdin().read_line(&mut buf).unwrap();
+                    println!(
+                        "Select x,y to put the piece to, ? to list the empty places, flip to turn the field, or undo:"
+                    );
+                    let input = read_input();
                     let base = game.array_base;
-                    let pos = try_parse_pos(&buf).map(|(x, y)| (base.unbased(x), base.unbased(y)));
+                    if input == "undo" {
+                        if take_back(&mut game) {
+                            continue 'game;
+                        }
+                        continue;
+                    }
+                    if input == "flip" {
+                        game.flipped = !game.flipped;
+                        print!("{}", game.field.render(game.render_style()));
+                        continue;
+                    }
+                    if input == "?" {
+                        print_empty_spaces(&game);
+                        continue;
+                    }
+                    let (pos, quick_piece) = match quick_move(&game, &input) {
+                        Some((pos, piece)) => (Ok(pos), piece),
+                        None => (
+                            try_parse_pos(&input).map(|(x, y)| (base.unbased(x), base.unbased(y))),
+                            None,
+                        ),
+                    };
                     if let Ok(pos) = pos {
-                        if pos.0 < Field::SIZE && pos.1 < Field::SIZE {
-                            let next_piece = read_piece(&game);
+                        if pos.0 < Field::SIZE
+                            && pos.1 < Field::SIZE
+                            && game.field.get(pos).is_none()
+                        {
+                            if game.preview
+                                && game.hidden_property.is_none()
+                                && !confirm_placement(&game, pos)
+                            {
+                                continue;
+                            }
+                            if game.hidden_property.is_some() {
+                                // Blind Quarto: the piece is revealed once placed.
+                                print!("Placed piece: ");
+                                print!("{}", game.next_piece().unwrap().render(game.glyphs));
+                                println!("\nRemaining Pieces:");
+                                print!("{}", game.render_remaining_pieces());
+                            }
+                            // There is nothing left to give with the last piece.
+                            let next_piece = match game.remaining_pieces() {
+                                [] => game.next_piece().unwrap(),
+                                _ => quick_piece.unwrap_or_else(|| read_piece(&game)),
+                            };
                             if game.do_move(pos, next_piece).is_ok() {
                                 break;
                             }
                         }
                     }
-                    println!("Illegal move! The x,y value must be an empty place on the field!");
+                    println!(
+                        "Illegal move! The x,y value must be an empty place on the field, from {0},{0} to {1},{1}!",
+                        base.based(0),
+                        base.based(Field::SIZE - 1)
+                    );
                     println!();
                 }
             }
             println!();
         } else {
+            let before = game.field.clone();
             game = ai.play_iteratively(&mut game);
+            print_reasoning(ai);
+            report_changes(&before.diff(&game.field), game.render_style());
         }
+        game.set_move_time(started.elapsed());
     }
 }
 
+/// Lists the spaces a piece can be put to, such as `Empty places: 1,1 2,1`.
+fn print_empty_spaces(game: &Game) {
+    let base = game.array_base;
+    let spaces: Vec<String> = game
+        .field
+        .empty_spaces()
+        .iter()
+        .map(|&(x, y)| format!("{},{}", base.based(x), base.based(y)))
+        .collect();
+    println!("Empty places: {}", spaces.join(" "));
+}
+
+/// Takes back the previous move of the player to move, and the opponent's moves since. Returns
+/// false if they made none.
+fn take_back(game: &mut Game) -> bool {
+    let player = game.player();
+    let previous = game
+        .history_with_players()
+        .iter()
+        .rposition(|&(mover, _)| mover == player);
+    if previous.is_some_and(|ply| game.rollback_to(ply).is_ok()) {
+        return true;
+    }
+    println!("There is no move of yours to take back.");
+    false
+}
+
+/// Prints why the AI made its last move, with [`Game::ai_reasoning`].
+fn print_reasoning(ai: &mut SimpleAi) {
+    for line in ai.take_reasoning() {
+        println!("{line}");
+    }
+}
+
+/// Tells what the AI changed on the field: the piece it put, or the pieces cleared when it
+/// scored with the scoring variant.
+fn report_changes(changes: &[CellChange], style: RenderStyle) {
+    let base = style.array_base;
+    let pos = |(x, y): Pos| format!("{},{}", base.based(x), base.based(y));
+    let cleared: Vec<String> = changes
+        .iter()
+        .filter(|change| change.after.is_none())
+        .map(|change| pos(change.pos))
+        .collect();
+    for change in changes {
+        if let Some(piece) = change.after {
+            println!(
+                "The AI put {} on {}.",
+                piece.render(style.glyphs),
+                pos(change.pos)
+            );
+        }
+    }
+    if !cleared.is_empty() {
+        println!("The AI scored, clearing {}.", cleared.join(" "));
+    }
+}
+
+/// Shows the field with the piece to place put at `pos`, marking the lines it would complete or
+/// leave one piece short, and asks whether to put it there.
+fn confirm_placement(game: &Game, pos: Pos) -> bool {
+    let mut field = game.field.clone();
+    field
+        .apply_patch(&[CellChange {
+            pos,
+            before: None,
+            after: game.next_piece(),
+        }])
+        .unwrap();
+    let completed: Vec<[Pos; 4]> = field
+        .completed_lines()
+        .into_iter()
+        .filter(|line| line.contains(&pos))
+        .collect();
+    let threatened: Vec<[Pos; 4]> = field
+        .threatened_lines()
+        .into_iter()
+        .filter(|line| line.contains(&pos))
+        .collect();
+    let highlighted: Vec<Pos> = completed
+        .iter()
+        .chain(&threatened)
+        .flatten()
+        .copied()
+        .collect();
+    print!(
+        "{}",
+        field.render_highlighted(game.render_style(), &highlighted)
+    );
+    let shared = |line: &[Pos; 4]| {
+        let values = Piece::mask_values(field.line_property_intersection(line));
+        game.property_names.join(&values)
+    };
+    if let Some(line) = completed.first() {
+        println!("This completes a line of {} pieces!", shared(line));
+    } else if let Some(line) = threatened.first() {
+        println!(
+            "This leaves a line of {} pieces one piece short, mind the piece you give!",
+            shared(line)
+        );
+    }
+    println!("Put the piece there? [Y/n]");
+    !read_input().eq_ignore_ascii_case("n")
+}
+
+/// Reads a trimmed line from stdin, quits once stdin is closed.
+fn read_input() -> String {
+    let mut buf = String::new();
+    match stdin().read_line(&mut buf) {
+        Ok(0) | Err(_) => {
+            println!();
+            println!("No more input, quitting.");
+            std::process::exit(0);
+        }
+        Ok(_) => buf.trim().to_string(),
+    }
+}
+
+/// Parses a move in quick-pick mode: the x and y digits, optionally followed by the key of the
+/// piece to give, such as `23f`.
+fn quick_move(game: &Game, input: &str) -> Option<(Pos, Option<Piece>)> {
+    if !game.quick_pick {
+        return None;
+    }
+    let mut chars = input.chars();
+    let mut coordinate = || {
+        let c = chars.next()?.to_digit(10)?;
+        Some(game.array_base.unbased(usize::try_from(c).unwrap()))
+    };
+    let pos = (coordinate()?, coordinate()?);
+    match (chars.next(), chars.next()) {
+        (None, _) => Some((pos, None)),
+        (Some(key), None) => Some((pos, Some(from_quick_key(key)?))),
+        _ => None,
+    }
+}
+
+/// Asks for the piece to give, by its id or its [`code`](Piece::code), or by its key in
+/// quick-pick mode. The remaining pieces can be listed in other [views](TrayView) meanwhile.
 fn read_piece(game: &Game) -> Piece {
-    let mut buf = String::with_capacity(16);
     let base = game.array_base;
-    let piece_id: usize = loop {
+    let mut view = TrayView::All;
+    loop {
+        if game.quick_pick {
+            println!(
+                "\n{}, please chose your opponent's next piece by its key (or list them: safe, sort, group <property>, all):",
+                game.player()
+            );
+            let input = read_input();
+            if let Some(new_view) = TrayView::parse(game, &input) {
+                view = new_view;
+                print!("{}", view.render(game));
+                continue;
+            }
+            let mut chars = input.chars();
+            if let (Some(key), None) = (chars.next(), chars.next()) {
+                if let Some(piece) = from_quick_key(key) {
+                    if game.remaining_pieces().contains(&piece) {
+                        return piece;
+                    }
+                }
+            }
+            println!("Illegal choice: '{input}', please pick the key of a remaining piece:");
+            print!("{}", view.render(game));
+            continue;
+        }
         println!(
-            "\n{}, please chose your opponent's next piece ({}-{}):",
+            "\n{}, please chose your opponent's next piece ({}-{}, or a code such as TLRF; or list them: safe, sort, group <property>, all):",
             game.player(),
             base.based(0),
             base.based(game.remaining_pieces().len() - 1),
         );
-        buf.clear();
-        stdin().read_line(&mut buf).unwrap();
-        let num = buf.trim().parse().map(|x| base.unbased(x));
-        if let Ok(num) = num {
-            if num < game.remaining_pieces().len() {
-                break num;
+        let input = read_input();
+        if let Some(new_view) = TrayView::parse(game, &input) {
+            view = new_view;
+            print!("{}", view.render(game));
+            continue;
+        }
+        if let Ok(piece) = Piece::from_code(&input) {
+            if game.remaining_pieces().contains(&piece) {
+                return piece;
             }
+            println!("The piece {} is no longer available.", piece.code());
+        }
+        let num = input.parse().map(|x| base.unbased(x));
+        if let Some(&piece) = num
+            .as_ref()
+            .ok()
+            .and_then(|&i| game.remaining_pieces().get(i))
+        {
+            return piece;
         }
-        let buf = buf.strip_suffix('\n').unwrap();
         #[cfg(debug_assertions)]
-        println!("{:?} (str: '{buf}')", num.err());
-        println!("Illegal choice: '{buf}', please pick the id of a remaining piece:");
-        game.pp_remaining_pieces();
-    };
-    game.remaining_pieces()[piece_id]
+        println!("{:?} (str: '{input}')", num.err());
+        println!("Illegal choice: '{input}', please pick the id of a remaining piece:");
+        print!("{}", view.render(game));
+    }
 }
 
 #[allow(clippy::cast_precision_loss, clippy::cast_lossless)]
-fn ai_simulation(base_game: &Game) {
+fn ai_simulation(base_game: &Game, results: Option<&str>) {
     const ITERS: usize = 100;
 
-    let it = std::time::Instant::now();
+    let it = Instant::now();
 
     let mut ai_one_wins = 0;
     let mut ai_two_wins = 0;
     let mut turns = 0_u64;
+    let mut games = vec![];
 
     #[allow(clippy::cast_possible_truncation)]
     let seed = base_game.seed.unwrap_or_else(|| time_nanos() as u64);
     let mut rng = RomuDuoJrRand::with_seed(seed);
 
     println!("Using seed {seed}");
+    crash::track_seed(seed);
 
     'outer: for _ in 0..ITERS {
         let mut game = base_game.clone();
@@ -247,8 +1475,9 @@ fn ai_simulation(base_game: &Game) {
         let mut ai_two = SimpleAi::with_seed(Player::PlayerTwo, rng.next());
 
         loop {
+            crash::track(&game);
             if base_game.ai_reasoning {
-                game.pp();
+                print!("{}", game.render());
             }
             if !game.running() {
                 if let Status::Won { winner } = game.status {
@@ -259,14 +1488,17 @@ fn ai_simulation(base_game: &Game) {
                     }
                 }
                 turns += game.round() as u64;
+                games.push(game);
                 continue 'outer;
             }
 
-            if game.player() == Player::PlayerOne {
-                game = ai_one.play_iteratively(&mut game);
+            let ai = if game.player() == Player::PlayerOne {
+                &mut ai_one
             } else {
-                game = ai_two.play_iteratively(&mut game);
-            }
+                &mut ai_two
+            };
+            game = ai.play_iteratively(&mut game);
+            print_reasoning(ai);
         }
     }
 
@@ -294,12 +1526,18 @@ fn ai_simulation(base_game: &Game) {
     let draw_percentage = (draws as f64 / ITERS as f64) * 100.;
 
     println!("We had {draws} draws ({draw_percentage}%)");
+
+    if let Some(path) = results {
+        if let Err(err) = std::fs::write(path, results::for_path(path, &games)) {
+            println!("Could not write results to {path}: {err}");
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{
-        field::Field,
+    use quarto::{
+        field::{Field, RenderStyle},
         game::ArrayBase,
         piece::{Piece, Property},
     };
@@ -320,6 +1558,6 @@ mod test {
 
         field.put((0, 3), test_light_tall).unwrap();
 
-        field.pp(ArrayBase::One);
+        print!("{}", field.render(RenderStyle::new(ArrayBase::One)));
     }
 }
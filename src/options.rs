@@ -0,0 +1,180 @@
+//! The options of the AI in one registry: each has a name, a short flag and a parser, so a new
+//! tunable is added here once, and can be set both with its flags and in a config file.
+//!
+//! A config file has one `name = value` per line, such as `contempt = 0.5`, with the names of the
+//! long flags. Empty lines and `#` comments are skipped.
+
+use crate::{
+    game::{Game, MAX_SOLVER_SPACES},
+    piece::PropertyNames,
+};
+
+/// An option of the AI
+#[derive(Debug)]
+pub struct EngineOption {
+    /// The name, in config files and in the long flag, such as `seed` for `--seed`
+    pub name: &'static str,
+    /// The short flag, such as `-s`
+    pub short: &'static str,
+    set: fn(&mut Game, &str) -> Result<(), String>,
+    get: fn(&Game) -> Option<String>,
+}
+
+impl EngineOption {
+    /// Sets the option in `game` to the parsed `value`. Nothing changes if it is invalid.
+    pub fn set(&self, game: &mut Game, value: &str) -> Result<(), String> {
+        (self.set)(game, value.trim())
+    }
+
+    /// The value of the option in `game`, in the format it's set in, or `None` if it isn't set
+    pub fn get(&self, game: &Game) -> Option<String> {
+        (self.get)(game)
+    }
+}
+
+/// All options, in the order of the help
+pub const OPTIONS: [EngineOption; 5] = [
+    EngineOption {
+        name: "property-names",
+        short: "-y",
+        set: |game, value| {
+            game.property_names = PropertyNames::parse(value)
+                .map_err(|err| format!("Invalid property names: {err}"))?;
+            Ok(())
+        },
+        get: |game| Some(game.property_names.notation()),
+    },
+    EngineOption {
+        name: "seed",
+        short: "-s",
+        set: |game, value| {
+            let seed = value
+                .parse()
+                .map_err(|_| format!("Invalid seed: {value}"))?;
+            game.seed = Some(seed);
+            Ok(())
+        },
+        get: |game| game.seed.map(|seed| seed.to_string()),
+    },
+    EngineOption {
+        name: "temperature",
+        short: "-e",
+        set: |game, value| match value.parse::<f64>() {
+            Ok(temperature) if temperature >= 0.0 => {
+                game.ai_temperature = Some(temperature);
+                Ok(())
+            }
+            _ => Err(format!("Invalid temperature: {value}")),
+        },
+        get: |game| {
+            game.ai_temperature
+                .map(|temperature| temperature.to_string())
+        },
+    },
+    EngineOption {
+        name: "solver",
+        short: "-d",
+        set: |game, value| match value.parse::<usize>() {
+            Ok(spaces) if spaces <= MAX_SOLVER_SPACES => {
+                game.solver_spaces = spaces;
+                Ok(())
+            }
+            _ => Err(format!("Invalid number of empty spaces: {value}")),
+        },
+        get: |game| Some(game.solver_spaces.to_string()),
+    },
+    EngineOption {
+        name: "contempt",
+        short: "-j",
+        set: |game, value| match value.parse::<f64>() {
+            Ok(contempt) if (-1.0..=1.0).contains(&contempt) => {
+                game.contempt = contempt;
+                Ok(())
+            }
+            _ => Err(format!("Invalid contempt: {value}")),
+        },
+        get: |game| Some(game.contempt.to_string()),
+    },
+];
+
+/// The option with the given name
+pub fn find(name: &str) -> Option<&'static EngineOption> {
+    OPTIONS.iter().find(|option| option.name == name)
+}
+
+/// Sets the options of a config file in `game`, failing at the first invalid line
+pub fn parse_config(game: &mut Game, s: &str) -> Result<(), String> {
+    for (idx, line) in s.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |msg: String| format!("line {}: {msg}", idx + 1);
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| err(format!("expected `name = value`, not `{line}`")))?;
+        let name = name.trim();
+        let option = find(name).ok_or_else(|| err(format!("unknown option `{name}`")))?;
+        option.set(game, value).map_err(err)?;
+    }
+    Ok(())
+}
+
+/// The config file setting all options as in `game`, leaving out those not set
+pub fn to_config(game: &Game) -> String {
+    OPTIONS
+        .iter()
+        .filter_map(|option| Some(format!("{} = {}\n", option.name, option.get(game)?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, Player};
+
+    use super::{find, parse_config, to_config, OPTIONS};
+
+    #[test]
+    fn test_options() {
+        let mut game = Game::new(Player::PlayerOne);
+        for option in &OPTIONS {
+            assert_eq!(find(option.name).unwrap().short, option.short);
+        }
+        assert!(find("hash").is_none());
+        let contempt = find("contempt").unwrap();
+        contempt.set(&mut game, " -0.5").unwrap();
+        assert_eq!(contempt.get(&game).as_deref(), Some("-0.5"));
+        assert_eq!(
+            contempt.set(&mut game, "2"),
+            Err("Invalid contempt: 2".to_string())
+        );
+        assert!((game.contempt + 0.5).abs() < f64::EPSILON);
+        assert_eq!(find("seed").unwrap().get(&game), None);
+    }
+
+    #[test]
+    fn test_config() {
+        let mut game = Game::new(Player::PlayerOne);
+        parse_config(
+            &mut game,
+            "# Slow and steady\n\nseed = 42\ntemperature=0.25\n  solver = 7\n",
+        )
+        .unwrap();
+        assert_eq!(game.seed, Some(42));
+        assert_eq!(game.ai_temperature, Some(0.25));
+        assert_eq!(game.solver_spaces, 7);
+
+        let config = to_config(&game);
+        assert!(config.contains("seed = 42\n"));
+        let mut other = Game::new(Player::PlayerOne);
+        parse_config(&mut other, &config).unwrap();
+        assert_eq!(to_config(&other), config);
+
+        assert_eq!(
+            parse_config(&mut game, "seed = 1\nsolver = 9"),
+            Err("line 2: Invalid number of empty spaces: 9".to_string())
+        );
+        assert!(parse_config(&mut game, "hash = 64").is_err());
+        assert!(parse_config(&mut game, "contempt").is_err());
+    }
+}
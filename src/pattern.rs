@@ -0,0 +1,263 @@
+//! Winning patterns besides the rows, columns and diagonals, such as the 2x2 squares of square
+//! mode.
+//!
+//! A pattern is a shape of four spaces, it's a win wherever on the field the shape fits, and its
+//! pieces share a property. Fixed patterns only win at one place on the field.
+//!
+//! Patterns are written as four `x,y` spaces separated by `+`, such as `1,1+2,1+3,1+2,2` for T
+//! shapes, or `@` followed by the spaces for a fixed pattern, such as `@2,2+3,2+2,3+3,3` for the
+//! 2x2 center. Coordinates start at 1. The house rules in [`WinPattern::NAMES`] can be written by
+//! name instead.
+
+use crate::{
+    field::{try_parse_pos, Field, Pos},
+    game::ArrayBase,
+};
+
+/// Coordinates in patterns are always 1-based, whatever the user prefers.
+const PATTERN_BASE: ArrayBase = ArrayBase::One;
+
+/// A winning pattern, see the [module documentation](self)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WinPattern {
+    /// The spaces of the shape, moved to the top left corner unless the pattern is fixed
+    cells: [Pos; 4],
+    /// If true, the pattern only wins exactly at `cells`
+    fixed: bool,
+    /// Every place the shape fits on the field
+    placements: Vec<[Pos; 4]>,
+}
+
+impl WinPattern {
+    /// The names of the ready-made patterns for common house rules, see [`WinPattern::by_name`]
+    pub const NAMES: [&'static str; 4] = ["squares", "corners", "center", "plus"];
+
+    /// A pattern from four distinct spaces, only the shape matters, not where on the field it is.
+    pub fn new(cells: [Pos; 4]) -> Result<Self, ()> {
+        let min_x = cells.iter().map(|pos| pos.0).min().unwrap();
+        let min_y = cells.iter().map(|pos| pos.1).min().unwrap();
+        let mut pattern = Self::fixed(cells.map(|(x, y)| (x - min_x, y - min_y)))?;
+
+        let width = pattern.cells.iter().map(|pos| pos.0).max().unwrap() + 1;
+        let height = pattern.cells.iter().map(|pos| pos.1).max().unwrap() + 1;
+        pattern.fixed = false;
+        pattern.placements.clear();
+        for y in 0..=(Field::SIZE - height) {
+            for x in 0..=(Field::SIZE - width) {
+                pattern
+                    .placements
+                    .push(pattern.cells.map(|(dx, dy)| (x + dx, y + dy)));
+            }
+        }
+        Ok(pattern)
+    }
+
+    /// A pattern only winning at exactly these four distinct spaces
+    pub fn fixed(mut cells: [Pos; 4]) -> Result<Self, ()> {
+        cells.sort_unstable_by_key(|&(x, y)| (y, x));
+        if cells.windows(2).any(|pair| pair[0] == pair[1])
+            || cells
+                .iter()
+                .any(|&(x, y)| x >= Field::SIZE || y >= Field::SIZE)
+        {
+            return Err(());
+        }
+        Ok(Self {
+            cells,
+            fixed: true,
+            placements: vec![cells],
+        })
+    }
+
+    /// The 2x2 squares of square mode
+    pub fn square() -> Self {
+        Self::new([(0, 0), (1, 0), (0, 1), (1, 1)]).unwrap()
+    }
+
+    /// The ready-made pattern with the given name, one of [`WinPattern::NAMES`]:
+    ///  * `squares`: 2x2 squares anywhere, as in square mode,
+    ///  * `corners`: the four corners of the field,
+    ///  * `center`: the 2x2 square in the center of the field,
+    ///  * `plus`: the four spaces around any inner space, forming a plus sign.
+    pub fn by_name(name: &str) -> Option<Self> {
+        let pattern = match name {
+            "squares" => Self::square(),
+            "corners" => Self::fixed([(0, 0), (3, 0), (0, 3), (3, 3)]).unwrap(),
+            "center" => Self::fixed([(1, 1), (2, 1), (1, 2), (2, 2)]).unwrap(),
+            "plus" => Self::new([(1, 0), (0, 1), (2, 1), (1, 2)]).unwrap(),
+            _ => return None,
+        };
+        Some(pattern)
+    }
+
+    /// The name of this pattern, if it's one of the ready-made ones
+    pub fn name(&self) -> Option<&'static str> {
+        Self::NAMES
+            .into_iter()
+            .find(|&name| Self::by_name(name).as_ref() == Some(self))
+    }
+
+    /// Parses a pattern, see the [module documentation](self) for the format.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        Self::parse_based(s, PATTERN_BASE)
+    }
+
+    /// Parses a pattern like [`WinPattern::parse`], with coordinates starting at `array_base`, as
+    /// typed by the user.
+    pub fn parse_based(s: &str, array_base: ArrayBase) -> Result<Self, String> {
+        let s = s.trim();
+        if let Some(pattern) = Self::by_name(&s.to_ascii_lowercase()) {
+            return Ok(pattern);
+        }
+        let err = || {
+            format!(
+                "invalid pattern `{s}`, expected one of {} or four distinct x,y spaces",
+                Self::NAMES.join(", ")
+            )
+        };
+        let (fixed, cells) = match s.strip_prefix('@') {
+            Some(cells) => (true, cells),
+            None => (false, s),
+        };
+        let cells: Vec<Pos> = cells
+            .split(|c: char| c == '+' || c.is_whitespace())
+            .filter(|cell| !cell.is_empty())
+            .map(|cell| {
+                try_parse_pos(cell)
+                    .map(|(x, y)| (array_base.unbased(x), array_base.unbased(y)))
+                    .map_err(|()| err())
+            })
+            .collect::<Result<_, _>>()?;
+        let cells: [Pos; 4] = cells.try_into().map_err(|_| err())?;
+        if fixed {
+            Self::fixed(cells)
+        } else {
+            Self::new(cells)
+        }
+        .map_err(|()| err())
+    }
+
+    /// Parses a list of patterns, separated by `;`, with coordinates starting at `array_base`.
+    pub fn parse_list(s: &str, array_base: ArrayBase) -> Result<Vec<Self>, String> {
+        s.split(';')
+            .filter(|pattern| !pattern.trim().is_empty())
+            .map(|pattern| Self::parse_based(pattern, array_base))
+            .collect()
+    }
+
+    /// Every place on the field the shape fits
+    pub fn placements(&self) -> &[[Pos; 4]] {
+        &self.placements
+    }
+}
+
+impl std::fmt::Display for WinPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(name) = self.name() {
+            return f.write_str(name);
+        }
+        let cells: Vec<String> = self
+            .cells
+            .iter()
+            .map(|&(x, y)| format!("{},{}", PATTERN_BASE.based(x), PATTERN_BASE.based(y)))
+            .collect();
+        if self.fixed {
+            f.write_str("@")?;
+        }
+        f.write_str(&cells.join("+"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{field::Field, game::ArrayBase, piece::Piece};
+
+    use super::WinPattern;
+
+    #[test]
+    fn test_placements() {
+        assert_eq!(WinPattern::square().placements().len(), 9);
+        let t = WinPattern::parse("2,2 3,2 4,2 3,3").unwrap();
+        assert_eq!(t.to_string(), "1,1+2,1+3,1+2,2");
+        assert_eq!(WinPattern::parse(&t.to_string()), Ok(t.clone()));
+        assert_eq!(t.placements().len(), 6);
+        assert!(t.placements().contains(&[(1, 2), (2, 2), (3, 2), (2, 3)]));
+
+        let fixed = WinPattern::parse("@1,1+2,1+3,1+2,2").unwrap();
+        assert_eq!(fixed.placements(), [[(0, 0), (1, 0), (2, 0), (1, 1)]]);
+        assert_eq!(fixed.to_string(), "@1,1+2,1+3,1+2,2");
+        assert_ne!(fixed, t);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            WinPattern::parse("2,2 3,2 2,3 3,3"),
+            Ok(WinPattern::square())
+        );
+        assert!(WinPattern::parse("1,1 2,1 1,2").is_err());
+        assert!(WinPattern::parse("1,1 2,1 1,2 1,2").is_err());
+        assert!(WinPattern::parse("0,1 1,1 1,2 2,2").is_err());
+        assert!(WinPattern::parse("1,1 5,1 1,2 2,2").is_err());
+        assert!(WinPattern::parse("diamonds").is_err());
+        assert_eq!(
+            WinPattern::parse_list("1,1+2,1+1,2+2,2; Corners;", ArrayBase::One).unwrap(),
+            [
+                WinPattern::square(),
+                WinPattern::by_name("corners").unwrap()
+            ]
+        );
+        assert_eq!(
+            WinPattern::parse_based("@0,0+1,0+2,0+1,1", ArrayBase::Zero)
+                .unwrap()
+                .to_string(),
+            "@1,1+2,1+3,1+2,2"
+        );
+        assert!(WinPattern::parse_based("0,0 1,0 0,1 1,4", ArrayBase::Zero).is_err());
+    }
+
+    #[test]
+    fn test_names() {
+        for name in WinPattern::NAMES {
+            let pattern = WinPattern::by_name(name).unwrap();
+            assert_eq!(pattern.name(), Some(name));
+            assert_eq!(pattern.to_string(), name);
+            assert_eq!(WinPattern::parse(name), Ok(pattern));
+        }
+    }
+
+    /// A field with only the given pattern, and pieces sharing only the tall property on `spaces`
+    fn field_with(pattern: &str, spaces: [(usize, usize); 4]) -> Field {
+        let mut field = Field::new();
+        field.add_pattern(WinPattern::parse(pattern).unwrap());
+        for (pos, code) in spaces.into_iter().zip(["TLRF", "TDQH", "TLQH", "TDRF"]) {
+            field.put(pos, Piece::from_code(code).unwrap()).unwrap();
+        }
+        field
+    }
+
+    #[test]
+    fn test_squares() {
+        assert!(field_with("squares", [(2, 1), (3, 1), (2, 2), (3, 2)]).check_field_for_win());
+        assert!(!field_with("squares", [(0, 0), (3, 0), (0, 3), (3, 3)]).check_field_for_win());
+    }
+
+    #[test]
+    fn test_corners() {
+        assert!(field_with("corners", [(0, 0), (3, 0), (0, 3), (3, 3)]).check_field_for_win());
+        assert!(!field_with("corners", [(0, 0), (3, 0), (0, 3), (2, 3)]).check_field_for_win());
+    }
+
+    #[test]
+    fn test_center() {
+        assert!(field_with("center", [(1, 1), (2, 1), (1, 2), (2, 2)]).check_field_for_win());
+        assert!(!field_with("center", [(0, 0), (1, 0), (0, 1), (1, 1)]).check_field_for_win());
+    }
+
+    #[test]
+    fn test_plus() {
+        assert!(field_with("plus", [(2, 1), (1, 2), (3, 2), (2, 3)]).check_field_for_win());
+        assert!(!field_with("plus", [(2, 1), (1, 2), (3, 2), (2, 2)]).check_field_for_win());
+        assert_eq!(WinPattern::by_name("plus").unwrap().placements().len(), 4);
+    }
+}
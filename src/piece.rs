@@ -1,8 +1,32 @@
+//! The 16 pieces, their properties, and how they are written and drawn.
+
 use std::fmt::Formatter;
 
+/// How pieces are drawn
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GlyphSet {
+    /// An emoji per property, such as `[✋⬜🟠🔴]`
+    Emoji,
+    /// The letters of the piece [code](Piece::code), such as `[TLRF]`, for terminals that can't
+    /// show emoji
+    Ascii,
+}
+
+impl GlyphSet {
+    /// The width of a rendered piece, in terminal columns
+    pub fn width(self) -> usize {
+        match self {
+            // Emoji take up two columns each.
+            GlyphSet::Emoji => 10,
+            GlyphSet::Ascii => 6,
+        }
+    }
+}
+
 /// A quarto piece.
 #[derive(Default, PartialEq, Eq, Copy, Clone, Hash, Ord, PartialOrd)]
 pub struct Piece {
+    /// The set properties in the low four bits, the unset ones in the high four bits
     pub properties: u8,
 }
 
@@ -12,16 +36,149 @@ impl std::fmt::Debug for Piece {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// A property a piece has or lacks, such as being tall
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Property {
+    /// Tall, or short if unset
     Tall = 1 << 0,
+    /// Round, or square if unset
     Round = 1 << 1,
+    /// Full, or hollow if unset
     Full = 1 << 2,
+    /// Light, or dark if unset
     Light = 1 << 3,
 }
 
+impl Property {
+    /// The name of this property, as accepted by [`Property::from_name`]
+    pub fn name(self) -> &'static str {
+        match self {
+            Property::Tall => "tall",
+            Property::Round => "round",
+            Property::Full => "full",
+            Property::Light => "light",
+        }
+    }
+
+    /// Parses a property from its name, such as `full` or `tall`.
+    pub fn from_name(name: &str) -> Result<Self, ()> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "tall" | "height" => Ok(Property::Tall),
+            "round" | "shape" => Ok(Property::Round),
+            "full" | "fill" => Ok(Property::Full),
+            "light" | "color" | "colour" => Ok(Property::Light),
+            _ => Err(()),
+        }
+    }
+}
+
+/// The words for the values of each property, such as `tall` and `short`, used wherever pieces
+/// are described in words rather than drawn. They default to [English](PropertyNames::english),
+/// and may follow a house convention instead, such as `solid` for full pieces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyNames {
+    /// A pair of words, if set and if unset, per property in the order of
+    /// [`Piece::CODE_LETTERS`]
+    words: [(String, String); 4],
+}
+
+impl Default for PropertyNames {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+impl PropertyNames {
+    /// `tall/short`, `light/dark`, `round/square` and `full/hollow`
+    pub fn english() -> Self {
+        Self::parse("tall/short,light/dark,round/square,full/hollow").unwrap()
+    }
+
+    /// Parses the words as four `set/unset` pairs separated by commas, in the order of the
+    /// [code](Piece::code) letters: height, color, shape and fill, such as
+    /// `tall/short,light/dark,round/square,solid/hollow`.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let pairs: Vec<(String, String)> = s
+            .split(',')
+            .map(|pair| {
+                let (set, unset) = pair.split_once('/').ok_or(format!(
+                    "expected a pair of words such as tall/short, not `{pair}`"
+                ))?;
+                let word = |w: &str| w.trim().to_lowercase();
+                Ok((word(set), word(unset)))
+            })
+            .collect::<Result<_, String>>()?;
+        let words: [(String, String); 4] = pairs
+            .try_into()
+            .map_err(|_| "expected four pairs of words, one per property".to_string())?;
+        let all: Vec<&String> = words.iter().flat_map(|(set, unset)| [set, unset]).collect();
+        for (i, word) in all.iter().enumerate() {
+            if word.is_empty() || word.contains(char::is_whitespace) {
+                return Err(format!("`{word}` should be a single word"));
+            }
+            if all[..i].contains(word) {
+                return Err(format!("the word `{word}` is given twice"));
+            }
+        }
+        Ok(Self { words })
+    }
+
+    /// The words in the notation [parsed](PropertyNames::parse), such as
+    /// `tall/short,light/dark,round/square,full/hollow`
+    pub fn notation(&self) -> String {
+        self.words
+            .iter()
+            .map(|(set, unset)| format!("{set}/{unset}"))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The word for a property value, such as `short` for a piece that isn't tall
+    pub fn value(&self, prop: Property, set: bool) -> &str {
+        let idx = Piece::CODE_LETTERS
+            .iter()
+            .position(|&(p, _, _)| p == prop)
+            .unwrap();
+        let (set_word, unset_word) = &self.words[idx];
+        if set {
+            set_word
+        } else {
+            unset_word
+        }
+    }
+
+    /// The property value with the given word, case-insensitive
+    pub fn find(&self, word: &str) -> Option<(Property, bool)> {
+        let word = word.trim().to_lowercase();
+        Piece::CODE_LETTERS
+            .iter()
+            .flat_map(|&(prop, _, _)| [(prop, true), (prop, false)])
+            .find(|&(prop, set)| self.value(prop, set) == word)
+    }
+
+    /// The values of a piece in words, such as `tall, dark, round, hollow`, leaving out `hidden`
+    pub fn describe(&self, piece: Piece, hidden: Option<Property>) -> String {
+        Piece::CODE_LETTERS
+            .iter()
+            .filter(|&&(prop, _, _)| hidden != Some(prop))
+            .map(|&(prop, _, _)| self.value(prop, piece.get(prop)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Values joined by `and`, such as `tall and dark`
+    pub fn join(&self, values: &[(Property, bool)]) -> String {
+        values
+            .iter()
+            .map(|&(prop, set)| self.value(prop, set))
+            .collect::<Vec<_>>()
+            .join(" and ")
+    }
+}
+
 impl Piece {
+    /// The piece with the set properties in the low four bits of `props`
     #[must_use]
     pub const fn with_props(props: u8) -> Self {
         assert!(props >> 4 == 0, "top bits should be clear");
@@ -31,10 +188,76 @@ impl Piece {
         Piece { properties: props }
     }
 
+    /// The canonical index of this piece, from 0 to 15. It is stable across versions: the bits
+    /// of the index are the set properties, [`Property::Tall`] being bit 0, `Round` bit 1, `Full`
+    /// bit 2 and `Light` bit 3.
+    pub fn index(self) -> u8 {
+        self.properties & 0x0F
+    }
+
+    /// The piece with the given [`index`](Piece::index), if it is below 16.
+    pub fn from_index(index: u8) -> Result<Self, ()> {
+        if index < 16 {
+            Ok(Self::with_props(index))
+        } else {
+            Err(())
+        }
+    }
+
+    /// All 16 pieces, ordered by [`index`](Piece::index)
+    pub fn all() -> impl Iterator<Item = Self> {
+        (0..16).map(Self::with_props)
+    }
+
+    /// If this piece has the property
     pub fn get(self, prop: Property) -> bool {
         (self.properties & prop as u8) != 0
     }
 
+    /// The four letter code of this piece, one letter per property, in display order:
+    /// `T`all/`S`hort, `L`ight/`D`ark, `R`ound/s`Q`uare and `F`ull/`H`ollow.
+    pub fn code(self) -> String {
+        Self::CODE_LETTERS
+            .iter()
+            .map(|&(prop, set, unset)| if self.get(prop) { set } else { unset })
+            .collect()
+    }
+
+    /// Parses a piece from its four letter [`code`](Piece::code).
+    /// Letters are case-insensitive and may come in any order, but each property must be given
+    /// exactly once.
+    pub fn from_code(code: &str) -> Result<Self, ()> {
+        let mut props = 0;
+        let mut seen = 0;
+        for c in code.trim().chars() {
+            let c = c.to_ascii_uppercase();
+            let &(prop, set, _) = Self::CODE_LETTERS
+                .iter()
+                .find(|&&(_, set, unset)| c == set || c == unset)
+                .ok_or(())?;
+            if seen & prop as u8 != 0 {
+                return Err(());
+            }
+            seen |= prop as u8;
+            if c == set {
+                props |= prop as u8;
+            }
+        }
+        if seen != 0b1111 {
+            return Err(());
+        }
+        Ok(Self::with_props(props))
+    }
+
+    /// The letters used in piece codes, as (property, letter if set, letter if unset)
+    pub const CODE_LETTERS: [(Property, char, char); 4] = [
+        (Property::Tall, 'T', 'S'),
+        (Property::Light, 'L', 'D'),
+        (Property::Round, 'R', 'Q'),
+        (Property::Full, 'F', 'H'),
+    ];
+
+    /// Writes this piece as emoji, one per property.
     pub fn pp_write(self, f: &mut Formatter) -> std::fmt::Result {
         f.write_str("[")?;
         if self.get(Property::Tall) {
@@ -64,41 +287,65 @@ impl Piece {
         f.write_str("]")
     }
 
-    /// Pretty-print a piece
-    pub fn pp(self) {
-        // TODO: This could probably be in Debug or Display?
-        print!("[");
-        if self.get(Property::Tall) {
-            print!("✋");
-            //print!("️⬆️");
-        } else {
-            print!("🤏");
-            //print!("⬇️");
-        }
-        if self.get(Property::Light) {
-            //print!("🏳️");
-            print!("⬜");
-        } else {
-            //print!("🏴");
-            print!("⬛");
-        }
-        if self.get(Property::Round) {
-            print!("🟠");
-        } else {
-            print!("🔶");
-        }
-        if self.get(Property::Full) {
-            print!("🔴");
-        } else {
-            print!("⭕");
+    /// Render a piece
+    pub fn render(self, glyphs: GlyphSet) -> String {
+        self.render_masked(None, glyphs)
+    }
+
+    /// Render a piece, showing `hidden` as unknown.
+    pub fn render_masked(self, hidden: Option<Property>, glyphs: GlyphSet) -> String {
+        if glyphs == GlyphSet::Ascii {
+            let letters: String = Self::CODE_LETTERS
+                .iter()
+                .map(|&(prop, set, unset)| match hidden {
+                    Some(hidden) if hidden == prop => '?',
+                    _ if self.get(prop) => set,
+                    _ => unset,
+                })
+                .collect();
+            return format!("[{letters}]");
         }
-        print!("]");
+        let glyph = |prop: Property, set: &'static str, unset: &'static str| {
+            if hidden == Some(prop) {
+                "❔"
+            } else if self.get(prop) {
+                set
+            } else {
+                unset
+            }
+        };
+        format!(
+            "[{}{}{}{}]",
+            glyph(Property::Tall, "✋", "🤏"),
+            glyph(Property::Light, "⬜", "⬛"),
+            glyph(Property::Round, "🟠", "🔶"),
+            glyph(Property::Full, "🔴", "⭕")
+        )
+    }
+
+    /// The property values in a mask like [`Piece::properties`], with a bit per property that is
+    /// set and one per property that is unset, such as `(Property::Tall, false)` for the bit of
+    /// short pieces
+    pub fn mask_values(mask: u8) -> Vec<(Property, bool)> {
+        Self::CODE_LETTERS
+            .iter()
+            .flat_map(|&(prop, _, _)| [(prop, true), (prop, false)])
+            .filter(|&(prop, set)| {
+                let bit = if set { prop as u8 } else { (prop as u8) << 4 };
+                mask & bit != 0
+            })
+            .collect()
+    }
+
+    /// Returns true if both pieces agree on all properties but `ignored`.
+    pub fn matches_except(self, other: Piece, ignored: Property) -> bool {
+        (self.properties ^ other.properties) & 0b1111 & !(ignored as u8) == 0
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::piece::{Piece, Property};
+    use crate::piece::{Piece, Property, PropertyNames};
 
     const TEST_LIGHT_TALL: Piece = Piece::with_props(Property::Tall as u8 | Property::Light as u8);
 
@@ -108,4 +355,89 @@ mod tests {
         // Test the binary representation of a piece.
         assert!(TEST_LIGHT_TALL.properties == 0b_0110_1001);
     }
+
+    #[test]
+    fn test_code() {
+        assert_eq!(TEST_LIGHT_TALL.code(), "TLQH");
+        assert_eq!(Piece::from_code("TLQH"), Ok(TEST_LIGHT_TALL));
+        assert_eq!(Piece::from_code(" hqlt\n"), Ok(TEST_LIGHT_TALL));
+        for props in 0..16 {
+            let piece = Piece::with_props(props);
+            assert_eq!(Piece::from_code(&piece.code()), Ok(piece));
+        }
+        assert!(Piece::from_code("TLQ").is_err());
+        assert!(Piece::from_code("TSQH").is_err());
+        assert!(Piece::from_code("TLQHF").is_err());
+        assert!(Piece::from_code("TLQX").is_err());
+    }
+
+    #[test]
+    fn test_index() {
+        assert_eq!(TEST_LIGHT_TALL.index(), 0b1001);
+        assert_eq!(Piece::from_code("SDQH").unwrap().index(), 0);
+        assert_eq!(Piece::from_code("TLRF").unwrap().index(), 15);
+        for (i, piece) in Piece::all().enumerate() {
+            assert_eq!(usize::from(piece.index()), i);
+            assert_eq!(Piece::from_index(piece.index()), Ok(piece));
+        }
+        assert!(Piece::from_index(16).is_err());
+    }
+
+    #[test]
+    fn test_matches_except() {
+        let short = Piece::with_props(Property::Light as u8);
+        assert!(TEST_LIGHT_TALL.matches_except(short, Property::Tall));
+        assert!(!TEST_LIGHT_TALL.matches_except(short, Property::Full));
+        assert!(TEST_LIGHT_TALL.matches_except(TEST_LIGHT_TALL, Property::Full));
+        assert_eq!(Property::from_name("Fill"), Ok(Property::Full));
+        assert_eq!(
+            Property::from_name(Property::Light.name()),
+            Ok(Property::Light)
+        );
+        assert!(Property::from_name("weight").is_err());
+    }
+
+    #[test]
+    fn test_property_names() {
+        let names = PropertyNames::english();
+        assert_eq!(names.value(Property::Round, false), "square");
+        assert_eq!(names.find(" Dark"), Some((Property::Light, false)));
+        assert_eq!(names.find("solid"), None);
+        assert_eq!(
+            names.describe(TEST_LIGHT_TALL, None),
+            "tall, light, square, hollow"
+        );
+        assert_eq!(
+            names.describe(TEST_LIGHT_TALL, Some(Property::Light)),
+            "tall, square, hollow"
+        );
+
+        let house = PropertyNames::parse("big/small, white/black, round/square, solid/hollow");
+        let house = house.unwrap();
+        assert_eq!(house.find("solid"), Some((Property::Full, true)));
+        assert_eq!(
+            house.join(&[(Property::Tall, false), (Property::Light, true)]),
+            "small and white"
+        );
+        assert_eq!(
+            house.notation(),
+            "big/small,white/black,round/square,solid/hollow"
+        );
+        assert_eq!(PropertyNames::parse(&house.notation()), Ok(house));
+        assert!(PropertyNames::parse("tall/short,light/dark,round/square").is_err());
+        assert!(PropertyNames::parse("tall/short,light/dark,round/square,full").is_err());
+        assert!(PropertyNames::parse("tall/short,light/dark,round/square,full/tall").is_err());
+        assert!(PropertyNames::parse("tall/short,light/dark,round/square,full/not full").is_err());
+
+        let short_dark =
+            Piece::with_props(0).properties & Piece::with_props(Property::Full as u8).properties;
+        assert_eq!(
+            Piece::mask_values(short_dark),
+            [
+                (Property::Tall, false),
+                (Property::Light, false),
+                (Property::Round, false)
+            ]
+        );
+    }
 }
@@ -0,0 +1,310 @@
+//! Named player profiles, stored locally, so ratings and statistics accrue to a person rather
+//! than to a single session.
+//!
+//! Profiles are plain text files in the `profiles` directory of the data dir, which is
+//! `$QUARTO_HOME`, or `~/.quarto` if that is not set.
+
+use std::{
+    env,
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use quarto::{game::ArrayBase, rng::time_nanos, version};
+
+/// The directory all local data is stored in: `$QUARTO_HOME`, or `~/.quarto` if that is not set
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("QUARTO_HOME") {
+        Some(PathBuf::from(dir))
+    } else {
+        Some(
+            PathBuf::from(env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?)
+                .join(".quarto"),
+        )
+    }
+}
+
+/// The rating of a new profile, also assumed for the AI and for players without a profile
+pub const DEFAULT_RATING: i32 = 1200;
+
+/// The version of the profile format, see [`version`]
+const VERSION: u32 = 1;
+
+/// How much a single game can change the rating
+const K_FACTOR: f64 = 32.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+impl Outcome {
+    fn score(self) -> f64 {
+        match self {
+            Outcome::Win => 1.0,
+            Outcome::Loss => 0.0,
+            Outcome::Draw => 0.5,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Win => "win",
+            Outcome::Loss => "loss",
+            Outcome::Draw => "draw",
+        }
+    }
+}
+
+/// The result of a single game, from the perspective of the profile's owner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameResult {
+    /// Seconds since the unix epoch
+    pub timestamp: u64,
+    pub outcome: Outcome,
+    /// The rating of the opponent before the game
+    pub opponent_rating: i32,
+    pub opponent: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    /// The preferred base to count in
+    pub array_base: ArrayBase,
+    /// The Elo rating
+    pub rating: i32,
+    /// All results, oldest first
+    pub results: Vec<GameResult>,
+}
+
+/// The expected score of a player against an opponent, between 0 (loss) and 1 (win).
+pub fn expected_score(rating: i32, opponent_rating: i32) -> f64 {
+    1.0 / (1.0 + 10_f64.powf(f64::from(opponent_rating - rating) / 400.0))
+}
+
+impl Profile {
+    /// A new profile, names may only contain alphanumeric characters, `-` and `_`.
+    pub fn new(name: &str) -> Result<Self, String> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!(
+                "Invalid profile name '{name}', only letters, digits, - and _ are allowed"
+            ));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            array_base: ArrayBase::One,
+            rating: DEFAULT_RATING,
+            results: vec![],
+        })
+    }
+
+    /// The directory all profiles are stored in
+    pub fn dir() -> Option<PathBuf> {
+        Some(data_dir()?.join("profiles"))
+    }
+
+    fn path(dir: &Path, name: &str) -> PathBuf {
+        dir.join(format!("{name}.profile"))
+    }
+
+    /// Loads the profile with the given name, or creates a new one if it does not exist yet.
+    pub fn load_or_create(name: &str) -> Result<Self, String> {
+        let profile = Self::new(name)?;
+        let dir = Self::dir().ok_or("Could not determine the profile directory")?;
+        match fs::read_to_string(Self::path(&dir, name)) {
+            Ok(s) => Self::parse(&s),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(profile),
+            Err(err) => Err(format!("Could not read profile {name}: {err}")),
+        }
+    }
+
+    /// Loads all stored profiles, sorted by rating.
+    pub fn list() -> Result<Vec<Self>, String> {
+        let dir = Self::dir().ok_or("Could not determine the profile directory")?;
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Ok(vec![]);
+        };
+        let mut profiles = vec![];
+        for entry in entries.flatten() {
+            if entry.path().extension().is_some_and(|ext| ext == "profile") {
+                let s = fs::read_to_string(entry.path()).map_err(|e| e.to_string())?;
+                profiles.push(Self::parse(&s)?);
+            }
+        }
+        profiles.sort_by_key(|profile| -profile.rating);
+        Ok(profiles)
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let dir = Self::dir().ok_or_else(|| io::Error::other("no profile directory"))?;
+        fs::create_dir_all(&dir)?;
+        fs::write(Self::path(&dir, &self.name), self.serialize())
+    }
+
+    /// Parses a profile, as written by [`Profile::serialize`], or by an older release.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut profile: Option<Self> = None;
+        for (line_idx, line) in s.lines().enumerate() {
+            let err = || format!("Invalid profile, line {}", line_idx + 1);
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            // Version 0 profiles have the same layout, only without this line, which is added on
+            // the next save.
+            if key == "version" && profile.is_none() {
+                version::check("profile", value.parse().map_err(|_| err())?, VERSION)?;
+                continue;
+            }
+            if key == "name" {
+                profile = Some(Self::new(value)?);
+                continue;
+            }
+            let profile = profile.as_mut().ok_or_else(err)?;
+            match key {
+                "base" => {
+                    profile.array_base = match value {
+                        "0" => ArrayBase::Zero,
+                        "1" => ArrayBase::One,
+                        _ => return Err(err()),
+                    }
+                }
+                "rating" => profile.rating = value.parse().map_err(|_| err())?,
+                "result" => {
+                    let mut parts = value.splitn(4, ' ');
+                    let mut next = || parts.next().ok_or_else(err);
+                    let timestamp = next()?.parse().map_err(|_| err())?;
+                    let outcome = match next()? {
+                        "win" => Outcome::Win,
+                        "loss" => Outcome::Loss,
+                        "draw" => Outcome::Draw,
+                        _ => return Err(err()),
+                    };
+                    let opponent_rating = next()?.parse().map_err(|_| err())?;
+                    let opponent = next()?.to_string();
+                    profile.results.push(GameResult {
+                        timestamp,
+                        outcome,
+                        opponent_rating,
+                        opponent,
+                    });
+                }
+                _ => return Err(err()),
+            }
+        }
+        profile.ok_or_else(|| "Invalid profile, no name".to_string())
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut ret = format!(
+            "version: {VERSION}\nname: {}\nbase: {}\nrating: {}\n",
+            self.name,
+            self.array_base.based(0),
+            self.rating
+        );
+        for result in &self.results {
+            writeln!(
+                ret,
+                "result: {} {} {} {}",
+                result.timestamp,
+                result.outcome.as_str(),
+                result.opponent_rating,
+                result.opponent
+            )
+            .unwrap();
+        }
+        ret
+    }
+
+    /// Records the result of a game and updates the rating accordingly.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn record(&mut self, outcome: Outcome, opponent: &str, opponent_rating: i32) {
+        let change = K_FACTOR * (outcome.score() - expected_score(self.rating, opponent_rating));
+        self.rating += change.round() as i32;
+        self.results.push(GameResult {
+            timestamp: (time_nanos() / 1_000_000_000) as u64,
+            outcome,
+            opponent_rating,
+            opponent: opponent.to_string(),
+        });
+    }
+
+    /// The number of games with the given outcome
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.outcome == outcome)
+            .count()
+    }
+
+    /// A summary of this profile, such as `alice: rating 1216, 3 games (2 won, 1 lost, 0 drawn)`
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: rating {}, {} games ({} won, {} lost, {} drawn)",
+            self.name,
+            self.rating,
+            self.results.len(),
+            self.count(Outcome::Win),
+            self.count(Outcome::Loss),
+            self.count(Outcome::Draw)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quarto::game::ArrayBase;
+
+    use super::{expected_score, Outcome, Profile, DEFAULT_RATING};
+
+    #[test]
+    fn test_names() {
+        assert!(Profile::new("Alice_1-2").is_ok());
+        assert!(Profile::new("").is_err());
+        assert!(Profile::new("../etc").is_err());
+        assert!(Profile::new("a b").is_err());
+    }
+
+    #[test]
+    fn test_elo() {
+        assert!((expected_score(1200, 1200) - 0.5).abs() < f64::EPSILON);
+        assert!(expected_score(1400, 1200) > 0.75);
+
+        let mut profile = Profile::new("Alice").unwrap();
+        profile.record(Outcome::Win, "Bob", DEFAULT_RATING);
+        assert_eq!(profile.rating, DEFAULT_RATING + 16);
+        profile.record(Outcome::Draw, "Bob", DEFAULT_RATING + 16);
+        assert_eq!(profile.rating, DEFAULT_RATING + 16);
+        profile.record(Outcome::Loss, "Bob", DEFAULT_RATING + 16);
+        assert_eq!(profile.rating, DEFAULT_RATING);
+        assert_eq!(profile.count(Outcome::Win), 1);
+        assert_eq!(profile.count(Outcome::Draw), 1);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let mut profile = Profile::new("Alice").unwrap();
+        profile.array_base = ArrayBase::Zero;
+        profile.record(Outcome::Win, "Bob Builder", 1300);
+        profile.record(Outcome::Loss, "AI", 1200);
+        assert_eq!(Profile::parse(&profile.serialize()), Ok(profile));
+
+        // Profiles from before the format was versioned
+        let legacy = "name: Alice\nbase: 0\nrating: 1216\nresult: 12 win 1200 AI\n";
+        let profile = Profile::parse(legacy).unwrap();
+        assert_eq!(profile.rating, 1216);
+        assert!(profile.serialize().starts_with("version: 1\n"));
+        assert!(Profile::parse("version: 2\nname: Alice\n").is_err());
+
+        assert!(Profile::parse("rating: 12").is_err());
+        assert!(Profile::parse("name: Alice\nresult: 12 lost 1200 AI").is_err());
+    }
+}
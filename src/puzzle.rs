@@ -0,0 +1,171 @@
+//! Difficulty of puzzles, positions where the side to move can force a win, so generated puzzle
+//! packs can be sorted from easy to hard. A puzzle is harder the deeper the forced win, the fewer
+//! of the candidate moves keep it, and if the key is the piece given rather than where the piece
+//! is put.
+
+use std::fmt::Display;
+
+use crate::{field::Pos, game::Game, piece::Piece};
+
+/// What a winning move has to get right
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Key {
+    /// Where the piece is put: a winning placement wins with any piece given
+    Placement,
+    /// The piece given: it matters for every winning placement
+    Gift,
+}
+
+/// How hard a puzzle is, from the grade
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    /// A short win, with many winning moves
+    Easy,
+    /// Between easy and hard
+    Medium,
+    /// A deep win, with few winning moves or the gift as the key
+    Hard,
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        })
+    }
+}
+
+/// The analysis of a puzzle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grade {
+    /// The side to move forces a win within this many moves of both players, see
+    /// [`Game::forced_win_in`]
+    pub depth: usize,
+    /// The legal moves: each placement with each piece to give, or by itself if it wins
+    pub candidates: usize,
+    /// The moves keeping the forced win, with the piece given, unless the placement wins
+    pub winning_moves: Vec<(Pos, Option<Piece>)>,
+    /// What the winning moves have to get right
+    pub key: Key,
+}
+
+impl Grade {
+    /// Grades the position, if the side to move forces a win within `max_depth` moves.
+    pub fn of(game: &Game, max_depth: usize) -> Option<Self> {
+        let player = game.player();
+        let depth = (1..=max_depth)
+            .step_by(2)
+            .find(|&depth| game.forced_win_in(depth))?;
+        let hand = game.next_piece()?;
+        let gifts: Vec<Option<Piece>> = match game.remaining_pieces() {
+            [] => vec![None],
+            remaining => remaining.iter().copied().map(Some).collect(),
+        };
+        let mut candidates = 0;
+        let mut winning_moves = vec![];
+        // Placements with some gifts keeping the win and others losing it
+        let mut gift_matters = false;
+        for pos in game.field.empty_spaces() {
+            let mut won = game.clone();
+            won.do_move(pos, gifts[0].unwrap_or(hand)).unwrap();
+            // The piece isn't given if the placement wins.
+            if won.winner() == Some(player) {
+                candidates += 1;
+                winning_moves.push((pos, None));
+                continue;
+            }
+            let mut wins = 0;
+            for &gift in &gifts {
+                candidates += 1;
+                let mut after = game.clone();
+                after.do_move(pos, gift.unwrap_or(hand)).unwrap();
+                if after.wins_within(player, depth - 1) {
+                    winning_moves.push((pos, gift));
+                    wins += 1;
+                }
+            }
+            gift_matters |= wins > 0 && wins < gifts.len();
+        }
+        Some(Self {
+            depth,
+            candidates,
+            winning_moves,
+            key: if gift_matters {
+                Key::Gift
+            } else {
+                Key::Placement
+            },
+        })
+    }
+
+    /// The difficulty: deeper wins are harder, as are few winning moves and gifts as the key
+    pub fn difficulty(&self) -> Difficulty {
+        let mut points = self.depth / 2 * 2;
+        if self.winning_moves.len() * 10 < self.candidates {
+            points += 1;
+        }
+        if self.key == Key::Gift {
+            points += 1;
+        }
+        match points {
+            0 | 1 => Difficulty::Easy,
+            2 | 3 => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+
+    /// A summary, such as `win in 3, 4 of 120 moves win, the gift is the key`
+    pub fn describe(&self) -> String {
+        let key = match self.key {
+            Key::Placement => "placement",
+            Key::Gift => "gift",
+        };
+        format!(
+            "win in {}, {} of {} moves win, the {key} is the key",
+            self.depth,
+            self.winning_moves.len(),
+            self.candidates
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        field::Field,
+        game::{Game, Player},
+        piece::Piece,
+    };
+
+    use super::{Difficulty, Grade, Key};
+
+    fn game(grid: &str, hand: &str) -> Game {
+        let field = Field::parse_grid(grid).unwrap();
+        Game::from_position(field, Player::PlayerOne, Piece::from_code(hand).ok()).unwrap()
+    }
+
+    #[test]
+    fn test_grade() {
+        let win_in_one = game("TLRF SDRH TDRF .\n. . . .\n. . SLQF .\n. . . .", "SLRH");
+        let grade = Grade::of(&win_in_one, 3).unwrap();
+        assert_eq!(grade.depth, 1);
+        assert_eq!(grade.key, Key::Placement);
+        // Putting it to 4,1 wins, the piece given doesn't matter.
+        assert_eq!(grade.winning_moves, [((3, 0), None)]);
+        assert_eq!(grade.candidates, 1 + 11 * 11);
+        assert_eq!(grade.difficulty(), Difficulty::Easy);
+        assert_eq!(
+            grade.describe(),
+            "win in 1, 1 of 122 moves win, the placement is the key"
+        );
+
+        let empty = Game::from_position(
+            Field::new(),
+            Player::PlayerOne,
+            Piece::from_code("SLRH").ok(),
+        );
+        assert!(Grade::of(&empty.unwrap(), 3).is_none());
+    }
+}
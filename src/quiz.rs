@@ -0,0 +1,274 @@
+//! A drill for beginners (`quarto quiz`): recognizing the properties pieces share. Questions are
+//! asked on random positions from the [generator](quarto::generate), and alternate between
+//!  * a line one piece short of winning, asking which property its pieces share, and
+//!  * the remaining pieces, asking which are unsafe to give, completing a line right away.
+//!
+//! Each answer is scored and timed.
+
+use std::{
+    io::stdin,
+    time::{Duration, Instant},
+};
+
+use quarto::{
+    field::Pos,
+    game::Game,
+    generate::{self, Filters},
+    piece::{Piece, Property, PropertyNames},
+    rng::RomuDuoJrRand,
+};
+
+/// How many random positions may lack what a question needs, before giving up
+const ATTEMPTS: usize = 1000;
+
+/// Parses a property value by its [name](PropertyNames), such as `dark`, or the letter of its
+/// [code](Piece::code), such as `D`.
+fn parse_value(names: &PropertyNames, s: &str) -> Option<(Property, bool)> {
+    let s = s.trim().to_ascii_lowercase();
+    names.find(&s).or_else(|| {
+        Piece::CODE_LETTERS
+            .into_iter()
+            .flat_map(|(prop, set, unset)| [(prop, true, set), (prop, false, unset)])
+            .find(|&(_, _, letter)| s == letter.to_ascii_lowercase().to_string())
+            .map(|(prop, set, _)| (prop, set))
+    })
+}
+
+#[derive(Debug, Clone)]
+pub enum Question {
+    /// Which property the pieces in `line`, all but one space full, share
+    SharedProperty {
+        game: Game,
+        line: [Pos; 4],
+        shared: Vec<(Property, bool)>,
+    },
+    /// Which of the remaining pieces could complete a line right away
+    Unsafe {
+        game: Game,
+        unsafe_pieces: Vec<Piece>,
+    },
+}
+
+impl Question {
+    /// A random question of the given kind: with `shared` true, about the shared property
+    pub fn random(base: &Game, shared: bool, rng: &mut RomuDuoJrRand) -> Result<Self, String> {
+        let filters = Filters::parse(["plies=3-12"])?;
+        for _ in 0..ATTEMPTS {
+            let mut game = generate::generate(base, &filters, 1, rng)?.remove(0).game;
+            game.copy_settings(base);
+            if shared {
+                let lines = game.field.threatened_lines();
+                if lines.is_empty() {
+                    continue;
+                }
+                let line = rng.choose(lines);
+                let shared = Piece::mask_values(game.field.line_property_intersection(&line));
+                return Ok(Question::SharedProperty { game, line, shared });
+            }
+            if game.remaining_pieces().is_empty() {
+                continue;
+            }
+            let unsafe_pieces = game
+                .danger_matrix()
+                .into_iter()
+                .filter(|(_, spaces)| !spaces.is_empty())
+                .map(|(piece, _)| piece)
+                .collect();
+            return Ok(Question::Unsafe {
+                game,
+                unsafe_pieces,
+            });
+        }
+        Err(format!(
+            "No position for a question found in {ATTEMPTS} tries"
+        ))
+    }
+
+    /// Prints the position and the question.
+    pub fn ask(&self) {
+        match self {
+            Question::SharedProperty { game, line, .. } => {
+                print!(
+                    "{}",
+                    game.field.render_highlighted(game.render_style(), line)
+                );
+                println!("Which property do the pieces in the marked line share?");
+                let names = &game.property_names;
+                let pairs: Vec<String> = Piece::CODE_LETTERS
+                    .iter()
+                    .map(|&(prop, _, _)| {
+                        format!("{}/{}", names.value(prop, true), names.value(prop, false))
+                    })
+                    .collect();
+                println!("({} or {})", pairs[..3].join(", "), pairs[3]);
+            }
+            Question::Unsafe { game, .. } => {
+                print!("{}", game.field.render(game.render_style()));
+                println!("Remaining pieces:");
+                print!("{}", game.render_remaining_pieces());
+                println!("Which pieces are unsafe to give, letting the opponent win right away?");
+                println!("(their numbers, separated by spaces, or none)");
+            }
+        }
+    }
+
+    /// Checks an answer, errors if it can't be understood.
+    pub fn check(&self, answer: &str) -> Result<bool, String> {
+        match self {
+            Question::SharedProperty { game, shared, .. } => {
+                let mut values = vec![];
+                for word in answer.split_whitespace().filter(|&w| w != "and") {
+                    values.push(
+                        parse_value(&game.property_names, word)
+                            .ok_or(format!("`{word}` is not a property"))?,
+                    );
+                }
+                if values.is_empty() {
+                    return Err("Please name a property".to_string());
+                }
+                Ok(values.iter().all(|value| shared.contains(value)))
+            }
+            Question::Unsafe {
+                game,
+                unsafe_pieces,
+            } => {
+                let mut picked = vec![];
+                for word in answer.split_whitespace().filter(|&w| w != "none") {
+                    let piece = word
+                        .parse()
+                        .ok()
+                        .and_then(|i| game.remaining_pieces().get(game.array_base.unbased(i)))
+                        .ok_or_else(|| format!("`{word}` is not the number of a piece"))?;
+                    picked.push(*piece);
+                }
+                picked.sort_unstable();
+                picked.dedup();
+                let mut expected = unsafe_pieces.clone();
+                expected.sort_unstable();
+                Ok(picked == expected)
+            }
+        }
+    }
+
+    /// The right answer, as shown after a wrong one
+    pub fn solution(&self) -> String {
+        match self {
+            Question::SharedProperty { game, shared, .. } => game.property_names.join(shared),
+            Question::Unsafe {
+                game,
+                unsafe_pieces,
+            } if !unsafe_pieces.is_empty() => game
+                .remaining_pieces()
+                .iter()
+                .enumerate()
+                .filter(|(_, piece)| unsafe_pieces.contains(piece))
+                .map(|(i, _)| game.array_base.based(i).to_string())
+                .collect::<Vec<_>>()
+                .join(" "),
+            Question::Unsafe { .. } => "none".to_string(),
+        }
+    }
+}
+
+/// Reads a line from stdin, returns `None` once stdin is closed.
+fn read_line() -> Option<String> {
+    let mut buf = String::new();
+    match stdin().read_line(&mut buf) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(buf),
+    }
+}
+
+/// Asks `count` questions with the rules of `base`, then prints the score and the time taken.
+pub fn run(base: &Game, count: usize, rng: &mut RomuDuoJrRand) -> Result<(), String> {
+    let (mut correct, mut asked, mut time) = (0, 0, Duration::ZERO);
+    for i in 0..count {
+        let question = Question::random(base, i % 2 == 0, rng)?;
+        println!();
+        println!("Question {}/{count}:", i + 1);
+        question.ask();
+        let started = Instant::now();
+        let right = loop {
+            let Some(answer) = read_line() else {
+                break None;
+            };
+            match question.check(&answer) {
+                Ok(right) => break Some(right),
+                Err(err) => println!("{err}, try again:"),
+            }
+        };
+        let Some(right) = right else {
+            break;
+        };
+        let took = started.elapsed();
+        time += took;
+        asked += 1;
+        if right {
+            correct += 1;
+            println!("Correct! ({:.1}s)", took.as_secs_f64());
+        } else {
+            println!("Not quite, the answer is: {}", question.solution());
+        }
+    }
+    if asked > 0 {
+        println!();
+        println!(
+            "Score: {correct}/{asked} in {:.1}s, {:.1}s per question",
+            time.as_secs_f64(),
+            (time / asked).as_secs_f64()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use quarto::{
+        game::{Game, Player},
+        piece::{Piece, Property, PropertyNames},
+        rng::RomuDuoJrRand,
+    };
+
+    use super::{parse_value, Question};
+
+    #[test]
+    fn test_parse_value() {
+        let names = PropertyNames::english();
+        assert_eq!(parse_value(&names, "dark"), Some((Property::Light, false)));
+        assert_eq!(parse_value(&names, " Tall\n"), Some((Property::Tall, true)));
+        assert_eq!(parse_value(&names, "S"), Some((Property::Tall, false)));
+        assert_eq!(parse_value(&names, "q"), Some((Property::Round, false)));
+        assert_eq!(parse_value(&names, "h"), Some((Property::Full, false)));
+        assert_eq!(parse_value(&names, "big"), None);
+        assert_eq!(parse_value(&names, "short"), Some((Property::Tall, false)));
+        let house = PropertyNames::parse("tall/short,light/dark,round/square,solid/hollow");
+        assert_eq!(
+            parse_value(&house.unwrap(), "solid"),
+            Some((Property::Full, true))
+        );
+    }
+
+    #[test]
+    fn test_questions() {
+        let base = Game::new(Player::PlayerOne);
+        let mut rng = RomuDuoJrRand::with_seed(1337);
+        for i in 0..10 {
+            let question = Question::random(&base, i % 2 == 0, &mut rng).unwrap();
+            assert_eq!(question.check(&question.solution()), Ok(true));
+            match &question {
+                Question::SharedProperty { game, line, shared } => {
+                    assert!(!shared.is_empty());
+                    let pieces: Vec<Piece> =
+                        line.iter().filter_map(|&pos| game.field.get(pos)).collect();
+                    assert_eq!(pieces.len(), 3);
+                }
+                Question::Unsafe { game, .. } => {
+                    assert!(question.check("x").is_err());
+                    assert!(question
+                        .check(&format!("{}", game.remaining_pieces().len() + 1))
+                        .is_err());
+                }
+            }
+        }
+    }
+}
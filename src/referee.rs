@@ -0,0 +1,169 @@
+//! Matches between external programs (`quarto referee`), with this crate as the referee: it
+//! launches both bots, relays the moves between them, checks them against the rules and the
+//! clock, and keeps the official record as a [transcript](quarto::series::Series::transcript).
+//!
+//! The bots talk over their stdin and stdout, a line per message, with moves in the
+//! [notation](quarto::game::Move::to_notation), counting from 1 like transcripts:
+//!
+//! - `new <1|2> <rules>`: a game starts, the bot plays the given side with the
+//!   [rules](quarto::rules), such as `new 2 s;pie`
+//! - `opponent <move>`: the move the opponent made
+//! - `go`: the bot is to move, and answers with its move on a line of its own
+//! - `quit`: the match is over
+//!
+//! A bot forfeits the game if its move is illegal, it takes longer than the time limit, or it
+//! exits. Empty lines in the answers are ignored. Both bots see all pieces, also in the blind
+//! variant.
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use quarto::{
+    game::{Game, Move, Player},
+    validate::{self, FILE_BASE},
+};
+
+/// An external program playing one side
+#[derive(Debug)]
+pub struct Bot {
+    /// The command line the bot was launched with
+    pub command: String,
+    child: Child,
+    stdin: ChildStdin,
+    /// The lines the bot writes, read on a thread of their own so the clock can run out
+    lines: Receiver<String>,
+}
+
+impl Bot {
+    /// Launches the program with the arguments in `command`, separated by whitespace.
+    pub fn spawn(command: &str) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("empty bot command")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("Could not launch `{command}`: {err}"))?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in stdout.lines().map_while(Result::ok) {
+                if !line.trim().is_empty() && sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            command: command.to_string(),
+            child,
+            stdin,
+            lines,
+        })
+    }
+
+    fn send(&mut self, message: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{message}")
+            .and_then(|()| self.stdin.flush())
+            .map_err(|_| "the bot exited".to_string())
+    }
+
+    /// The next line of the bot, waiting at most `limit`
+    fn receive(&self, limit: Option<Duration>) -> Result<String, String> {
+        let line = match limit {
+            Some(limit) => self.lines.recv_timeout(limit).map_err(|err| match err {
+                RecvTimeoutError::Timeout => {
+                    format!("the bot took longer than {:.1}s", limit.as_secs_f64())
+                }
+                RecvTimeoutError::Disconnected => "the bot exited".to_string(),
+            }),
+            None => self.lines.recv().map_err(|_| "the bot exited".to_string()),
+        };
+        line.map(|line| line.trim().to_string())
+    }
+
+    /// Tells the bot the match is over.
+    pub fn quit(&mut self) {
+        let _ = self.send("quit");
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Plays `game` to the end between the bots, the first one playing player one. Returns the
+/// finished game, and why a bot forfeited it, if one did.
+pub fn play(
+    mut game: Game,
+    bots: &mut [Bot; 2],
+    limit: Option<Duration>,
+) -> (Game, Option<String>) {
+    let rules = game.rules().notation();
+    for player in [Player::PlayerOne, Player::PlayerTwo] {
+        let new = format!("new {} {rules}", player.index() + 1);
+        if let Err(reason) = bots[player.index()].send(&new) {
+            game.forfeit(player);
+            return (game, Some(format!("{player} forfeits: {reason}")));
+        }
+    }
+    while game.running() {
+        let player = game.player();
+        let started = Instant::now();
+        let bot = &mut bots[player.index()];
+        let made = bot
+            .send("go")
+            .and_then(|()| bot.receive(limit))
+            .and_then(|line| {
+                let mv = Move::from_notation(&line, FILE_BASE)?;
+                game.make_move(mv).map_err(|()| {
+                    let reason = validate::reason(&game, mv, FILE_BASE);
+                    format!("the move `{line}` is illegal, {reason}")
+                })?;
+                Ok(mv)
+            });
+        let (forfeiting, reason) = match made {
+            Ok(mv) => {
+                game.set_move_time(started.elapsed());
+                let opponent = format!("opponent {}", mv.to_notation(FILE_BASE));
+                match bots[player.next().index()].send(&opponent) {
+                    Ok(()) => continue,
+                    Err(reason) => (player.next(), reason),
+                }
+            }
+            Err(reason) => (player, reason),
+        };
+        game.forfeit(forfeiting);
+        return (game, Some(format!("{forfeiting} forfeits: {reason}")));
+    }
+    (game, None)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use quarto::game::{Game, Player};
+
+    use super::{play, Bot};
+
+    #[test]
+    fn test_play() {
+        assert!(Bot::spawn("./no-such-bot").is_err());
+        // `cat` answers with the messages it gets, which aren't moves.
+        let mut bots = [Bot::spawn("cat").unwrap(), Bot::spawn("cat").unwrap()];
+        let (game, forfeit) = play(Game::new(Player::PlayerOne), &mut bots, None);
+        assert_eq!(game.winner(), Some(Player::PlayerTwo));
+        assert_eq!(
+            forfeit.unwrap(),
+            "Player 1 forfeits: invalid move `new 1 -`"
+        );
+    }
+}
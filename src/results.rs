@@ -0,0 +1,129 @@
+//! Machine-readable results of AI simulations (`--results=<file>`), one record per game, as CSV
+//! or, for files ending in `.json`, as JSON.
+
+use std::fmt::Write;
+
+use crate::{game::Game, validate::FILE_BASE};
+
+/// The columns of the CSV format, and the keys of the JSON format
+const FIELDS: [&str; 5] = ["game", "rules", "winner", "plies", "moves"];
+
+/// The values of one game, in the order of [`FIELDS`]
+fn record(idx: usize, game: &Game) -> [String; 5] {
+    let moves: Vec<String> = game
+        .history()
+        .iter()
+        .map(|mv| mv.to_notation(FILE_BASE))
+        .collect();
+    [
+        (idx + 1).to_string(),
+        game.rules().notation(),
+        game.winner()
+            .map(|player| (player.index() + 1).to_string())
+            .unwrap_or_default(),
+        game.history().len().to_string(),
+        moves.join("; "),
+    ]
+}
+
+/// Quotes a CSV value if needed
+fn csv_value(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Formats a JSON string, quoted and escaped
+pub fn json_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Formats a JSON value: numbers as they are, no winner as `null`, anything else as a string
+fn json_value(value: &str) -> String {
+    if value.is_empty() {
+        "null".to_string()
+    } else if value.bytes().all(|b| b.is_ascii_digit()) {
+        value.to_string()
+    } else {
+        json_string(value)
+    }
+}
+
+/// The results as CSV, with a header line
+pub fn csv(games: &[Game]) -> String {
+    let mut ret = FIELDS.join(",");
+    ret.push('\n');
+    for (idx, game) in games.iter().enumerate() {
+        let values: Vec<String> = record(idx, game).iter().map(|v| csv_value(v)).collect();
+        writeln!(ret, "{}", values.join(",")).unwrap();
+    }
+    ret
+}
+
+/// The results as a JSON array of objects
+pub fn json(games: &[Game]) -> String {
+    let records: Vec<String> = games
+        .iter()
+        .enumerate()
+        .map(|(idx, game)| {
+            let pairs: Vec<String> = FIELDS
+                .iter()
+                .zip(record(idx, game))
+                .map(|(key, value)| format!("\"{key}\": {}", json_value(&value)))
+                .collect();
+            format!("  {{{}}}", pairs.join(", "))
+        })
+        .collect();
+    format!("[\n{}\n]\n", records.join(",\n"))
+}
+
+/// The results as written to `path`: as JSON if it ends in `.json`, else as CSV.
+pub fn for_path(path: &str, games: &[Game]) -> String {
+    let is_json = std::path::Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    if is_json {
+        json(games)
+    } else {
+        csv(games)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::{Game, Player},
+        piece::Piece,
+    };
+
+    use super::{csv, json};
+
+    fn games() -> Vec<Game> {
+        let mut game = Game::new(Player::PlayerTwo);
+        game.initial_move(Piece::from_code("SDQH").unwrap())
+            .unwrap();
+        game.do_move((1, 2), Piece::from_code("TLRF").unwrap())
+            .unwrap();
+        vec![game]
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            csv(&games()),
+            "game,rules,winner,plies,moves\n\
+             1,-,,2,\"give SDQH; put 2,3, give TLRF\"\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            json(&games()),
+            "[\n  {\"game\": 1, \"rules\": \"-\", \"winner\": null, \
+             \"plies\": 2, \"moves\": \"give SDQH; put 2,3, give TLRF\"}\n]\n"
+        );
+    }
+}
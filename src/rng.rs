@@ -35,8 +35,9 @@ impl RomuDuoJrRand {
         self.y_state = seed ^ 0x6789A;
     }
 
+    /// The next random number
     #[inline]
-    #[allow(clippy::unreadable_literal)]
+    #[allow(clippy::unreadable_literal, clippy::should_implement_trait)]
     pub fn next(&mut self) -> u64 {
         let xp = self.x_state;
         self.x_state = 15241094284759029579_u64.wrapping_mul(self.y_state);
@@ -66,6 +67,28 @@ impl RomuDuoJrRand {
         unbiased_rnd % upper_bound_excl
     }
 
+    /// Gets a value between 0 (inclusive) and 1 (exclusive)
+    #[allow(clippy::cast_precision_loss)]
+    pub fn next_float(&mut self) -> f64 {
+        // The upper 53 bits, as many as fit into the mantissa
+        (self.next() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// Choose an index at random, with a probability proportional to its weight.
+    /// The weights must not be negative, and not all zero.
+    pub fn choose_weighted(&mut self, weights: &[f64]) -> usize {
+        let total: f64 = weights.iter().sum();
+        let mut target = self.next_float() * total;
+        for (idx, &weight) in weights.iter().enumerate() {
+            if target < weight {
+                return idx;
+            }
+            target -= weight;
+        }
+        // Only reachable through rounding errors
+        weights.iter().rposition(|&weight| weight > 0.0).unwrap()
+    }
+
     /// Choose an item at random from the given iterator, sampling uniformly.
     ///
     /// Note: the runtime cost is bound by the iterator's [`nth`][`Iterator::nth`] implementation
@@ -100,4 +123,16 @@ mod tests {
 
         _ = rng.next();
     }
+
+    #[test]
+    fn test_choose_weighted() {
+        use crate::rng::RomuDuoJrRand;
+        let mut rng = RomuDuoJrRand::with_seed(1337);
+
+        for _ in 0..100 {
+            let float = rng.next_float();
+            assert!((0.0..1.0).contains(&float));
+            assert_eq!(rng.choose_weighted(&[0.0, 1.0, 0.0]), 1);
+        }
+    }
 }
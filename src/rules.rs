@@ -0,0 +1,396 @@
+//! The rules a game is played with: the standard rules, plus any combination of variants.
+//!
+//! Rules are written as `-` for the standard rules, or as a `;` separated list of variants:
+//!  * winning patterns, see [`crate::pattern`], with `s` as a short form of `squares`,
+//!  * `pie` for the pie rule,
+//!  * `blind=<property>` for Blind Quarto, hiding `tall`, `light`, `round` or `full`,
+//!  * `race=<points>` for the scoring variant.
+//!
+//! For example `s;corners;pie`.
+
+use crate::{
+    field::{Field, Pos},
+    game::Game,
+    pattern::WinPattern,
+    piece::{Piece, Property},
+};
+
+#[cfg(feature = "unstable")]
+use crate::{piece::PropertyNames, results::json_string};
+
+/// The rules a game is played with, see the [module documentation](self)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Ruleset {
+    /// Winning patterns besides rows, columns and diagonals
+    pub patterns: Vec<WinPattern>,
+    /// If true, the player moving after the first placement may swap sides instead.
+    pub pie_rule: bool,
+    /// With Blind Quarto, this property of unplaced pieces is hidden from the player placing.
+    pub hidden_property: Option<Property>,
+    /// With the scoring variant, the points needed to win
+    pub race_to: Option<u32>,
+}
+
+impl Ruleset {
+    /// The tournament-standard rules: rows, columns and diagonals win, without any variants
+    pub fn standard() -> Self {
+        Self::default()
+    }
+
+    /// How these rules differ from the [standard](Self::standard) ones, a description per
+    /// variant, such as `the winning pattern squares`
+    pub fn deviations(&self) -> Vec<String> {
+        let mut deviations: Vec<String> = self
+            .patterns
+            .iter()
+            .map(|pattern| format!("the winning pattern {pattern}"))
+            .collect();
+        if self.pie_rule {
+            deviations.push("the pie rule".to_string());
+        }
+        if let Some(property) = self.hidden_property {
+            deviations.push(format!("Blind Quarto, hiding {}", property.name()));
+        }
+        if let Some(target) = self.race_to {
+            deviations.push(format!("the scoring variant, to {target} points"));
+        }
+        deviations
+    }
+
+    /// Describes these rules as JSON, for frontends (`quarto rules --json`): the board size,
+    /// the properties with their code letters and the value words of `names`, the rows, columns
+    /// and diagonals, the patterns with every place they win at, the variant flags, and whether
+    /// the rules are the standard ones. Spaces are `[x, y]`, starting at 1. The format is
+    /// unstable, like the [results](crate::results) it shares the encoding with.
+    #[cfg(feature = "unstable")]
+    pub fn to_json(&self, names: &PropertyNames) -> String {
+        let spaces = |spaces: &[Pos; 4]| {
+            let spaces: Vec<String> = spaces
+                .iter()
+                .map(|&(x, y)| format!("[{}, {}]", x + 1, y + 1))
+                .collect();
+            format!("[{}]", spaces.join(", "))
+        };
+        let properties: Vec<String> = Piece::CODE_LETTERS
+            .iter()
+            .map(|&(prop, set, unset)| {
+                format!(
+                    "    {{\"property\": {}, \"values\": [{}, {}], \"letters\": [\"{set}\", \"{unset}\"]}}",
+                    json_string(prop.name()),
+                    json_string(names.value(prop, true)),
+                    json_string(names.value(prop, false))
+                )
+            })
+            .collect();
+        let lines: Vec<String> = Field::new()
+            .lines()
+            .iter()
+            .map(|line| format!("    {}", spaces(line)))
+            .collect();
+        let patterns: Vec<String> = self
+            .patterns
+            .iter()
+            .map(|pattern| {
+                let placements: Vec<String> = pattern.placements().iter().map(spaces).collect();
+                format!(
+                    "    {{\"pattern\": {}, \"name\": {}, \"placements\": [{}]}}",
+                    json_string(&pattern.to_string()),
+                    pattern.name().map_or("null".to_string(), json_string),
+                    placements.join(", ")
+                )
+            })
+            .collect();
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "null".to_string());
+        format!(
+            "{{\n  \"notation\": {},\n  \"standard\": {},\n  \"board_size\": {},\n  \"pieces\": {},\n  \"properties\": [\n{}\n  ],\n  \"lines\": [\n{}\n  ],\n  \"patterns\": [{}],\n  \"pie_rule\": {},\n  \"hidden_property\": {},\n  \"race_to\": {}\n}}\n",
+            json_string(&self.notation()),
+            self.deviations().is_empty(),
+            Field::SIZE,
+            Piece::all().count(),
+            properties.join(",\n"),
+            lines.join(",\n"),
+            if patterns.is_empty() {
+                String::new()
+            } else {
+                format!("\n{}\n  ", patterns.join(",\n"))
+            },
+            self.pie_rule,
+            optional(self.hidden_property.map(|prop| json_string(prop.name()))),
+            optional(self.race_to.map(|target| target.to_string())),
+        )
+    }
+
+    /// Checks that the variants make sense, on their own and together.
+    pub fn validate(&self) -> Result<(), String> {
+        let lines = Field::new().lines();
+        for (i, pattern) in self.patterns.iter().enumerate() {
+            if self.patterns[..i].contains(pattern) {
+                return Err(format!("The pattern {pattern} is given twice"));
+            }
+            if pattern
+                .placements()
+                .iter()
+                .all(|placement| lines.iter().any(|line| same_spaces(line, placement)))
+            {
+                return Err(format!(
+                    "The pattern {pattern} only repeats rows, columns or diagonals"
+                ));
+            }
+        }
+
+        if let Some(target) = self.race_to {
+            let max_points = self.max_points();
+            if target == 0 {
+                return Err("The scoring variant needs at least one point to win".to_string());
+            }
+            if target > max_points {
+                return Err(format!(
+                    "{target} points can never be reached, at most {max_points} can be scored"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Parses rules, see the [module documentation](self) for the format. The rules are not
+    /// validated.
+    pub fn from_notation(s: &str) -> Result<Self, String> {
+        let mut rules = Self::default();
+        if s.trim() == "-" {
+            return Ok(rules);
+        }
+        for variant in s.split(';').map(str::trim).filter(|v| !v.is_empty()) {
+            if variant == "s" {
+                rules.patterns.push(WinPattern::square());
+            } else if variant == "pie" {
+                rules.pie_rule = true;
+            } else if let Some(property) = variant.strip_prefix("blind=") {
+                rules.hidden_property = Some(
+                    Property::from_name(property)
+                        .map_err(|()| format!("invalid property `{property}`"))?,
+                );
+            } else if let Some(target) = variant.strip_prefix("race=") {
+                rules.race_to = Some(
+                    target
+                        .parse()
+                        .map_err(|_| format!("invalid number of points `{target}`"))?,
+                );
+            } else {
+                rules.patterns.push(WinPattern::parse(variant)?);
+            }
+        }
+        Ok(rules)
+    }
+
+    /// Formats these rules, see the [module documentation](self) for the format.
+    pub fn notation(&self) -> String {
+        let mut variants: Vec<String> = if self.patterns == [WinPattern::square()] {
+            vec!["s".to_string()]
+        } else {
+            self.patterns.iter().map(ToString::to_string).collect()
+        };
+        if self.pie_rule {
+            variants.push("pie".to_string());
+        }
+        if let Some(property) = self.hidden_property {
+            variants.push(format!("blind={}", property.name()));
+        }
+        if let Some(target) = self.race_to {
+            variants.push(format!("race={target}"));
+        }
+        if variants.is_empty() {
+            "-".to_string()
+        } else {
+            variants.join(";")
+        }
+    }
+
+    /// Returns true if both rules are the same, whatever the order of their patterns
+    pub fn same_as(&self, other: &Ruleset) -> bool {
+        self.patterns.len() == other.patterns.len()
+            && self.patterns.iter().all(|p| other.patterns.contains(p))
+            && self.pie_rule == other.pie_rule
+            && self.hidden_property == other.hidden_property
+            && self.race_to == other.race_to
+    }
+
+    /// An upper bound of the points that can be scored in a game with the scoring variant: every
+    /// point clears at least four pieces, and a single piece can complete at most all lines
+    /// through its space.
+    fn max_points(&self) -> u32 {
+        let mut field = Field::new();
+        for pattern in &self.patterns {
+            field.add_pattern(pattern.clone());
+        }
+        let lines = field.lines();
+        let lines_per_space = (0..Field::SIZE)
+            .flat_map(|x| (0..Field::SIZE).map(move |y| (x, y)))
+            .map(|pos| lines.iter().filter(|line| line.contains(&pos)).count())
+            .max()
+            .unwrap();
+        u32::try_from(Field::SIZE * Field::SIZE / 4 * lines_per_space).unwrap()
+    }
+}
+
+/// How a set of pieces differs from the standard one, where each of the sixteen pieces exists
+/// once, such as `the piece SDQH is used twice`
+pub fn audit_pieces(pieces: &[Piece]) -> Vec<String> {
+    let mut deviations = vec![];
+    for piece in Piece::all() {
+        match pieces.iter().filter(|&&p| p == piece).count() {
+            0 => deviations.push(format!("the piece {} is missing", piece.code())),
+            1 => {}
+            2 => deviations.push(format!("the piece {} is used twice", piece.code())),
+            n => deviations.push(format!("the piece {} is used {n} times", piece.code())),
+        }
+    }
+    deviations
+}
+
+/// How `game` differs from tournament-standard Quarto, such as in the pieces, which have to be
+/// the sixteen standard ones, or the [rules](Ruleset::deviations). Meant for positions from
+/// sources of dubious quality; an empty list means the game is standard.
+pub fn audit(game: &Game) -> Vec<String> {
+    let mut pieces = game.field.pieces();
+    pieces.extend(game.next_piece());
+    pieces.extend_from_slice(game.remaining_pieces());
+    let mut deviations = audit_pieces(&pieces);
+    if game.running() && game.field.check_field_for_win() {
+        deviations.push("a line is completed, but the game goes on".to_string());
+    }
+    deviations.extend(game.rules().deviations());
+    deviations
+}
+
+/// Returns true if both lines consist of the same spaces, in any order
+fn same_spaces(a: &[Pos; 4], b: &[Pos; 4]) -> bool {
+    a.iter().all(|pos| b.contains(pos))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{game::ArrayBase, pattern::WinPattern};
+
+    use crate::{
+        field::Field,
+        game::{Game, Player},
+        piece::Piece,
+    };
+
+    use super::{audit, audit_pieces, Ruleset};
+
+    fn with_patterns(patterns: &str) -> Ruleset {
+        Ruleset {
+            patterns: WinPattern::parse_list(patterns, ArrayBase::One).unwrap(),
+            ..Ruleset::default()
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Ruleset::default().validate().is_ok());
+        assert!(with_patterns("squares;corners;center;plus")
+            .validate()
+            .is_ok());
+        assert!(with_patterns("squares;2,2+3,2+2,3+3,3").validate().is_err());
+        // Rows, or a diagonal
+        assert!(with_patterns("1,1+2,1+3,1+4,1").validate().is_err());
+        assert!(with_patterns("@1,1+2,2+3,3+4,4").validate().is_err());
+        // Three spaces of a row don't make a row
+        assert!(with_patterns("1,1+2,1+3,1+3,2").validate().is_ok());
+    }
+
+    #[test]
+    fn test_notation() {
+        for notation in ["-", "s", "corners;1,1+2,1+3,1+2,2;pie;blind=full;race=3"] {
+            let rules = Ruleset::from_notation(notation).unwrap();
+            assert_eq!(rules.notation(), notation);
+        }
+        assert_eq!(Ruleset::from_notation("squares").unwrap().notation(), "s");
+        assert!(Ruleset::from_notation("corners;center")
+            .unwrap()
+            .same_as(&Ruleset::from_notation("center;corners").unwrap()));
+        assert!(!Ruleset::from_notation("corners;pie")
+            .unwrap()
+            .same_as(&Ruleset::from_notation("corners").unwrap()));
+        assert!(Ruleset::from_notation("blind=weight").is_err());
+        assert!(Ruleset::from_notation("race=x").is_err());
+        assert!(Ruleset::from_notation("diamonds").is_err());
+    }
+
+    #[test]
+    fn test_race_to() {
+        let mut rules = Ruleset {
+            race_to: Some(0),
+            ..Ruleset::default()
+        };
+        assert!(rules.validate().is_err());
+        rules.race_to = Some(12);
+        assert!(rules.validate().is_ok());
+        rules.race_to = Some(13);
+        assert!(rules.validate().is_err());
+        rules.patterns = vec![WinPattern::square()];
+        assert!(rules.validate().is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "unstable")]
+    fn test_to_json() {
+        let names = crate::piece::PropertyNames::english();
+        let standard = Ruleset::standard().to_json(&names);
+        assert!(standard.contains("\"standard\": true,"));
+        assert!(standard.contains("\"patterns\": [],"));
+        assert!(standard.contains("\"race_to\": null\n"));
+        assert!(standard.contains("[[4, 1], [3, 2], [2, 3], [1, 4]]\n"));
+        assert!(standard.contains(
+            "{\"property\": \"light\", \"values\": [\"light\", \"dark\"], \"letters\": [\"L\", \"D\"]}"
+        ));
+
+        let rules = Ruleset::from_notation("corners;blind=round").unwrap();
+        let json = rules.to_json(&names);
+        assert!(json.contains("\"standard\": false,"));
+        assert!(json.contains(
+            "{\"pattern\": \"corners\", \"name\": \"corners\", \"placements\": [[[1, 1], [4, 1], [1, 4], [4, 4]]]}"
+        ));
+        assert!(json.contains("\"hidden_property\": \"round\","));
+    }
+
+    #[test]
+    fn test_audit() {
+        let game = Game::new(Player::PlayerOne);
+        assert!(audit(&game).is_empty());
+        let mut rules = Ruleset::from_notation("s;pie;race=3").unwrap();
+        assert_eq!(
+            rules.deviations(),
+            [
+                "the winning pattern squares",
+                "the pie rule",
+                "the scoring variant, to 3 points"
+            ]
+        );
+        rules = Ruleset::standard();
+        assert!(rules.deviations().is_empty());
+
+        let mut won = Game::new(Player::PlayerOne);
+        won.field = Field::parse_grid("TLRF SDRH TDRF SLRH\n. . . .\n. . . .\n. . . .").unwrap();
+        assert_eq!(
+            audit(&won)[..2],
+            [
+                "the piece SDRH is used twice",
+                "the piece TDRF is used twice"
+            ]
+        );
+        assert!(audit(&won).contains(&"a line is completed, but the game goes on".to_string()));
+
+        let sdqh = Piece::from_code("SDQH").unwrap();
+        let deviations = audit_pieces(&[sdqh; 3]);
+        assert_eq!(deviations.len(), 16);
+        assert_eq!(
+            deviations[..2],
+            [
+                "the piece SDQH is used 3 times",
+                "the piece TDQH is missing"
+            ]
+        );
+    }
+}
@@ -0,0 +1,353 @@
+//! A series of games between the same two players, such as "best of 5".
+
+use std::{fmt::Write, time::Duration};
+
+use crate::{
+    game::{Game, Player},
+    validate::FILE_BASE,
+};
+
+/// Several games between the same two players, and their results
+#[derive(Debug, Clone)]
+pub struct Series {
+    /// The number of games in this series, `None` for an open-ended series.
+    pub best_of: Option<u32>,
+    /// The display names of player one and player two
+    names: [String; 2],
+    /// All finished games
+    games: Vec<Game>,
+    /// For each finished game, why it was forfeited, if it was
+    forfeits: Vec<Option<String>>,
+}
+
+impl Series {
+    /// A series of `best_of` games, open-ended if `None`, between the players `names`
+    pub fn new(best_of: Option<u32>, names: [String; 2]) -> Self {
+        Self {
+            best_of,
+            names,
+            games: vec![],
+            forfeits: vec![],
+        }
+    }
+
+    /// The display name of a player
+    pub fn name(&self, player: Player) -> &str {
+        match player {
+            Player::PlayerOne => &self.names[0],
+            Player::PlayerTwo => &self.names[1],
+        }
+    }
+
+    /// The player with their name, such as `Player 1 (Alice)`
+    pub fn label(&self, player: Player) -> String {
+        format!("{player} ({})", self.name(player))
+    }
+
+    /// Players take turns in starting the games, player one starts the first one.
+    fn starting_player(game_idx: usize) -> Player {
+        if game_idx.is_multiple_of(2) {
+            Player::PlayerOne
+        } else {
+            Player::PlayerTwo
+        }
+    }
+
+    /// The player starting the next game
+    pub fn next_starting_player(&self) -> Player {
+        Self::starting_player(self.games.len())
+    }
+
+    /// Records the result of a finished game
+    pub fn record(&mut self, game: Game) {
+        debug_assert!(!game.running(), "only finished games can be recorded");
+        self.games.push(game);
+        self.forfeits.push(None);
+    }
+
+    /// Records a game that was [forfeited](Game::forfeit), with the reason, such as `Player 1
+    /// forfeits: the bot exited`
+    pub fn record_forfeit(&mut self, game: Game, reason: String) {
+        self.record(game);
+        *self.forfeits.last_mut().unwrap() = Some(reason);
+    }
+
+    /// How a finished game ended: `line`, `points` with the scoring variant, `draw`, or
+    /// `forfeit` with the reason
+    fn termination(&self, game_idx: usize) -> String {
+        let game = &self.games[game_idx];
+        if let Some(reason) = &self.forfeits[game_idx] {
+            format!("forfeit, {reason}")
+        } else if game.winner().is_none() {
+            "draw".to_string()
+        } else if game.race_to.is_some() {
+            "points".to_string()
+        } else {
+            "line".to_string()
+        }
+    }
+
+    /// The number of games `player` won
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn wins(&self, player: Player) -> u32 {
+        self.games
+            .iter()
+            .filter(|game| game.winner() == Some(player))
+            .count() as u32
+    }
+
+    /// The number of drawn games
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn draws(&self) -> u32 {
+        self.games.len() as u32 - self.wins(Player::PlayerOne) - self.wins(Player::PlayerTwo)
+    }
+
+    /// Returns true once no more games are needed to decide this series.
+    pub fn is_decided(&self) -> bool {
+        let Some(best_of) = self.best_of else {
+            return false;
+        };
+        self.winner().is_some() || self.games.len() >= best_of as usize
+    }
+
+    /// The player that won the majority of the series, if any
+    pub fn winner(&self) -> Option<Player> {
+        let best_of = self.best_of?;
+        [Player::PlayerOne, Player::PlayerTwo]
+            .into_iter()
+            .find(|&player| self.wins(player) * 2 > best_of)
+    }
+
+    /// The running score, such as `Player 1 (Alice) 2 - 1 Player 2 (Bob) (1 draw)`
+    pub fn score(&self) -> String {
+        let mut score = format!(
+            "{} {} - {} {}",
+            self.label(Player::PlayerOne),
+            self.wins(Player::PlayerOne),
+            self.wins(Player::PlayerTwo),
+            self.label(Player::PlayerTwo)
+        );
+        match self.draws() {
+            0 => {}
+            1 => score.push_str(" (1 draw)"),
+            draws => write!(score, " ({draws} draws)").unwrap(),
+        }
+        score
+    }
+
+    /// The total time `player` took for their timed moves, and the number of those moves
+    pub fn thinking_time(&self, player: Player) -> (Duration, u32) {
+        let mut ret = (Duration::ZERO, 0);
+        for game in &self.games {
+            for (ply, (mover, _)) in game.history_with_players().into_iter().enumerate() {
+                if let Some(time) = game.move_time(ply).filter(|_| mover == player) {
+                    ret.0 += time;
+                    ret.1 += 1;
+                }
+            }
+        }
+        ret
+    }
+
+    /// How long both players took, such as `Thinking time: Player 1 (Alice) 12.0s, 2.0s per
+    /// move; Player 2 (AI) 0.6s, 0.1s per move`, if any moves were timed
+    pub fn thinking_times(&self) -> Option<String> {
+        let times: Vec<String> = [Player::PlayerOne, Player::PlayerTwo]
+            .into_iter()
+            .filter_map(|player| {
+                let (total, moves) = self.thinking_time(player);
+                (moves > 0).then(|| {
+                    format!(
+                        "{} {:.1}s, {:.1}s per move",
+                        self.label(player),
+                        total.as_secs_f64(),
+                        (total / moves).as_secs_f64()
+                    )
+                })
+            })
+            .collect();
+        (!times.is_empty()).then(|| format!("Thinking time: {}", times.join("; ")))
+    }
+
+    /// The transcript of all games of this series, so far
+    pub fn transcript(&self) -> String {
+        let mut ret = String::new();
+        if let Some(best_of) = self.best_of {
+            writeln!(ret, "# Quarto series, best of {best_of}").unwrap();
+        } else {
+            writeln!(ret, "# Quarto series").unwrap();
+        }
+        for player in [Player::PlayerOne, Player::PlayerTwo] {
+            writeln!(ret, "# {player}: {}", self.name(player)).unwrap();
+        }
+
+        for (i, game) in self.games.iter().enumerate() {
+            writeln!(ret).unwrap();
+            writeln!(ret, "[Game {}]", i + 1).unwrap();
+            for pattern in game.field.patterns() {
+                writeln!(ret, "Pattern: {pattern}").unwrap();
+            }
+            for (ply, (player, mv)) in game.history_with_players().into_iter().enumerate() {
+                write!(ret, "{}. {player}: {}", ply + 1, mv.to_notation(FILE_BASE)).unwrap();
+                if let Some(time) = game.move_time(ply) {
+                    write!(ret, " ({:.1}s)", time.as_secs_f64()).unwrap();
+                }
+                writeln!(ret).unwrap();
+            }
+            match game.winner() {
+                Some(winner) => writeln!(ret, "Result: {} won", self.label(winner)).unwrap(),
+                None => writeln!(ret, "Result: draw").unwrap(),
+            }
+            writeln!(ret, "Termination: {}", self.termination(i)).unwrap();
+        }
+
+        writeln!(ret).unwrap();
+        writeln!(ret, "Score: {}", self.score()).unwrap();
+        if let Some(times) = self.thinking_times() {
+            writeln!(ret, "{times}").unwrap();
+        }
+        if self.is_decided() {
+            match self.winner() {
+                Some(winner) => writeln!(ret, "{} won the series", self.label(winner)).unwrap(),
+                None => writeln!(ret, "The series ended in a draw").unwrap(),
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        game::{ArrayBase, Game, Player, Status},
+        piece::Piece,
+        validate::validate,
+    };
+
+    use super::Series;
+
+    fn finished_game(starting_player: Player, status: Status) -> Game {
+        let mut game = Game::new(starting_player);
+        game.initial_move(game.remaining_pieces()[0]).unwrap();
+        game.status = status;
+        game
+    }
+
+    #[test]
+    fn test_transcript_base() {
+        let mut game = Game::new(Player::PlayerOne);
+        game.array_base = ArrayBase::Zero;
+        game.initial_move(Piece::from_code("SDQH").unwrap())
+            .unwrap();
+        game.do_move((0, 0), Piece::from_code("TDQH").unwrap())
+            .unwrap();
+        let field = game.field.clone();
+        game.forfeit(Player::PlayerOne);
+        let mut series = Series::new(None, ["Alice".into(), "Bob".into()]);
+        series.record(game);
+        let transcript = series.transcript();
+        assert!(transcript.contains("2. Player 2: put 1,1, give TDQH"));
+
+        // Read back without -0, the moves land on the same spaces.
+        let moves = transcript
+            .lines()
+            .filter(|line| line.starts_with(|c: char| c.is_ascii_digit()))
+            .map(ToString::to_string);
+        let replayed = validate(&Game::new(Player::PlayerOne), moves, |_, _| {}).unwrap();
+        assert_eq!(replayed.field, field);
+    }
+
+    #[test]
+    fn test_best_of_three() {
+        let mut series = Series::new(Some(3), ["Alice".into(), "Bob".into()]);
+        assert_eq!(series.next_starting_player(), Player::PlayerOne);
+
+        series.record(finished_game(
+            Player::PlayerOne,
+            Status::Won {
+                winner: Player::PlayerTwo,
+            },
+        ));
+        assert_eq!(series.next_starting_player(), Player::PlayerTwo);
+        assert!(!series.is_decided());
+
+        series.record(finished_game(
+            Player::PlayerTwo,
+            Status::Draw {
+                last_player: Player::PlayerOne,
+            },
+        ));
+        assert_eq!(
+            series.score(),
+            "Player 1 (Alice) 0 - 1 Player 2 (Bob) (1 draw)"
+        );
+        assert!(!series.is_decided());
+
+        series.record(finished_game(
+            Player::PlayerOne,
+            Status::Won {
+                winner: Player::PlayerTwo,
+            },
+        ));
+        assert!(series.is_decided());
+        assert_eq!(series.winner(), Some(Player::PlayerTwo));
+
+        assert_eq!(series.thinking_times(), None);
+        let transcript = series.transcript();
+        assert!(transcript.contains("[Game 3]"));
+        assert!(transcript.contains("1. Player 1: give SDQH"));
+        assert!(transcript.contains("Player 2 (Bob) won the series"));
+        assert!(transcript.contains("Result: draw\nTermination: draw\n"));
+        assert!(transcript.contains("Result: Player 2 (Bob) won\nTermination: line\n"));
+    }
+
+    #[test]
+    fn test_thinking_time() {
+        let mut game = Game::new(Player::PlayerOne);
+        game.initial_move(game.remaining_pieces()[0]).unwrap();
+        game.set_move_time(Duration::from_millis(1500));
+        game.do_move((0, 0), game.remaining_pieces()[0]).unwrap();
+        game.set_move_time(Duration::from_millis(200));
+        game.do_move((1, 0), game.remaining_pieces()[0]).unwrap();
+        game.set_move_time(Duration::from_millis(2500));
+        game.status = Status::Draw {
+            last_player: Player::PlayerOne,
+        };
+        let mut series = Series::new(None, ["Alice".into(), "AI".into()]);
+        series.record(game);
+        assert_eq!(
+            series.thinking_time(Player::PlayerOne),
+            (Duration::from_secs(4), 2)
+        );
+        assert_eq!(
+            series.thinking_times().unwrap(),
+            "Thinking time: Player 1 (Alice) 4.0s, 2.0s per move; Player 2 (AI) 0.2s, 0.2s per move"
+        );
+        assert!(series.transcript().contains("2. Player 2: put 1,1, give "));
+        assert!(series.transcript().contains(" (0.2s)\n"));
+    }
+
+    #[test]
+    fn test_open_ended() {
+        let mut series = Series::new(None, ["Alice".into(), "Bob".into()]);
+        for _ in 0..5 {
+            series.record(finished_game(
+                Player::PlayerOne,
+                Status::Won {
+                    winner: Player::PlayerOne,
+                },
+            ));
+        }
+        assert!(!series.is_decided());
+        assert_eq!(series.winner(), None);
+
+        let mut forfeited = Game::new(Player::PlayerOne);
+        forfeited.forfeit(Player::PlayerOne);
+        series.record_forfeit(forfeited, "Player 1 forfeits: the bot exited".to_string());
+        assert!(series
+            .transcript()
+            .contains("[Game 6]\nResult: Player 2 (Bob) won\nTermination: forfeit, Player 1 forfeits: the bot exited\n"));
+    }
+}
@@ -0,0 +1,265 @@
+//! Share codes: a whole game, its rules and every move played, as a short URL-safe string, see
+//! [`Game::to_share_code`].
+//!
+//! The code is the URL-safe base64 (without padding) of:
+//!  * a version byte, `VERSION`, see [`crate::version`],
+//!  * a flags byte: bit 0 is set if player two started, bit 1 if the game was set up from a
+//!    position,
+//!  * the length of the rules [notation](crate::rules), followed by the notation, empty for the
+//!    standard rules,
+//!  * for games set up from a position, the [packed](Game::pack) position, little-endian,
+//!  * a byte per move: the [index](Piece::index) of the piece given for the initial move, `y * 4 + x` in the
+//!    high and the piece given in the low nibble for placements, and 0 or 1 for keeping or
+//!    swapping sides. Placements ending the game give the piece just placed.
+
+use crate::{
+    field::{Field, Pos},
+    game::{Game, Move, Player, Status},
+    piece::Piece,
+    rules::Ruleset,
+    version,
+};
+
+/// The version of the format, increased on incompatible changes
+const VERSION: u8 = 1;
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[allow(clippy::cast_possible_truncation)]
+fn pos_index((x, y): Pos) -> u8 {
+    (y * Field::SIZE + x) as u8
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut ret = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0_u32, |acc, (i, &b)| acc | u32::from(b) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            ret.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 0x3F] as char);
+        }
+    }
+    ret
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn decode(s: &str) -> Option<Vec<u8>> {
+    let values: Vec<u32> = s
+        .bytes()
+        .map(|c| ALPHABET.iter().position(|&a| a == c).map(|v| v as u32))
+        .collect::<Option<_>>()?;
+    let mut ret = vec![];
+    for chunk in values.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, &v)| acc | v << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            ret.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(ret)
+}
+
+impl Game {
+    /// Encodes the rules and all moves of this game as a share code, see the
+    /// [module documentation](self). For games set up from a position, that's the position and
+    /// the moves after it.
+    pub fn to_share_code(&self) -> String {
+        let rules = self.rules();
+        let notation = if rules == Ruleset::default() {
+            String::new()
+        } else {
+            rules.notation()
+        };
+        let flags = u8::from(self.starting_player() == Player::PlayerTwo)
+            | u8::from(self.setup().is_some()) << 1;
+        let mut bytes = vec![VERSION, flags];
+        bytes.push(u8::try_from(notation.len()).expect("rules are never that long"));
+        bytes.extend(notation.bytes());
+        if let Some(setup) = self.setup() {
+            bytes.extend(setup.to_le_bytes());
+        }
+
+        let mut last_given = None;
+        for &mv in self.history() {
+            bytes.push(match mv {
+                Move::Initial { next_piece } => next_piece.index(),
+                Move::Place { pos, next_piece } => {
+                    let next_piece = next_piece.or(last_given).unwrap();
+                    pos_index(pos) << 4 | next_piece.index()
+                }
+                Move::Swap { accepted } => u8::from(accepted),
+            });
+            if let Move::Initial { next_piece }
+            | Move::Place {
+                next_piece: Some(next_piece),
+                ..
+            } = mv
+            {
+                last_given = Some(next_piece);
+            }
+        }
+        encode(&bytes)
+    }
+
+    /// Decodes a share code, replaying all its moves. The settings besides the rules, such as
+    /// those of the AI, are taken from `base`.
+    pub fn from_share_code(code: &str, base: &Game) -> Result<Self, String> {
+        let err = || format!("invalid share code `{code}`");
+        let bytes = decode(code.trim()).ok_or_else(err)?;
+        let [version, flags, len, rest @ ..] = &bytes[..] else {
+            return Err(err());
+        };
+        if *version == 0 {
+            return Err(err());
+        }
+        version::check("share code", u32::from(*version), u32::from(VERSION))?;
+        let (notation, moves) = rest.split_at_checked(usize::from(*len)).ok_or_else(err)?;
+        let rules = match std::str::from_utf8(notation).map_err(|_| err())? {
+            "" => Ruleset::default(),
+            notation => Ruleset::from_notation(notation)?,
+        };
+        rules.validate()?;
+
+        let (setup, moves) = if flags & 2 == 0 {
+            (None, moves)
+        } else {
+            let (setup, moves) = moves.split_first_chunk::<16>().ok_or_else(err)?;
+            (Some(u128::from_le_bytes(*setup)), moves)
+        };
+        let starting_player = if flags & 1 == 0 {
+            Player::PlayerOne
+        } else {
+            Player::PlayerTwo
+        };
+        let mut game = match setup {
+            Some(setup) => Game::unpack(setup).map_err(|()| err())?,
+            None => Game::new(starting_player),
+        };
+        game.copy_settings(base);
+        game.set_rules(&rules);
+
+        for &byte in moves {
+            let piece = Piece::from_index(byte & 0x0F).unwrap();
+            match game.status {
+                Status::InitialMove { .. } if byte >> 4 == 0 => game.initial_move(piece),
+                Status::SwapDecision { .. } if byte <= 1 => game.decide_swap(byte == 1),
+                Status::Move {
+                    next_piece: placed, ..
+                } => {
+                    let pos = (
+                        usize::from(byte >> 4) % Field::SIZE,
+                        usize::from(byte >> 4) / Field::SIZE,
+                    );
+                    // The placed piece marks the end of the game, any piece would do then.
+                    let ending = piece == placed;
+                    let next_piece = if ending {
+                        game.remaining_pieces().first().copied().unwrap_or(placed)
+                    } else {
+                        piece
+                    };
+                    game.do_move(pos, next_piece).and_then(|()| {
+                        if ending == game.running() {
+                            Err(())
+                        } else {
+                            Ok(())
+                        }
+                    })
+                }
+                _ => Err(()),
+            }
+            .map_err(|()| err())?;
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "engine")]
+    use crate::ai::SimpleAi;
+    use crate::{
+        field::Field,
+        game::{Game, Player},
+        piece::Piece,
+        rules::Ruleset,
+    };
+
+    use super::{decode, encode};
+
+    #[test]
+    fn test_base64() {
+        for bytes in [&b""[..], b"a", b"ab", b"abc", b"\xff\x00\x10\x80"] {
+            assert_eq!(decode(&encode(bytes)).unwrap(), bytes);
+        }
+        assert_eq!(encode(b"Quarto"), "UXVhcnRv");
+        assert!(decode("UXVhcnRv!").is_none());
+        assert!(decode("UXVhc").is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "engine")]
+    fn test_share_code() {
+        let base = Game::new(Player::PlayerOne);
+        for rules in ["-", "s;pie", "race=2"] {
+            let rules = Ruleset::from_notation(rules).unwrap();
+            let mut game = Game::with_rules(Player::PlayerTwo, &rules).unwrap();
+            let mut ais = [Player::PlayerOne, Player::PlayerTwo]
+                .map(|player| SimpleAi::with_seed(player, 1337));
+            while game.running() {
+                let code = game.to_share_code();
+                let shared = Game::from_share_code(&code, &base).unwrap();
+                assert_eq!(shared.history(), game.history());
+                assert_eq!(shared.status, game.status);
+                game = ais[game.player().index()].play_iteratively(&mut game);
+            }
+            let shared = Game::from_share_code(&game.to_share_code(), &base).unwrap();
+            assert_eq!(shared.history(), game.history());
+            assert_eq!(shared.status, game.status);
+            assert_eq!(shared.rules(), rules);
+        }
+    }
+
+    #[test]
+    fn test_position_share_code() {
+        let base = Game::new(Player::PlayerOne);
+        let field = Field::parse_grid(". . . .\n. TLRF . .\n. . . .\n. . . SDQH").unwrap();
+        let mut game =
+            Game::from_position(field, Player::PlayerTwo, Piece::from_code("TLQH").ok()).unwrap();
+        game.set_rules(&Ruleset::from_notation("s").unwrap());
+        let shared = Game::from_share_code(&game.to_share_code(), &base).unwrap();
+        assert_eq!(shared.field, game.field);
+        assert_eq!(shared.status, game.status);
+
+        game.do_move((0, 0), Piece::from_code("SLRF").unwrap())
+            .unwrap();
+        let shared = Game::from_share_code(&game.to_share_code(), &base).unwrap();
+        assert_eq!(shared.pack(), game.pack());
+        assert_eq!(shared.history(), game.history());
+        assert_eq!(shared.rules(), game.rules());
+    }
+
+    #[test]
+    fn test_invalid_share_code() {
+        let base = Game::new(Player::PlayerOne);
+        let game = Game::new(Player::PlayerOne);
+        let code = game.to_share_code();
+        assert_eq!(code, "AQAA");
+        assert!(Game::from_share_code(&code, &base).is_ok());
+        // Version 2
+        assert!(Game::from_share_code("AgAA", &base).is_err());
+        // Giving the same piece twice
+        let mut game = Game::new(Player::PlayerOne);
+        game.initial_move(game.remaining_pieces()[0]).unwrap();
+        let mut bytes = decode(&game.to_share_code()).unwrap();
+        bytes.push(bytes[3]);
+        assert!(Game::from_share_code(&encode(&bytes), &base).is_err());
+        assert!(Game::from_share_code("not a code", &base).is_err());
+    }
+}
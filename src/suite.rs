@@ -0,0 +1,391 @@
+//! Test position suites, in an EPD-like text format ("Quarto Position Description", `.qpd`).
+//!
+//! Every non-empty line not starting with `#` describes one position:
+//!
+//! ```text
+//! TLRFSDRHTDRF1/4/4/4 SLRH - bm 4,1; id "win in 1: row";
+//! ```
+//!
+//! The fields are
+//!  * the board, rows from top to bottom separated by `/`. A row consists of piece codes (see
+//!    [`Piece::code`]) and digits, counting empty spaces,
+//!  * the piece code of the piece to place, or `-` for the initial move,
+//!  * the rules, such as `-` for the standard rules or `s` for square mode, see
+//!    [`crate::rules`],
+//!  * operations, each ending with `;`:
+//!    - `bm <moves>`: the engine's move has to be one of the given moves,
+//!    - `am <moves>`: the engine's move must not be any of the given moves,
+//!    - `safe`: the engine must not give a piece that lets the opponent win immediately,
+//!    - `dm <n>`: the side to move can force a win within `n` moves of both players, and the
+//!      engine's move must keep it that way,
+//!    - `id "<name>"` and any other operation are kept as metadata, such as `c0 "comment"`.
+//!
+//! A move is `x,y`, putting the piece to x,y, or `x,y:CODE`, also giving the piece `CODE`.
+//! `x` or `y` may be `*` to match any coordinate. Coordinates start at 1.
+
+use std::fmt::Write;
+
+use crate::{
+    field::{Field, Pos},
+    game::{ArrayBase, Game, Move, Player},
+    piece::Piece,
+    rules::Ruleset,
+};
+
+/// Coordinates in suites are always 1-based, whatever the user prefers.
+const SUITE_BASE: ArrayBase = ArrayBase::One;
+
+/// A (partial) move to compare an engine's move against
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MovePattern {
+    /// The x coordinate, `None` matches all
+    pub x: Option<usize>,
+    /// The y coordinate, `None` matches all
+    pub y: Option<usize>,
+    /// The piece to give, `None` matches all
+    pub next_piece: Option<Piece>,
+}
+
+impl MovePattern {
+    fn parse(s: &str) -> Result<Self, String> {
+        let err = || format!("invalid move `{s}`");
+        let (pos, next_piece) = match s.split_once(':') {
+            Some((pos, piece)) => (pos, Some(Piece::from_code(piece).map_err(|()| err())?)),
+            None => (s, None),
+        };
+        let (x, y) = pos.split_once(',').ok_or_else(err)?;
+        let coord = |c: &str| -> Result<Option<usize>, String> {
+            if c == "*" {
+                return Ok(None);
+            }
+            let c = SUITE_BASE.unbased(c.parse().map_err(|_| err())?);
+            if c < Field::SIZE {
+                Ok(Some(c))
+            } else {
+                Err(err())
+            }
+        };
+        Ok(Self {
+            x: coord(x)?,
+            y: coord(y)?,
+            next_piece,
+        })
+    }
+
+    /// Returns true if the move putting a piece to `pos` and giving `next_piece` matches.
+    pub fn matches(&self, pos: Pos, next_piece: Option<Piece>) -> bool {
+        self.x.is_none_or(|x| x == pos.0)
+            && self.y.is_none_or(|y| y == pos.1)
+            && self
+                .next_piece
+                .is_none_or(|piece| Some(piece) == next_piece)
+    }
+}
+
+impl std::fmt::Display for MovePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let coord =
+            |c: Option<usize>| c.map_or("*".to_string(), |c| SUITE_BASE.based(c).to_string());
+        write!(f, "{},{}", coord(self.x), coord(self.y))?;
+        if let Some(piece) = self.next_piece {
+            write!(f, ":{}", piece.code())?;
+        }
+        Ok(())
+    }
+}
+
+/// A position of a suite, with what the engine is expected to play there
+#[derive(Debug, Clone)]
+pub struct TestPosition {
+    /// The position
+    pub game: Game,
+    /// The engine has to play one of these moves, if any are given
+    pub best_moves: Vec<MovePattern>,
+    /// The engine must not play one of these moves
+    pub avoid_moves: Vec<MovePattern>,
+    /// The engine must not give a piece that lets the opponent win immediately
+    pub safe: bool,
+    /// The side to move can force a win within this many moves, see [`Game::forced_win_in`].
+    pub forced_win: Option<usize>,
+    /// All other operations, in order, such as `id` or comments
+    pub metadata: Vec<(String, String)>,
+}
+
+impl TestPosition {
+    /// The `id` of this position, or an empty string
+    pub fn id(&self) -> &str {
+        self.metadata
+            .iter()
+            .find(|(op, _)| op == "id")
+            .map_or("", |(_, value)| value)
+    }
+
+    /// Parses a single position, see the [module documentation](self) for the format.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut fields = line.trim().splitn(4, char::is_whitespace);
+        let board = fields.next().ok_or("missing board")?;
+        let hand = fields.next().ok_or("missing piece to place")?;
+        let rules = fields.next().ok_or("missing rules")?;
+        let operations = fields.next().unwrap_or("");
+
+        let field = parse_board(board)?;
+        let rules = Ruleset::from_notation(rules)?;
+        rules.validate()?;
+        let hand = if hand == "-" {
+            None
+        } else {
+            Some(Piece::from_code(hand).map_err(|()| format!("invalid piece `{hand}`"))?)
+        };
+        let mut game = Game::from_position(field, Player::PlayerOne, hand)
+            .map_err(|()| "pieces may only be used once".to_string())?;
+        game.set_rules(&rules);
+
+        let mut position = Self {
+            game,
+            best_moves: vec![],
+            avoid_moves: vec![],
+            safe: false,
+            forced_win: None,
+            metadata: vec![],
+        };
+        for operation in operations.split(';') {
+            let operation = operation.trim();
+            if operation.is_empty() {
+                continue;
+            }
+            let (op, operands) = operation.split_once(' ').unwrap_or((operation, ""));
+            let operands = operands.trim();
+            match op {
+                "bm" | "am" => {
+                    let moves = operands
+                        .split_whitespace()
+                        .map(MovePattern::parse)
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if op == "bm" {
+                        position.best_moves.extend(moves);
+                    } else {
+                        position.avoid_moves.extend(moves);
+                    }
+                }
+                "safe" => position.safe = true,
+                "dm" => {
+                    position.forced_win = Some(
+                        operands
+                            .parse()
+                            .map_err(|_| format!("invalid number of moves `{operands}`"))?,
+                    );
+                }
+                _ => position
+                    .metadata
+                    .push((op.to_string(), operands.trim_matches('"').to_string())),
+            }
+        }
+        Ok(position)
+    }
+
+    /// Formats this position as a single line of a suite.
+    pub fn to_line(&self) -> String {
+        let mut ret = format_board(&self.game.field);
+        let hand = self.game.next_piece().map_or("-".to_string(), Piece::code);
+        let rules = self.game.rules().notation();
+        write!(ret, " {hand} {rules}").unwrap();
+        for (op, moves) in [("bm", &self.best_moves), ("am", &self.avoid_moves)] {
+            if !moves.is_empty() {
+                write!(ret, " {op}").unwrap();
+                for mv in moves {
+                    write!(ret, " {mv}").unwrap();
+                }
+                ret.push(';');
+            }
+        }
+        if self.safe {
+            ret.push_str(" safe;");
+        }
+        if let Some(plies) = self.forced_win {
+            write!(ret, " dm {plies};").unwrap();
+        }
+        for (op, value) in &self.metadata {
+            write!(ret, " {op} \"{value}\";").unwrap();
+        }
+        ret
+    }
+
+    /// Checks the move an engine played in this position, `result` being the game afterwards.
+    pub fn check(&self, result: &Game) -> bool {
+        let Some(&Move::Place { pos, next_piece }) = result.history().last() else {
+            return false;
+        };
+        if !self.best_moves.is_empty()
+            && !self.best_moves.iter().any(|mv| mv.matches(pos, next_piece))
+        {
+            return false;
+        }
+        if self
+            .avoid_moves
+            .iter()
+            .any(|mv| mv.matches(pos, next_piece))
+        {
+            return false;
+        }
+        if let Some(plies) = self.forced_win {
+            if !result.wins_within(self.game.player(), plies.saturating_sub(1)) {
+                return false;
+            }
+        }
+        if self.safe && result.running() {
+            let next_piece = next_piece.expect("a running game has a next piece");
+            return result.field.winning_spaces(next_piece).is_empty();
+        }
+        true
+    }
+}
+
+/// Parses the board part of a position
+fn parse_board(board: &str) -> Result<Field, String> {
+    let err = || format!("invalid board `{board}`");
+    let mut field = Field::new();
+    let rows: Vec<&str> = board.split('/').collect();
+    if rows.len() != Field::SIZE {
+        return Err(err());
+    }
+    for (y, row) in rows.iter().enumerate() {
+        let mut x = 0;
+        let mut chars = row.chars().peekable();
+        while let Some(c) = chars.next() {
+            if let Some(empty) = c.to_digit(10) {
+                x += empty as usize;
+                continue;
+            }
+            let code: String = [Some(c), chars.next(), chars.next(), chars.next()]
+                .into_iter()
+                .collect::<Option<_>>()
+                .ok_or_else(err)?;
+            let piece = Piece::from_code(&code).map_err(|()| err())?;
+            if x >= Field::SIZE {
+                return Err(err());
+            }
+            field.put((x, y), piece).map_err(|()| err())?;
+            x += 1;
+        }
+        if x != Field::SIZE {
+            return Err(err());
+        }
+    }
+    Ok(field)
+}
+
+/// Formats the board part of a position
+pub fn format_board(field: &Field) -> String {
+    let mut rows = vec![];
+    for y in 0..Field::SIZE {
+        let mut row = String::new();
+        let mut empty = 0;
+        for x in 0..Field::SIZE {
+            if let Some(piece) = field.get((x, y)) {
+                if empty > 0 {
+                    write!(row, "{empty}").unwrap();
+                    empty = 0;
+                }
+                row.push_str(&piece.code());
+            } else {
+                empty += 1;
+            }
+        }
+        if empty > 0 {
+            write!(row, "{empty}").unwrap();
+        }
+        rows.push(row);
+    }
+    rows.join("/")
+}
+
+/// Parses a whole suite, skipping empty lines and `#` comments.
+pub fn parse_suite(s: &str) -> Result<Vec<TestPosition>, String> {
+    s.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(idx, line)| {
+            TestPosition::parse(line).map_err(|err| format!("line {}: {err}", idx + 1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::piece::Piece;
+
+    use super::{parse_suite, MovePattern, TestPosition};
+
+    #[test]
+    fn test_parse() {
+        let position = TestPosition::parse(
+            "TLRFSDRHTDRF1/4/2SLQF1/4 SLRH s bm 4,1; am *,2:TDQH; safe; dm 1; id \"row\"; c0 \"hi\";",
+        )
+        .unwrap();
+        assert!(position.game.field.square_mode());
+        assert_eq!(position.game.field.pieces().len(), 4);
+        assert_eq!(position.game.next_piece(), Piece::from_code("SLRH").ok());
+        assert_eq!(
+            position.best_moves,
+            [MovePattern {
+                x: Some(3),
+                y: Some(0),
+                next_piece: None
+            }]
+        );
+        assert_eq!(
+            position.avoid_moves,
+            [MovePattern {
+                x: None,
+                y: Some(1),
+                next_piece: Piece::from_code("TDQH").ok()
+            }]
+        );
+        assert!(position.safe);
+        assert_eq!(position.forced_win, Some(1));
+        assert_eq!(position.id(), "row");
+        assert_eq!(position.metadata[1], ("c0".to_string(), "hi".to_string()));
+
+        let reparsed = TestPosition::parse(&position.to_line()).unwrap();
+        assert_eq!(reparsed.to_line(), position.to_line());
+        assert_eq!(reparsed.game.field, position.game.field);
+    }
+
+    #[test]
+    fn test_patterns() {
+        let line = "4/4/4/4 - corners;@1,1+2,1+3,1+2,2 id \"patterns\";";
+        let position = TestPosition::parse(line).unwrap();
+        assert_eq!(position.game.field.patterns().len(), 2);
+        assert!(!position.game.field.square_mode());
+        assert_eq!(position.to_line(), line);
+        assert!(TestPosition::parse("4/4/4/4 - corners;diamonds").is_err());
+        assert!(TestPosition::parse("4/4/4/4 - corners;corners").is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let mv = MovePattern::parse("*,2:TDQH").unwrap();
+        let piece = Piece::from_code("TDQH").ok();
+        assert!(mv.matches((3, 1), piece));
+        assert!(!mv.matches((3, 2), piece));
+        assert!(!mv.matches((3, 1), None));
+        assert!(MovePattern::parse("5,1").is_err());
+        assert!(MovePattern::parse("1").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(TestPosition::parse("4/4/4 - -").is_err());
+        assert!(TestPosition::parse("5/4/4/4 - -").is_err());
+        assert!(TestPosition::parse("TLRF3/4/4/4 TLRF -").is_err());
+        assert!(TestPosition::parse("TLRF3/4/4/4 - -").is_err());
+        assert!(TestPosition::parse("TLRF3/4/4/4 SDQH x").is_err());
+        assert!(TestPosition::parse("4/4/4/4 - - bm 1;").is_err());
+        assert!(TestPosition::parse("4/4/4/4 - - dm x;").is_err());
+        let suite = parse_suite("# comment\n\n4/4/4/4 - - id \"empty\";\nfoo").unwrap_err();
+        assert!(suite.starts_with("line 4"));
+        // Suites edited on Windows may end their lines with \r\n.
+        let crlf = parse_suite("# comment\r\n4/4/4/4 - - id \"empty\";\r\n").unwrap();
+        assert_eq!(crlf[0].metadata, [("id".to_string(), "empty".to_string())]);
+    }
+}
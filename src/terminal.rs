@@ -0,0 +1,88 @@
+//! Guesses what the terminal can show, to pick rendering defaults before any flag is given. The
+//! output has no colors, so only the [glyphs](GlyphSet) depend on the terminal: emoji need a
+//! UTF-8 locale and a terminal with an emoji font, which the Linux console and dumb terminals
+//! lack. When nothing points either way, emoji are assumed.
+//!
+//! On Windows, the legacy console host draws emoji as boxes, while Windows Terminal and the
+//! terminals of editors show them. Those set variables the console host doesn't, so without any
+//! of them the console is taken to be the legacy one. Code pages don't matter: the standard
+//! library writes to the console as UTF-16, and input lines end in `\r\n`, which is trimmed with
+//! the rest of the whitespace.
+
+use std::env;
+
+use quarto::piece::GlyphSet;
+
+/// Terminals known to lack emoji, by the start of `TERM`
+const ASCII_TERMS: [&str; 4] = ["dumb", "linux", "vt100", "vt220"];
+
+/// Variables set by the Windows terminals that show emoji: Windows Terminal, `ConEmu`, and
+/// those of editors such as VS Code
+const WINDOWS_EMOJI_VARS: [&str; 4] = ["WT_SESSION", "ConEmuANSI", "TERM_PROGRAM", "TERM"];
+
+/// The glyphs the current terminal likely shows
+pub fn glyphs() -> GlyphSet {
+    detect(cfg!(windows), |name| env::var(name).ok())
+}
+
+/// The glyphs for a terminal with the environment `var`
+fn detect(windows: bool, var: impl Fn(&str) -> Option<String>) -> GlyphSet {
+    if windows && WINDOWS_EMOJI_VARS.iter().all(|name| var(name).is_none()) {
+        return GlyphSet::Ascii;
+    }
+    if var("TERM").is_some_and(|term| ASCII_TERMS.iter().any(|t| term.starts_with(t))) {
+        return GlyphSet::Ascii;
+    }
+    // The first of these that is set decides the character encoding.
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .find_map(|name| var(name).filter(|value| !value.is_empty()));
+    match locale {
+        Some(locale) if !is_utf8(&locale) => GlyphSet::Ascii,
+        _ => GlyphSet::Emoji,
+    }
+}
+
+fn is_utf8(locale: &str) -> bool {
+    let locale = locale.to_ascii_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
+#[cfg(test)]
+mod tests {
+    use quarto::piece::GlyphSet;
+
+    use super::detect;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| (*v).to_string())
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect(false, env(&[])), GlyphSet::Emoji);
+        let xterm = [("TERM", "xterm-256color"), ("LANG", "en_US.UTF-8")];
+        assert_eq!(detect(false, env(&xterm)), GlyphSet::Emoji);
+        assert_eq!(detect(false, env(&[("TERM", "linux")])), GlyphSet::Ascii);
+        assert_eq!(detect(false, env(&[("TERM", "dumb")])), GlyphSet::Ascii);
+        assert_eq!(detect(false, env(&[("LANG", "C")])), GlyphSet::Ascii);
+        assert_eq!(
+            detect(false, env(&[("LANG", "de_DE.utf8")])),
+            GlyphSet::Emoji
+        );
+        // LC_ALL overrides LANG, empty variables are skipped.
+        let c = [("LC_ALL", "C"), ("LANG", "en_US.UTF-8")];
+        assert_eq!(detect(false, env(&c)), GlyphSet::Ascii);
+        let empty = [("LC_ALL", ""), ("LANG", "en_US.UTF-8")];
+        assert_eq!(detect(false, env(&empty)), GlyphSet::Emoji);
+
+        assert_eq!(detect(true, env(&[])), GlyphSet::Ascii);
+        let windows_terminal = [("WT_SESSION", "f00"), ("LANG", "")];
+        assert_eq!(detect(true, env(&windows_terminal)), GlyphSet::Emoji);
+        assert_eq!(detect(true, env(&[("TERM", "dumb")])), GlyphSet::Ascii);
+    }
+}
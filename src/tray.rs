@@ -0,0 +1,159 @@
+//! Views of the remaining pieces, for picking the piece to give: typed instead of a piece, `safe`
+//! shows only the pieces that can't complete a line right away, `group <value>` groups them by a
+//! property, such as `group dark`, `sort` orders them by the spaces they would win on, and `all`
+//! shows them all again. Pieces keep their numbers in every view.
+
+use std::fmt::Write;
+
+use crate::{
+    game::{quick_key, Game},
+    piece::{Piece, Property},
+};
+
+/// A remaining piece with its number and the spaces it would complete a line on
+type Entry = (usize, Piece, usize);
+
+/// How the remaining pieces are listed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TrayView {
+    /// All remaining pieces
+    All,
+    /// Only the pieces that can't complete a line right away
+    Safe,
+    /// Grouped by the value of the property
+    Group(Property),
+    /// The safest first, by the spaces they would complete a line on
+    Danger,
+}
+
+impl TrayView {
+    /// Parses a view, such as `safe` or `group tall`, with the property named as in `game`.
+    pub fn parse(game: &Game, s: &str) -> Option<Self> {
+        let mut words = s.split_whitespace();
+        let view = match (words.next()?, words.next()) {
+            ("all", None) => Self::All,
+            ("safe", None) => Self::Safe,
+            ("sort", None) => Self::Danger,
+            ("group", Some(value)) => Self::Group(game.property_names.find(value)?.0),
+            _ => return None,
+        };
+        words.next().is_none().then_some(view)
+    }
+
+    /// The remaining pieces of `game` in this view, with their numbers, and for each group its
+    /// heading.
+    fn groups(self, game: &Game) -> Vec<(Option<String>, Vec<Entry>)> {
+        let mut pieces: Vec<Entry> = game
+            .danger_matrix()
+            .into_iter()
+            .enumerate()
+            .map(|(i, (piece, spaces))| (i, piece, spaces.len()))
+            .collect();
+        match self {
+            Self::All => vec![(None, pieces)],
+            Self::Safe => {
+                pieces.retain(|&(_, _, spaces)| spaces == 0);
+                vec![(None, pieces)]
+            }
+            Self::Danger => {
+                pieces.sort_by_key(|&(_, _, spaces)| spaces);
+                vec![(None, pieces)]
+            }
+            Self::Group(prop) => [true, false]
+                .into_iter()
+                .map(|set| {
+                    let heading = game.property_names.value(prop, set).to_string();
+                    let group = pieces
+                        .iter()
+                        .copied()
+                        .filter(|&(_, piece, _)| piece.get(prop) == set)
+                        .collect();
+                    (Some(heading), group)
+                })
+                .collect(),
+        }
+    }
+
+    /// Renders the remaining pieces of `game` in this view, three per row, like
+    /// [`Game::render_remaining_pieces`].
+    pub fn render(self, game: &Game) -> String {
+        let mut ret = String::new();
+        for (heading, pieces) in self.groups(game) {
+            if let Some(heading) = heading {
+                writeln!(ret, "{heading}:").unwrap();
+            }
+            if pieces.is_empty() {
+                ret.push_str("  (none)\n");
+                continue;
+            }
+            for (n, &(i, piece, spaces)) in pieces.iter().enumerate() {
+                if n > 0 && n % 3 == 0 {
+                    ret.push('\n');
+                }
+                let based_i = game.array_base.based(i);
+                if game.quick_pick {
+                    write!(ret, "  {}: ", quick_key(piece)).unwrap();
+                } else {
+                    write!(ret, "  {based_i}: ").unwrap();
+                }
+                if game.quick_pick || based_i < 10 {
+                    // padding for low numbers
+                    ret.push(' ');
+                }
+                ret.push_str(&piece.render(game.glyphs));
+                match (self, spaces) {
+                    (Self::Danger, 0) => ret.push_str(" (safe)"),
+                    (Self::Danger, 1) => ret.push_str(" (wins on 1 space)"),
+                    (Self::Danger, _) => write!(ret, " (wins on {spaces} spaces)").unwrap(),
+                    _ => {}
+                }
+                if n + 1 < pieces.len() && (n + 1) % 3 != 0 {
+                    ret.push_str(",  ");
+                }
+            }
+            ret.push('\n');
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        field::Field,
+        game::{Game, Player},
+        piece::{GlyphSet, Piece, Property},
+    };
+
+    use super::TrayView;
+
+    #[test]
+    fn test_tray_views() {
+        let field = Field::parse_grid("TLRF SDRH TDRF .\n. . . .\n. . . .\n. . . .").unwrap();
+        let mut game =
+            Game::from_position(field, Player::PlayerOne, Piece::from_code("SLQF").ok()).unwrap();
+        game.glyphs = GlyphSet::Ascii;
+        assert_eq!(TrayView::parse(&game, "safe"), Some(TrayView::Safe));
+        assert_eq!(
+            TrayView::parse(&game, "group dark"),
+            Some(TrayView::Group(Property::Light))
+        );
+        assert_eq!(TrayView::parse(&game, "group big"), None);
+        assert_eq!(TrayView::parse(&game, "safe now"), None);
+
+        // Every round piece completes the first row, on 4,1.
+        let safe = TrayView::Safe.render(&game);
+        assert_eq!(safe.lines().count(), 3);
+        assert!(!safe.contains(&Piece::from_code("SDRF").unwrap().render(GlyphSet::Ascii)));
+        let sorted = TrayView::Danger.render(&game);
+        assert!(sorted.lines().next().unwrap().contains("(safe)"));
+        assert!(sorted.lines().last().unwrap().contains("(wins on 1 space)"));
+        let grouped = TrayView::Group(Property::Tall).render(&game);
+        assert!(grouped.starts_with("tall:\n"));
+        assert!(grouped.contains("\nshort:\n"));
+        assert_eq!(
+            TrayView::All.render(&game).trim_end(),
+            game.render_remaining_pieces().trim_end()
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Game tree utilities for research (`quarto tree`): counting the positions reachable at each
+//! ply, up to symmetry, and listing the positions the game ends in.
+//!
+//! A position is the field after a number of placements, the ply. Two fields are the same up to
+//! symmetry if a rearrangement of the spaces turns one into the other and maps every winning
+//! line to a winning line, such as rotating the field, or swapping the inner and outer rows and
+//! columns. Which rearrangements qualify depends on the winning patterns of the rules.
+
+use std::collections::HashSet;
+
+use crate::{
+    field::{Field, Pos},
+    piece::Piece,
+};
+
+/// The number of spaces on the field
+const SPACES: usize = Field::SIZE * Field::SIZE;
+
+/// A rearrangement of the spaces, mapping the space `y * 4 + x` to `self[y * 4 + x]`
+pub type Symmetry = [usize; SPACES];
+
+/// The positions at one ply
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PlyCount {
+    /// The number of pieces on the field
+    pub ply: usize,
+    /// The distinct positions, up to symmetry
+    pub positions: usize,
+    /// The positions ending the game: won, or a draw with a full field
+    pub terminal: usize,
+}
+
+fn space((x, y): Pos) -> usize {
+    y * Field::SIZE + x
+}
+
+fn pos(space: usize) -> Pos {
+    (space % Field::SIZE, space / Field::SIZE)
+}
+
+/// All permutations of the rows or columns
+fn permutations() -> Vec<[usize; Field::SIZE]> {
+    (0..1 << (2 * Field::SIZE))
+        .map(|n| std::array::from_fn(|i| n >> (2 * i) & 3))
+        .filter(|perm: &[usize; Field::SIZE]| (0..Field::SIZE).all(|i| perm.contains(&i)))
+        .collect()
+}
+
+/// The symmetries of the winning lines of `field`: all ways to permute the rows and columns,
+/// and transpose the field, that map every line to a line. Always includes the identity.
+pub fn symmetries(field: &Field) -> Vec<Symmetry> {
+    let normalize = |mut line: [Pos; 4]| {
+        line.sort_unstable();
+        line
+    };
+    let lines: HashSet<[Pos; 4]> = field.lines().into_iter().map(normalize).collect();
+    let mut ret = vec![];
+    for transpose in [false, true] {
+        for rows in permutations() {
+            for cols in permutations() {
+                let map = |(x, y): Pos| {
+                    let (x, y) = (cols[x], rows[y]);
+                    if transpose {
+                        (y, x)
+                    } else {
+                        (x, y)
+                    }
+                };
+                if lines
+                    .iter()
+                    .all(|line| lines.contains(&normalize(line.map(map))))
+                {
+                    ret.push(std::array::from_fn(|i| space(map(pos(i)))));
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// The field as a number, five bits per space: 0 if empty, else the piece index plus 1
+fn key(field: &Field, symmetry: &Symmetry) -> u128 {
+    (0..SPACES).fold(0, |acc, i| {
+        let value = field.get(pos(i)).map_or(0, |piece| piece.index() + 1);
+        acc | u128::from(value) << (5 * symmetry[i])
+    })
+}
+
+/// The smallest key of all symmetric fields, the same for all of them
+pub fn canonical(field: &Field, symmetries: &[Symmetry]) -> u128 {
+    symmetries
+        .iter()
+        .map(|symmetry| key(field, symmetry))
+        .min()
+        .unwrap()
+}
+
+/// The field with the given [`key`], and the winning patterns of `rules`
+fn from_key(key: u128, rules: &Field) -> Field {
+    let mut field = rules.emptied();
+    for i in 0..SPACES {
+        #[allow(clippy::cast_possible_truncation)]
+        let value = (key >> (5 * i)) as u8 & 0x1F;
+        if value > 0 {
+            field
+                .put(pos(i), Piece::from_index(value - 1).unwrap())
+                .unwrap();
+        }
+    }
+    field
+}
+
+/// Counts the positions reachable within `depth` plies, starting with an empty field with the
+/// winning patterns of `rules`. Calls `on_terminal` for each position, up to symmetry, that
+/// ends the game.
+pub fn count(rules: &Field, depth: usize, mut on_terminal: impl FnMut(&Field)) -> Vec<PlyCount> {
+    let symmetries = symmetries(rules);
+    let mut ret = vec![];
+    let mut level = vec![rules.emptied()];
+    for ply in 1..=depth.min(SPACES) {
+        let mut next = HashSet::new();
+        for field in &level {
+            let on_field = field.pieces();
+            for piece in Piece::all().filter(|piece| !on_field.contains(piece)) {
+                for pos in field.empty_spaces() {
+                    let mut child = field.clone();
+                    child.put(pos, piece).unwrap();
+                    next.insert(canonical(&child, &symmetries));
+                }
+            }
+        }
+        let mut count = PlyCount {
+            ply,
+            positions: next.len(),
+            terminal: 0,
+        };
+        level = vec![];
+        for key in next {
+            let field = from_key(key, rules);
+            if field.check_field_for_win() || ply == SPACES {
+                count.terminal += 1;
+                on_terminal(&field);
+            } else {
+                level.push(field);
+            }
+        }
+        ret.push(count);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{field::Field, pattern::WinPattern};
+
+    use super::{canonical, count, symmetries, PlyCount};
+
+    #[test]
+    fn test_symmetries() {
+        let field = Field::new();
+        let standard = symmetries(&field);
+        assert_eq!(standard.len(), 32);
+        let a = Field::parse_grid("TLRF . . .\n. . . .\n. . . .\n. . . .").unwrap();
+        let b = Field::parse_grid(". . . .\n. . . .\n. . . .\n. . . TLRF").unwrap();
+        let c = Field::parse_grid(". . . .\n. TLRF . .\n. . . .\n. . . .").unwrap();
+        assert_eq!(canonical(&a, &standard), canonical(&b, &standard));
+        // Swapping the inner and outer rows and columns keeps all lines.
+        assert_eq!(canonical(&a, &standard), canonical(&c, &standard));
+        let d = Field::parse_grid(". TLRF . .\n. . . .\n. . . .\n. . . .").unwrap();
+        assert_ne!(canonical(&a, &standard), canonical(&d, &standard));
+
+        // The center wins only in the middle, which the inner/outer swap doesn't keep.
+        let mut center = Field::new();
+        center.add_pattern(WinPattern::by_name("center").unwrap());
+        let symmetries = symmetries(&center);
+        assert_eq!(symmetries.len(), 16);
+        assert_ne!(canonical(&a, &symmetries), canonical(&c, &symmetries));
+    }
+
+    #[test]
+    fn test_count() {
+        let mut terminal = 0;
+        let counts = count(&Field::new(), 2, |_| terminal += 1);
+        // A corner or an edge space, for each piece
+        assert_eq!(
+            counts[0],
+            PlyCount {
+                ply: 1,
+                positions: 32,
+                terminal: 0
+            }
+        );
+        assert_eq!(counts.len(), 2);
+        assert_eq!(terminal, 0);
+    }
+}
@@ -0,0 +1,330 @@
+//! The interactive tutorial (`quarto learn`), walking new players through scripted lessons.
+//!
+//! Lessons are plain text files, see `lessons/` for the built-in ones. A lesson starts with a
+//! `title:` line, followed by steps separated by lines containing only `---`. Each step consists
+//! of `key: value` lines:
+//!  * `text:` a line of explanation, may be repeated,
+//!  * `row:` four cells (piece codes or `.`) of the board, exactly four rows or none at all,
+//!  * `square_mode:` `true` if squares count as a win in this step,
+//!  * `hand:` the piece code the learner has to place,
+//!  * `task:` one of `read` (default), `place-win` or `give-safe`,
+//!  * `success:` a line shown once the task is solved, may be repeated.
+//!
+//! Lines starting with `#` are comments.
+
+use std::io::stdin;
+
+use quarto::{
+    field::{try_parse_pos, Field, RenderStyle},
+    game::ArrayBase,
+    piece::Piece,
+};
+
+/// The lessons shipped with the game, in the order they are taught
+const BUILTIN_LESSONS: [&str; 4] = [
+    include_str!("../lessons/01_winning_lines.lesson"),
+    include_str!("../lessons/02_giving_pieces.lesson"),
+    include_str!("../lessons/03_square_mode.lesson"),
+    include_str!("../lessons/04_safety.lesson"),
+];
+
+/// What the learner has to do to finish a step
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Task {
+    /// Just read the text and continue
+    Read,
+    /// Put the piece in hand on a space that wins the game
+    PlaceWin,
+    /// Pick a piece to give that does not let the opponent win immediately
+    GiveSafe,
+}
+
+#[derive(Debug, Clone)]
+pub struct Step {
+    text: Vec<String>,
+    field: Option<Field>,
+    hand: Option<Piece>,
+    task: Task,
+    success: Vec<String>,
+}
+
+impl Step {
+    fn new() -> Self {
+        Self {
+            text: vec![],
+            field: None,
+            hand: None,
+            task: Task::Read,
+            success: vec![],
+        }
+    }
+
+    /// The pieces neither on the field nor in hand
+    fn remaining_pieces(&self) -> Vec<Piece> {
+        let used = self.field.as_ref().map(Field::pieces).unwrap_or_default();
+        Piece::all()
+            .filter(|piece| !used.contains(piece) && self.hand != Some(*piece))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Lesson {
+    pub title: String,
+    steps: Vec<Step>,
+}
+
+impl Lesson {
+    /// The built-in lessons
+    pub fn builtin() -> Vec<Self> {
+        BUILTIN_LESSONS
+            .iter()
+            .map(|s| Self::parse(s).expect("built-in lessons should be valid"))
+            .collect()
+    }
+
+    /// Parses a lesson, see the [module documentation](self) for the format.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut title = None;
+        let mut steps = vec![];
+        let mut step = Step::new();
+        let mut rows = vec![];
+        let mut square_mode = false;
+
+        // A trailing separator finishes the last step.
+        for (line_idx, line) in s.lines().chain(["---"]).enumerate() {
+            let line = line.trim();
+            let line_no = line_idx + 1;
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line == "---" {
+                if !rows.is_empty() {
+                    let mut field = Field::parse_grid(&rows.join("\n"))
+                        .map_err(|()| format!("line {line_no}: invalid board"))?;
+                    field.set_square_mode(square_mode);
+                    step.field = Some(field);
+                }
+                if step.task != Task::Read && step.field.is_none() {
+                    return Err(format!("line {line_no}: this task needs a board"));
+                }
+                if (step.task == Task::PlaceWin) != step.hand.is_some() {
+                    return Err(format!(
+                        "line {line_no}: a hand piece is required for, and only for, place-win"
+                    ));
+                }
+                if !step.text.is_empty() || step.field.is_some() {
+                    steps.push(step);
+                }
+                step = Step::new();
+                rows.clear();
+                square_mode = false;
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(format!("line {line_no}: expected `key: value`"));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "title" => title = Some(value.to_string()),
+                "text" => step.text.push(value.to_string()),
+                "success" => step.success.push(value.to_string()),
+                "row" => rows.push(value.to_string()),
+                "square_mode" => {
+                    square_mode = value
+                        .parse()
+                        .map_err(|_| format!("line {line_no}: expected true or false"))?;
+                }
+                "hand" => {
+                    step.hand = Some(
+                        Piece::from_code(value)
+                            .map_err(|()| format!("line {line_no}: invalid piece code"))?,
+                    );
+                }
+                "task" => {
+                    step.task = match value {
+                        "read" => Task::Read,
+                        "place-win" => Task::PlaceWin,
+                        "give-safe" => Task::GiveSafe,
+                        _ => return Err(format!("line {line_no}: unknown task `{value}`")),
+                    }
+                }
+                key => return Err(format!("line {line_no}: unknown key `{key}`")),
+            }
+        }
+
+        let title = title.ok_or_else(|| "lesson has no title".to_string())?;
+        Ok(Self { title, steps })
+    }
+}
+
+/// Reads a line from stdin, returns `None` once stdin is closed.
+fn read_line() -> Option<String> {
+    let mut buf = String::new();
+    match stdin().read_line(&mut buf) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(buf),
+    }
+}
+
+/// Runs all given lessons, one after the other.
+pub fn run(lessons: &[Lesson], style: RenderStyle) {
+    for (i, lesson) in lessons.iter().enumerate() {
+        println!();
+        println!("Lesson {}/{}: {}", i + 1, lessons.len(), lesson.title);
+        println!();
+        for step in &lesson.steps {
+            if !run_step(step, style) {
+                return;
+            }
+        }
+    }
+    println!(
+        "You completed the tutorial, time for a real game: just run the game without `learn`."
+    );
+}
+
+/// Runs a single step, returns `false` if the learner quit.
+fn run_step(step: &Step, style: RenderStyle) -> bool {
+    for line in &step.text {
+        println!("{line}");
+    }
+    if let Some(field) = &step.field {
+        println!();
+        print!("{}", field.render(style));
+    }
+    if let Some(piece) = step.hand {
+        print!("\nYour piece: ");
+        print!("{}", piece.render(style.glyphs));
+        println!();
+    }
+
+    let solved = match step.task {
+        Task::Read => {
+            println!("\n(press enter to continue)");
+            read_line().is_some()
+        }
+        Task::PlaceWin => place_win(step, style.array_base),
+        Task::GiveSafe => give_safe(step, style),
+    };
+    if solved {
+        for line in &step.success {
+            println!("{line}");
+        }
+        println!();
+    }
+    solved
+}
+
+fn place_win(step: &Step, array_base: ArrayBase) -> bool {
+    let field = step.field.as_ref().unwrap();
+    let piece = step.hand.unwrap();
+    let winning_spaces = field.winning_spaces(piece);
+    let mut tries = 0;
+    loop {
+        println!("Select x,y to put your piece to, completing a line:");
+        let Some(buf) = read_line() else {
+            return false;
+        };
+        let pos = try_parse_pos(&buf).map(|(x, y)| (array_base.unbased(x), array_base.unbased(y)));
+        match pos {
+            Ok(pos) if winning_spaces.contains(&pos) => {
+                println!("Correct!");
+                return true;
+            }
+            Ok(pos) if field.empty_spaces().contains(&pos) => {
+                println!("That does not complete a line, try again.");
+            }
+            _ => println!("The x,y value must be an empty place on the field!"),
+        }
+        tries += 1;
+        if tries == 3 {
+            let (x, y) = winning_spaces[0];
+            println!(
+                "Hint: have a look at {},{}",
+                array_base.based(x),
+                array_base.based(y)
+            );
+        }
+    }
+}
+
+fn give_safe(step: &Step, style: RenderStyle) -> bool {
+    let array_base = style.array_base;
+    let field = step.field.as_ref().unwrap();
+    let pieces = step.remaining_pieces();
+    loop {
+        println!(
+            "\nChoose a piece to give to your opponent ({}-{}):",
+            array_base.based(0),
+            array_base.based(pieces.len() - 1)
+        );
+        for (i, piece) in pieces.iter().enumerate() {
+            print!("  {:>2}: ", array_base.based(i));
+            print!("{}", piece.render(style.glyphs));
+            if (i + 1) % 4 == 0 {
+                println!();
+            }
+        }
+        println!();
+        let Some(buf) = read_line() else {
+            return false;
+        };
+        let Some(&piece) = buf
+            .trim()
+            .parse()
+            .ok()
+            .and_then(|i| pieces.get(array_base.unbased(i)))
+        else {
+            println!("Please pick the id of one of the pieces.");
+            continue;
+        };
+        if let Some(&(x, y)) = field.winning_spaces(piece).first() {
+            print!("Careful! Your opponent would win by putting ");
+            print!("{}", piece.render(style.glyphs));
+            println!(" on {},{}.", array_base.based(x), array_base.based(y));
+        } else {
+            println!("Correct, this piece is safe to give!");
+            return true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lesson, Task};
+
+    #[test]
+    fn test_builtin_lessons_solvable() {
+        for lesson in Lesson::builtin() {
+            for step in &lesson.steps {
+                let Some(field) = &step.field else {
+                    continue;
+                };
+                match step.task {
+                    Task::Read => {}
+                    Task::PlaceWin => {
+                        assert!(!field.winning_spaces(step.hand.unwrap()).is_empty());
+                    }
+                    Task::GiveSafe => {
+                        let pieces = step.remaining_pieces();
+                        assert!(pieces.iter().any(|&p| field.winning_spaces(p).is_empty()));
+                        assert!(pieces.iter().any(|&p| !field.winning_spaces(p).is_empty()));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Lesson::parse("text: no title").is_err());
+        assert!(Lesson::parse("title: t\ntask: place-win").is_err());
+        assert!(Lesson::parse("title: t\nrow: . . . .\ntask: give-safe").is_err());
+        assert!(Lesson::parse("title: t\nfoo: bar").is_err());
+        let lesson = Lesson::parse("title: t\ntext: a\n---\ntext: b\n---\n").unwrap();
+        assert_eq!(lesson.title, "t");
+        assert_eq!(lesson.steps.len(), 2);
+    }
+}
@@ -0,0 +1,180 @@
+//! Refereeing of games played elsewhere (`quarto validate`), such as by bots: the moves are read
+//! one per line in the [notation](crate::game::Move::to_notation), and each is checked against the
+//! rules before it is made, stopping at the first illegal one.
+//!
+//! Lines may start with the move number and player, as in transcripts, such as
+//! `3. Player 1: put 2,2, give SLRF`, and end with the time the move took. Empty lines and `#`
+//! comments are skipped. Player 1 makes the first move. Coordinates count from 1, see
+//! [`FILE_BASE`].
+
+use crate::{
+    field::Field,
+    game::{ArrayBase, Game, Move, Player, Status},
+};
+
+/// Moves in files, such as transcripts and results, always count from 1, whatever the user
+/// prefers, so a file reads the same with and without `-0`.
+pub const FILE_BASE: ArrayBase = ArrayBase::One;
+
+/// A move that broke the rules, or couldn't be parsed
+#[derive(Debug, PartialEq, Eq)]
+pub struct IllegalMove {
+    /// The line of the move, counting from 1
+    pub line: usize,
+    /// Why the move is illegal
+    pub reason: String,
+}
+
+/// The move on a line, without the move number or time, or `None` if there is no move
+fn notation(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    // The notation itself has no colons.
+    let mv = line.rsplit_once(':').map_or(line, |(_, mv)| mv);
+    let mv = match mv.rsplit_once(" (") {
+        Some((mv, time)) if time.ends_with("s)") => mv,
+        _ => mv,
+    };
+    Some(mv.trim())
+}
+
+/// Why `mv` can't be made in `game`
+pub fn reason(game: &Game, mv: Move, array_base: ArrayBase) -> String {
+    let expected = match game.status {
+        Status::InitialMove { .. } => "the first piece to give, such as `give SDQH`",
+        Status::SwapDecision { .. } => "whether to swap sides, `swap` or `keep`",
+        Status::Move { .. } => "a placement, such as `put 1,1, give SDQH`",
+        Status::Won { .. } | Status::Draw { .. } => return "the game is over".to_string(),
+    };
+    let based = |(x, y): (usize, usize)| (array_base.based(x), array_base.based(y));
+    match (mv, &game.status) {
+        (Move::Place { pos, .. }, Status::Move { .. }) if game.field.get(pos).is_some() => {
+            let (x, y) = based(pos);
+            format!("the space {x},{y} is taken")
+        }
+        (
+            Move::Place {
+                next_piece: None, ..
+            },
+            Status::Move { .. },
+        ) => "the game goes on, a piece has to be given".to_string(),
+        (Move::Initial { next_piece }, Status::InitialMove { .. })
+        | (
+            Move::Place {
+                next_piece: Some(next_piece),
+                ..
+            },
+            Status::Move { .. },
+        ) => format!("the piece {} is not available", next_piece.code()),
+        _ => format!("expected {expected}"),
+    }
+}
+
+/// Makes the moves on `lines` in a game with the rules of `base`, calling `on_move` with each
+/// legal move and the game after it. Returns the game after the last move, or the first illegal
+/// one.
+pub fn validate(
+    base: &Game,
+    lines: impl IntoIterator<Item = String>,
+    mut on_move: impl FnMut(Move, &Game),
+) -> Result<Game, IllegalMove> {
+    let mut game = base.rematch(Player::PlayerOne);
+    for (idx, line) in lines.into_iter().enumerate() {
+        let Some(notation) = notation(&line) else {
+            continue;
+        };
+        let illegal = |reason| IllegalMove {
+            line: idx + 1,
+            reason,
+        };
+        let mv = Move::from_notation(notation, FILE_BASE).map_err(illegal)?;
+        if game.make_move(mv).is_err() {
+            return Err(illegal(reason(&game, mv, FILE_BASE)));
+        }
+        on_move(mv, &game);
+    }
+    Ok(game)
+}
+
+/// The status of a validated game, such as `Player 2 to place SDQH`
+pub fn describe(game: &Game) -> String {
+    match game.status {
+        Status::InitialMove { starting_player } => format!("{starting_player} to give a piece"),
+        Status::SwapDecision { next_player, .. } => format!("{next_player} to decide the swap"),
+        Status::Move {
+            next_player,
+            next_piece,
+        } => format!("{next_player} to place {}", next_piece.code()),
+        Status::Won { winner } => format!("{winner} won"),
+        Status::Draw { .. } if game.field.pieces().len() == Field::SIZE * Field::SIZE => {
+            "draw".to_string()
+        }
+        Status::Draw { .. } => "draw, no line can be completed anymore".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, Player};
+
+    use super::{describe, notation, validate, IllegalMove};
+
+    fn lines(s: &str) -> Vec<String> {
+        s.lines().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_notation() {
+        assert_eq!(notation("give SDQH"), Some("give SDQH"));
+        assert_eq!(
+            notation("2. Player 2 (bot): put 1,1, give TDQH (0.4s)"),
+            Some("put 1,1, give TDQH")
+        );
+        assert_eq!(notation("  # comment"), None);
+        assert_eq!(notation(""), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let base = Game::new(Player::PlayerOne);
+        let mut moves = 0;
+        let game = validate(
+            &base,
+            lines("# a game\ngive SDQH\n\nput 1,1, give TDQH\n"),
+            |_, _| moves += 1,
+        )
+        .unwrap();
+        assert_eq!(moves, 2);
+        assert_eq!(describe(&game), "Player 1 to place TDQH");
+        let won = validate(
+            &base,
+            lines("give SDQH\nput 1,1, give TDQH\nput 2,1, give SDRH\nput 3,1, give TDRH\nput 4,1"),
+            |_, _| {},
+        );
+        assert_eq!(describe(&won.unwrap()), "Player 1 won");
+
+        let illegal = |s| validate(&base, lines(s), |_, _| {}).unwrap_err();
+        assert_eq!(
+            illegal("give SDQH\nput 1,1, give TDQH\nput 1,1, give SDRH"),
+            IllegalMove {
+                line: 3,
+                reason: "the space 1,1 is taken".to_string()
+            }
+        );
+        assert_eq!(
+            illegal("give SDQH\nput 1,1, give SDQH").reason,
+            "the piece SDQH is not available"
+        );
+        assert_eq!(
+            illegal("give SDQH\nput 1,1").reason,
+            "the game goes on, a piece has to be given"
+        );
+        assert_eq!(
+            illegal("put 1,1, give SDQH").reason,
+            "expected the first piece to give, such as `give SDQH`"
+        );
+        assert_eq!(illegal("give SDQH\npass").line, 2);
+    }
+}
@@ -0,0 +1,417 @@
+//! Variation trees, for annotating games and trying out other moves: the moves played from the
+//! start of a game, the main line, with variations branching off any move, and each move
+//! optionally annotated. Every node of the tree is the game after the moves leading to it, its
+//! [history](Game::history) is the line from the root.
+//!
+//! In the notation, moves are separated by `; ` as in [results](crate::results), an annotation
+//! follows its move in braces, and variations follow the move of the main line they replace in
+//! parentheses, such as
+//! `give SDRH; put 1,1, give TLRF {the usual} (put 2,2, give TLRF); put 1,2, give SLQH`.
+//! Coordinates count from 1, see [`FILE_BASE`].
+
+use std::fmt::Write;
+
+use crate::{
+    game::{Game, Move},
+    validate::FILE_BASE,
+};
+
+/// A node of a [`VariationTree`], the position after a move
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+#[derive(Debug, Clone)]
+struct Node {
+    /// The move leading here, `None` for the root
+    mv: Option<Move>,
+    parent: Option<NodeId>,
+    /// The moves played from here, the main line first
+    children: Vec<NodeId>,
+    annotation: String,
+    game: Game,
+}
+
+/// A game with variations, see the [module](self)
+#[derive(Debug, Clone)]
+pub struct VariationTree {
+    nodes: Vec<Node>,
+    current: NodeId,
+}
+
+impl VariationTree {
+    /// The tree of `game`: its history is the main line, and the current node its end
+    pub fn new(game: &Game) -> Self {
+        let mut root = game.clone();
+        root.rollback_to(0).unwrap();
+        let mut ret = Self {
+            nodes: vec![Node {
+                mv: None,
+                parent: None,
+                children: vec![],
+                annotation: String::new(),
+                game: root,
+            }],
+            current: NodeId(0),
+        };
+        for &mv in game.history() {
+            ret.play(mv).unwrap();
+        }
+        ret
+    }
+
+    fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+
+    /// The start of the game, before any move
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// The node the game is at
+    pub fn current(&self) -> NodeId {
+        self.current
+    }
+
+    /// The game at the current node
+    pub fn game(&self) -> &Game {
+        self.game_at(self.current)
+    }
+
+    /// The game at `id`, after the moves from the root leading to it
+    pub fn game_at(&self, id: NodeId) -> &Game {
+        &self.node(id).game
+    }
+
+    /// The move leading to `id`, `None` for the root
+    pub fn move_at(&self, id: NodeId) -> Option<Move> {
+        self.node(id).mv
+    }
+
+    /// The node before the move leading to `id`, `None` for the root
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent
+    }
+
+    /// The moves played from `id`, the main line first
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.node(id).children
+    }
+
+    /// The moves played instead of the move leading to `id`, itself included, the main line
+    /// first. None for the root.
+    pub fn siblings(&self, id: NodeId) -> &[NodeId] {
+        self.parent(id).map_or(&[], |parent| self.children(parent))
+    }
+
+    /// The end of the main line
+    pub fn main_line_end(&self) -> NodeId {
+        let mut id = self.root();
+        while let Some(&main) = self.children(id).first() {
+            id = main;
+        }
+        id
+    }
+
+    /// Makes a move from the current node and goes there. A move played before is reused, any
+    /// other starts a variation, or continues the line if it is the first move from here. Fails
+    /// if the move is illegal.
+    pub fn play(&mut self, mv: Move) -> Result<NodeId, ()> {
+        let id = self.add(self.current, mv)?;
+        self.current = id;
+        Ok(id)
+    }
+
+    /// The node after `mv` at `parent`, added unless it is there already
+    fn add(&mut self, parent: NodeId, mv: Move) -> Result<NodeId, ()> {
+        if let Some(&id) = self
+            .children(parent)
+            .iter()
+            .find(|&&id| self.move_at(id) == Some(mv))
+        {
+            return Ok(id);
+        }
+        let mut game = self.game_at(parent).clone();
+        game.make_move(mv)?;
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            mv: Some(mv),
+            parent: Some(parent),
+            children: vec![],
+            annotation: String::new(),
+            game,
+        });
+        self.nodes[parent.0].children.push(id);
+        Ok(id)
+    }
+
+    /// Goes to `id`
+    pub fn goto(&mut self, id: NodeId) {
+        self.current = id;
+    }
+
+    /// Takes back the move leading to the current node. Fails at the root.
+    pub fn back(&mut self) -> Result<(), ()> {
+        self.current = self.parent(self.current).ok_or(())?;
+        Ok(())
+    }
+
+    /// Goes on along the main line from the current node. Fails if no move was played from it.
+    pub fn forward(&mut self) -> Result<(), ()> {
+        self.current = *self.children(self.current).first().ok_or(())?;
+        Ok(())
+    }
+
+    /// The annotation of the move leading to `id`, or of the game for the root
+    pub fn annotation(&self, id: NodeId) -> Option<&str> {
+        Some(self.node(id).annotation.as_str()).filter(|text| !text.is_empty())
+    }
+
+    /// Annotates the move leading to `id`, an empty text removes the annotation. Fails if the
+    /// text has braces, which end annotations in the notation.
+    pub fn annotate(&mut self, id: NodeId, text: &str) -> Result<(), ()> {
+        if text.contains(['{', '}']) {
+            return Err(());
+        }
+        self.nodes[id.0].annotation = text.trim().to_string();
+        Ok(())
+    }
+
+    /// Makes the line leading to `id` the main line, moving each of its moves to the front of
+    /// its siblings
+    pub fn promote(&mut self, id: NodeId) {
+        let mut id = id;
+        while let Some(parent) = self.parent(id) {
+            let children = &mut self.nodes[parent.0].children;
+            let idx = children.iter().position(|&child| child == id).unwrap();
+            children[..=idx].rotate_right(1);
+            id = parent;
+        }
+    }
+
+    /// The move leading to `id` in the notation, with its annotation
+    fn move_notation(&self, id: NodeId) -> String {
+        let mv = self.move_at(id).unwrap().to_notation(FILE_BASE);
+        match self.annotation(id) {
+            Some(text) => format!("{mv} {{{text}}}"),
+            None => mv,
+        }
+    }
+
+    /// The main line from `id` on, with its variations
+    fn line_notation(&self, id: NodeId) -> String {
+        let mut moves = vec![];
+        let mut id = id;
+        while let Some((&main, variations)) = self.children(id).split_first() {
+            let mut mv = self.move_notation(main);
+            for &variation in variations {
+                let mut line = vec![self.move_notation(variation)];
+                line.extend(Some(self.line_notation(variation)).filter(|rest| !rest.is_empty()));
+                write!(mv, " ({})", line.join("; ")).unwrap();
+            }
+            moves.push(mv);
+            id = main;
+        }
+        moves.join("; ")
+    }
+
+    /// The whole tree in the [notation](self), with the annotation of the game first
+    pub fn to_notation(&self) -> String {
+        let line = self.line_notation(self.root());
+        match self.annotation(self.root()) {
+            Some(text) if line.is_empty() => format!("{{{text}}}"),
+            Some(text) => format!("{{{text}}} {line}"),
+            None => line,
+        }
+    }
+
+    /// Parses a tree in the [notation](self), played from the start of `game`, which may be a
+    /// set-up position. The current node is the end of the main line.
+    pub fn parse(game: &Game, s: &str) -> Result<Self, String> {
+        let mut root = game.clone();
+        root.rollback_to(0).unwrap();
+        let mut ret = Self::new(&root);
+        let mut unparsed = s.trim_start();
+        if let Some(text) = annotation(&mut unparsed)? {
+            ret.annotate(ret.root(), text).unwrap();
+        }
+        if !unparsed.is_empty() {
+            ret.parse_line(&mut unparsed, ret.root())?;
+        }
+        if !unparsed.is_empty() {
+            return Err(format!("unexpected `{unparsed}`"));
+        }
+        ret.current = ret.main_line_end();
+        Ok(ret)
+    }
+
+    /// Parses the moves of a line played from `id`, up to the end of the line
+    fn parse_line(&mut self, rest: &mut &str, id: NodeId) -> Result<(), String> {
+        let mut id = id;
+        loop {
+            let end = rest.find([';', '(', ')', '{', '}']).unwrap_or(rest.len());
+            let notation = rest[..end].trim();
+            *rest = &rest[end..];
+            let mv = Move::from_notation(notation, FILE_BASE)?;
+            let parent = id;
+            id = self
+                .add(parent, mv)
+                .map_err(|()| format!("illegal move `{notation}`"))?;
+            if let Some(text) = annotation(rest)? {
+                self.annotate(id, text).unwrap();
+            }
+            while let Some(variation) = rest.trim_start().strip_prefix('(') {
+                *rest = variation;
+                self.parse_line(rest, parent)?;
+                *rest = rest
+                    .trim_start()
+                    .strip_prefix(')')
+                    .ok_or("unclosed variation")?;
+            }
+            let Some(next) = rest.trim_start().strip_prefix(';') else {
+                *rest = rest.trim_start();
+                return Ok(());
+            };
+            *rest = next;
+        }
+    }
+}
+
+/// Parses the annotation at the start of `rest`, if there is one
+fn annotation<'a>(rest: &mut &'a str) -> Result<Option<&'a str>, String> {
+    let Some(text) = rest.trim_start().strip_prefix('{') else {
+        return Ok(None);
+    };
+    let (text, after) = text.split_once('}').ok_or("unclosed annotation")?;
+    *rest = after.trim_start();
+    Ok(Some(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        field::Field,
+        game::{Game, Move, Player},
+        piece::Piece,
+        validate::FILE_BASE,
+    };
+
+    use super::VariationTree;
+
+    fn game(moves: &str) -> Game {
+        let mut game = Game::new(Player::PlayerOne);
+        for mv in moves.split(';') {
+            game.make_move(Move::from_notation(mv, FILE_BASE).unwrap())
+                .unwrap();
+        }
+        game
+    }
+
+    fn mv(notation: &str) -> Move {
+        Move::from_notation(notation, FILE_BASE).unwrap()
+    }
+
+    const MAIN_LINE: &str = "give SDRH; put 1,1, give TLRF; put 2,2, give SLQH";
+
+    #[test]
+    fn test_variations() {
+        let game = game(MAIN_LINE);
+        let mut tree = VariationTree::new(&game);
+        assert_eq!(tree.game().history(), game.history());
+        assert_eq!(tree.current(), tree.main_line_end());
+
+        // A variation for the second placement
+        tree.back().unwrap();
+        let branch = tree.current();
+        let variation = tree.play(mv("put 4,4, give SLQH")).unwrap();
+        tree.play(mv("put 1,4, give TDQF")).unwrap();
+        assert_eq!(tree.siblings(variation).len(), 2);
+        assert_eq!(tree.siblings(variation)[1], variation);
+        assert_eq!(tree.parent(variation), Some(branch));
+        assert_eq!(tree.game_at(tree.main_line_end()).history(), game.history());
+        // Each node is the game after its line, as if played from the start.
+        let mut replayed = game.clone();
+        replayed.rollback_to(2).unwrap();
+        for &mv in &tree.game().history()[2..] {
+            replayed.make_move(mv).unwrap();
+        }
+        assert_eq!(tree.game().field, replayed.field);
+        assert_eq!(tree.game().status, replayed.status);
+
+        // Playing a move again reuses it.
+        tree.goto(branch);
+        assert_eq!(tree.play(mv("put 4,4, give SLQH")), Ok(variation));
+        assert!(tree.play(mv("put 4,4, give SLQH")).is_err());
+        assert_eq!(tree.children(branch).len(), 2);
+
+        tree.promote(variation);
+        assert_eq!(tree.children(branch)[0], variation);
+        assert_eq!(tree.game_at(tree.main_line_end()).history().len(), 4);
+        tree.goto(tree.root());
+        assert!(tree.back().is_err());
+        tree.forward().unwrap();
+        assert_eq!(tree.game().history(), &game.history()[..1]);
+    }
+
+    #[test]
+    fn test_annotations() {
+        let mut tree = VariationTree::new(&game(MAIN_LINE));
+        let last = tree.current();
+        assert_eq!(tree.annotation(last), None);
+        tree.annotate(last, "opens the diagonal").unwrap();
+        assert_eq!(tree.annotation(last), Some("opens the diagonal"));
+        assert!(tree.annotate(last, "a {brace}").is_err());
+        tree.annotate(last, "").unwrap();
+        assert_eq!(tree.annotation(last), None);
+    }
+
+    #[test]
+    fn test_notation() {
+        let game = game(MAIN_LINE);
+        let mut tree = VariationTree::new(&game);
+        tree.annotate(tree.root(), "a short game").unwrap();
+        tree.annotate(tree.current(), "threatens nothing yet")
+            .unwrap();
+        tree.back().unwrap();
+        tree.play(mv("put 4,4, give SLQH")).unwrap();
+        tree.play(mv("put 1,4")).unwrap_err();
+        tree.play(mv("put 1,4, give TDQF")).unwrap();
+        tree.back().unwrap();
+        tree.back().unwrap();
+        tree.play(mv("put 3,3, give SLQH")).unwrap();
+        let notation = tree.to_notation();
+        assert_eq!(
+            notation,
+            "{a short game} give SDRH; put 1,1, give TLRF; put 2,2, give SLQH \
+             {threatens nothing yet} (put 4,4, give SLQH; put 1,4, give TDQF) \
+             (put 3,3, give SLQH)"
+        );
+        let parsed = VariationTree::parse(&game, &notation).unwrap();
+        assert_eq!(parsed.to_notation(), notation);
+        assert_eq!(parsed.game().history(), game.history());
+        assert_eq!(VariationTree::parse(&game, "").unwrap().nodes.len(), 1);
+
+        for wrong in [
+            "give SDRH; put 1,1, give SDRH",
+            "give SDRH; put 1,1, give TLRF (put 2,2, give TLRF",
+            "give SDRH {unclosed",
+            "give SDRH) put 1,1",
+        ] {
+            assert!(VariationTree::parse(&game, wrong).is_err(), "{wrong}");
+        }
+    }
+
+    #[test]
+    fn test_setup_root() {
+        let mut field = Field::new();
+        field.put((0, 0), Piece::from_index(0).unwrap()).unwrap();
+        let mut game =
+            Game::from_position(field, Player::PlayerOne, Piece::from_index(1).ok()).unwrap();
+        game.make_move(mv("put 2,2, give TLRF")).unwrap();
+        let tree = VariationTree::new(&game);
+        assert_eq!(tree.game_at(tree.root()).field.pieces().len(), 1);
+        assert_eq!(tree.game().field, game.field);
+        let parsed = VariationTree::parse(&game, &tree.to_notation()).unwrap();
+        assert_eq!(parsed.game().field, game.field);
+    }
+}
@@ -0,0 +1,35 @@
+//! Versions of the formats written to disk or shared: profiles and autosaves, kept by the
+//! `quarto` binary, and [share codes](crate::share). Each format has its own version,
+//! increased on incompatible changes. Older versions are migrated when read, newer ones are
+//! refused with an error, as they were written by a newer release.
+//!
+//! Version 0 is for files written before their format was versioned.
+
+/// Checks that `version` of the format `what` can be read, supporting up to `supported`.
+pub fn check(what: &str, version: u32, supported: u32) -> Result<(), String> {
+    if version > supported {
+        Err(format!(
+            "{what} format version {version} is newer than supported (up to {supported}), please update quarto"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+
+    #[test]
+    fn test_check() {
+        assert!(check("profile", 0, 1).is_ok());
+        assert!(check("profile", 1, 1).is_ok());
+        assert_eq!(
+            check("profile", 2, 1),
+            Err(
+                "profile format version 2 is newer than supported (up to 1), please update quarto"
+                    .to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! A game with what frontends show about it cached, for frontends that redraw often: the pieces
+//! still to be placed, the threatened and dead lines, the danger matrix and the empty spaces are
+//! computed once per position, on first use. The game is only changed through the view, which
+//! drops the cache whenever a move is made or taken back.
+
+use std::cell::OnceCell;
+
+use crate::{
+    field::Pos,
+    game::{Game, Move},
+    piece::Piece,
+};
+
+/// The cached data of one position, each computed on first use
+#[derive(Debug, Clone, Default)]
+struct Derived {
+    pool: OnceCell<Vec<Piece>>,
+    threatened_lines: OnceCell<Vec<[Pos; 4]>>,
+    dead_lines: OnceCell<Vec<[Pos; 4]>>,
+    danger_matrix: OnceCell<Vec<(Piece, Vec<Pos>)>>,
+    empty_spaces: OnceCell<Vec<Pos>>,
+}
+
+/// A game, with the data derived from its position cached until the next move
+#[derive(Debug, Clone)]
+pub struct GameView {
+    game: Game,
+    derived: Derived,
+}
+
+impl From<Game> for GameView {
+    fn from(game: Game) -> Self {
+        Self {
+            game,
+            derived: Derived::default(),
+        }
+    }
+}
+
+impl GameView {
+    /// The game viewed
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// The game, to change it in other ways than moves. The cache is dropped, the game may
+    /// change.
+    pub fn game_mut(&mut self) -> &mut Game {
+        self.derived = Derived::default();
+        &mut self.game
+    }
+
+    /// The game viewed, without the cache
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    /// Makes a move, see [`Game::make_move`]. The cache is kept if the move is illegal, as the
+    /// game stays the same.
+    pub fn make_move(&mut self, mv: Move) -> Result<(), ()> {
+        self.game.make_move(mv)?;
+        self.derived = Derived::default();
+        Ok(())
+    }
+
+    /// Takes the game back, see [`Game::rollback_to`]. The cache is kept if it fails, as the game
+    /// stays the same.
+    pub fn rollback_to(&mut self, ply: usize) -> Result<(), ()> {
+        self.game.rollback_to(ply)?;
+        self.derived = Derived::default();
+        Ok(())
+    }
+
+    /// The pieces not on the field yet: the [remaining ones](Game::remaining_pieces), and the
+    /// piece to place
+    pub fn pool(&self) -> &[Piece] {
+        self.derived.pool.get_or_init(|| {
+            let mut pool = self.game.remaining_pieces().to_vec();
+            pool.extend(self.game.next_piece());
+            pool
+        })
+    }
+
+    /// The [threatened lines](crate::field::Field::threatened_lines)
+    pub fn threatened_lines(&self) -> &[[Pos; 4]] {
+        self.derived
+            .threatened_lines
+            .get_or_init(|| self.game.field.threatened_lines())
+    }
+
+    /// The [lines](crate::field::Field::dead_lines) no piece of the [pool](GameView::pool) can
+    /// complete anymore
+    pub fn dead_lines(&self) -> &[[Pos; 4]] {
+        self.derived
+            .dead_lines
+            .get_or_init(|| self.game.field.dead_lines(self.pool()))
+    }
+
+    /// The [danger matrix](Game::danger_matrix)
+    pub fn danger_matrix(&self) -> &[(Piece, Vec<Pos>)] {
+        self.derived
+            .danger_matrix
+            .get_or_init(|| self.game.danger_matrix())
+    }
+
+    /// The [empty spaces](crate::field::Field::empty_spaces)
+    pub fn empty_spaces(&self) -> &[Pos] {
+        self.derived
+            .empty_spaces
+            .get_or_init(|| self.game.field.empty_spaces())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        game::{Game, Move, Player},
+        piece::Piece,
+    };
+
+    use super::GameView;
+
+    /// Checks every cached value of `view`, filling the cache, against computing it afresh
+    fn assert_fresh(view: &GameView) {
+        let game = view.game();
+        let mut pool = game.remaining_pieces().to_vec();
+        pool.extend(game.next_piece());
+        assert_eq!(view.pool(), pool);
+        assert_eq!(view.threatened_lines(), game.field.threatened_lines());
+        assert_eq!(view.dead_lines(), game.field.dead_lines(&pool));
+        assert_eq!(view.danger_matrix(), game.danger_matrix());
+        assert_eq!(view.empty_spaces(), game.field.empty_spaces());
+    }
+
+    #[test]
+    fn test_view_invalidation() {
+        let mut game = Game::new(Player::PlayerOne);
+        game.pie_rule = true;
+        let mut view = GameView::from(game);
+        assert_fresh(&view);
+        view.make_move(Move::Initial {
+            next_piece: Piece::from_index(3).unwrap(),
+        })
+        .unwrap();
+        assert_fresh(&view);
+        // Placing on the first empty space, giving the last piece, until the game ends
+        while view.game().running() {
+            let mv = if view.game().is_swap_decision() {
+                Move::Swap { accepted: true }
+            } else {
+                Move::Place {
+                    pos: view.empty_spaces()[0],
+                    next_piece: view.game().remaining_pieces().last().copied(),
+                }
+            };
+            // An illegal move changes nothing, the cache stays right.
+            assert!(view
+                .make_move(Move::Initial {
+                    next_piece: Piece::from_index(0).unwrap(),
+                })
+                .is_err());
+            assert_fresh(&view);
+            view.make_move(mv).unwrap();
+            assert_fresh(&view);
+        }
+        let plies = view.game().history().len();
+        assert!(view.rollback_to(plies + 1).is_err());
+        assert_fresh(&view);
+        for ply in [plies - 1, 8, 2, 0] {
+            view.rollback_to(ply).unwrap();
+            assert_fresh(&view);
+        }
+        view.game_mut()
+            .field
+            .put((0, 0), Piece::from_index(5).unwrap())
+            .unwrap();
+        assert_fresh(&view);
+    }
+}